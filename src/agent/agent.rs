@@ -1,9 +1,13 @@
+use std::sync::Arc;
+
+use futures::future::join_all;
+use governor::DefaultDirectRateLimiter;
 use rig::{
     OneOrMany,
-    completion::{self, Completion, PromptError},
+    completion::{self, Completion, CompletionError, PromptError},
     extractor::{Extractor, ExtractorBuilder},
-    message::{AssistantContent, Message, ToolCall, ToolFunction, ToolResultContent, UserContent},
-    tool::Tool,
+    message::{AssistantContent, Message, ToolCall, ToolResultContent, UserContent},
+    tool::{Tool, ToolSetError},
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -11,55 +15,69 @@ use serde::{Deserialize, Serialize};
 use tracing::info;
 
 use crate::{
-    agent::tool_bgm_tv::BgmTVSearchTool,
+    agent::budget::{
+        BudgetExceeded, MatchBudget, TokenUsage, UsageReader, estimate_message_tokens,
+    },
+    agent::known_ids::KnownIds,
+    agent::prompt::RenderedPrompt,
+    agent::tool_bgm_tv::{
+        BgmTVEpisodesTool, BgmTVRelationsTool, BgmTVSearchTool, BgmTVSubjectTool,
+    },
+    agent::tool_known_ids::KnownIdsTool,
     agent::tool_submit::{SubmitBGMTool, SubmitTool},
-    agent::tool_tmdb::{TMDBMovieSearchTool, TMDBSearchTool, TMDBSeasonTool},
+    agent::tool_tmdb::{
+        TMDBAlternativeTitlesTool, TMDBMovieSearchTool, TMDBSearchTool, TMDBSeasonTool,
+    },
+    agent::transcript::ToolCallRecord,
 };
 
-pub static MATCH_BGM_PROMPT: &str = r#"You are an intelligent assistant responsible for matching anime information on Bangumi based on user queries.
-Your goal is to identify the single most relevant anime entry.
-
-1.  **Analyze User Query**: Identify potential anime titles (jp, romaji, English, etc.) and other relevant keywords provided by the user.
-2.  **Primary Search**: prioritizing the most promising keyword(s) for the search (usually the jp title, if available).
-3.  **Evaluate Results**: Examine the search results. If a highly relevant match is found based on the title and other available information (from the search tool's return data), proceed to step 5.
-4.  **Refine Search (If Necessary)**: If the initial search results are ambiguous or low quality, you may try searching again using alternative titles (e.g., romaji, English) or extracted keywords. **Only perform additional searches if the first attempt failed to yield a likely match.**
-5.  **Select Confident Match**: Evaluate the similarity between the user query and each search result (considering titles, aliases, air dates, etc.). Select the entry with the **highest similarity**, **but only if this similarity meets a high confidence threshold**. 
-6.  **Submit Result**: if found confident match, submit the matched id and name, and confidence-score, otherwise submit empty result.
-"#;
-
-pub static MATCH_TMDB_PROMPT: &str = r#"You are an intelligent assistant responsible for matching anime information on TMDB based on user queries, including identifying the correct season(TV show Only).
-Your goal is to identify the single most relevant anime entry and its specific season.
-You can process the anime TVshow or movie.
-
-1.  **Analyze User Query**: Identify potential anime titles (jp, romaji, English, etc.). **Critically, extract the *main title* of the anime, separating it from any season-specific identifiers or subtitles (e.g., "Season 2", "Part 3", "Arc X"). Identify these season identifiers and other relevant keywords separately.**
-2.  **Primary Search**: Construct a search query prioritizing the most promising *extracted main title* (usually the jp title, if available). **Do NOT include the identified season identifiers or subtitles (like "Season 2", "第二季") in this initial search query.**
-3.  **Evaluate Search Results**: Calculate the confidence score of the each search result(considering the title, air date, overview, etc.).  If no promising TV show match is found, proceed to step 8.
-4.  **Fetch Season Information**: with the TMDB ID of the most likely TV show match identified in the previous step. This tool will return a list of seasons with their names, numbers, and potentially air dates.
-5.  **Match Season**: Compare the season information obtained with the season details mentioned or implied in the user query. Identify the single season that best matches the user's request. Consider season numbers, names, or potentially air dates if provided.
-6.  **Refine Search (If Necessary)**: If the initial search results are ambiguous or low quality, you may try searching again using alternative titles (e.g., romaji, English) or extracted keywords. **Only perform additional searches if the first attempt failed to yield a likely match.**
-7.  **Select Confident Match**: Based on the TV show match (Step 3) and the specific season match (Step 5), confirm if this combination represents a high-confidence match for the user's query. 
-8.  **Submit Result**: if found confident match, submit the matched tv_id and name and season number, and confidence-score, otherwise submit empty result.
-"#;
-
-pub static EXTRACT_BGM_MATCH_RESULT_PROMPT: &str = r#"extract the id and name from the input text"#;
-pub static EXTRACT_TMDB_MATCH_RESULT_PROMPT: &str =
-    r#"extract the id and name and season from the input text"#;
-
 pub struct AnimeMatcherAgent<M: rig::completion::CompletionModel> {
     agent: MultiTurnAgent<M>,
     extractor: Extractor<M, MatchResult>,
 }
 
 impl<M: rig::completion::CompletionModel> AnimeMatcherAgent<M> {
+    pub fn with_budget(mut self, budget: MatchBudget) -> Self {
+        self.agent.budget = budget;
+        self
+    }
+
+    /// 每次请求模型前按 provider 的频率限制等待
+    pub fn with_rate_limit(mut self, limiter: Option<Arc<DefaultDirectRateLimiter>>) -> Self {
+        self.agent.rate_limiter = limiter;
+        self
+    }
+
+    /// 从原始响应中读取 provider 报告的 token 消耗
+    pub fn with_usage_reader(mut self, reader: UsageReader<M::Response>) -> Self {
+        self.agent.usage_reader = reader;
+        self
+    }
+
+    /// 本次匹配累计消耗的 token，provider 未报告的轮次按估算值计算
+    pub fn usage(&self) -> TokenUsage {
+        self.agent.usage
+    }
+
+    /// 本次匹配中的所有工具调用
+    pub fn tool_calls(&self) -> &[ToolCallRecord] {
+        &self.agent.tool_records
+    }
+
+    /// 模型最终输出的原始文本
+    pub fn output(&self) -> Option<&str> {
+        self.agent.output.as_deref()
+    }
+
     pub async fn match_anime(&mut self, query: &str) -> anyhow::Result<MatchResult> {
         let result = self.agent.multi_turn_prompt(query).await?;
 
         info!("模型输出: {}", result);
         match serde_json::from_str::<MatchResult>(&result) {
-            Ok(result) => Ok(result),
+            Ok(result) => Ok(result.normalize()),
             Err(_) => {
                 let extract_result = self.extractor.extract(&result).await?;
-                Ok(extract_result)
+                Ok(extract_result.normalize())
             }
         }
     }
@@ -68,19 +86,23 @@ impl<M: rig::completion::CompletionModel> AnimeMatcherAgent<M> {
 pub fn new_mapping_bgm_tv_agent<M: rig::completion::CompletionModel>(
     agent: rig::agent::AgentBuilder<M>,
     extractor: ExtractorBuilder<MatchResult, M>,
+    prompt: &RenderedPrompt,
+    known_ids: KnownIds,
+    rejected_ids: Vec<String>,
 ) -> AnimeMatcherAgent<M> {
     let agent = agent
-        .preamble(MATCH_BGM_PROMPT)
+        .preamble(&prompt.preamble)
         .max_tokens(8192)
         .temperature(0.2)
-        .tool(BgmTVSearchTool::new())
+        .tool(BgmTVSearchTool::new().with_rejected_ids(rejected_ids))
+        .tool(BgmTVSubjectTool::new())
+        .tool(BgmTVEpisodesTool::new())
+        .tool(BgmTVRelationsTool::new())
+        .tool(KnownIdsTool::new(known_ids))
         .tool(SubmitBGMTool {});
-    let multi_agent = MultiTurnAgent {
-        agent: agent.build(),
-        chat_history: Vec::new(),
-    };
+    let multi_agent = MultiTurnAgent::new(agent.build());
 
-    let extractor = extractor.preamble(EXTRACT_BGM_MATCH_RESULT_PROMPT).build();
+    let extractor = extractor.preamble(&prompt.extractor).build();
 
     AnimeMatcherAgent {
         agent: multi_agent,
@@ -91,22 +113,24 @@ pub fn new_mapping_bgm_tv_agent<M: rig::completion::CompletionModel>(
 pub fn new_mapping_tmdb_agent<M: rig::completion::CompletionModel>(
     agent: rig::agent::AgentBuilder<M>,
     extractor: ExtractorBuilder<MatchResult, M>,
+    prompt: &RenderedPrompt,
+    known_ids: KnownIds,
+    rejected_ids: Vec<String>,
 ) -> AnimeMatcherAgent<M> {
     let agent = agent
-        .preamble(MATCH_TMDB_PROMPT)
+        .preamble(&prompt.preamble)
         .max_tokens(8192)
         .temperature(0.2)
-        .tool(TMDBMovieSearchTool::new())
-        .tool(TMDBSearchTool::new())
+        .tool(TMDBMovieSearchTool::new().with_rejected_ids(rejected_ids.clone()))
+        .tool(TMDBSearchTool::new().with_rejected_ids(rejected_ids))
         .tool(TMDBSeasonTool::new())
+        .tool(TMDBAlternativeTitlesTool)
+        .tool(KnownIdsTool::new(known_ids))
         .tool(SubmitTool::new());
 
-    let multi_agent = MultiTurnAgent {
-        agent: agent.build(),
-        chat_history: Vec::new(),
-    };
+    let multi_agent = MultiTurnAgent::new(agent.build());
 
-    let extractor = extractor.preamble(EXTRACT_TMDB_MATCH_RESULT_PROMPT).build();
+    let extractor = extractor.preamble(&prompt.extractor).build();
 
     AnimeMatcherAgent {
         agent: multi_agent,
@@ -114,21 +138,94 @@ pub fn new_mapping_tmdb_agent<M: rig::completion::CompletionModel>(
     }
 }
 
+/// 多轮对话的错误，超出预算时与普通失败区分开
+#[derive(Debug, thiserror::Error)]
+pub enum MultiTurnError {
+    #[error(transparent)]
+    Prompt(#[from] PromptError),
+    #[error(transparent)]
+    BudgetExceeded(#[from] BudgetExceeded),
+}
+
+impl From<CompletionError> for MultiTurnError {
+    fn from(e: CompletionError) -> Self {
+        Self::Prompt(e.into())
+    }
+}
+
+impl From<ToolSetError> for MultiTurnError {
+    fn from(e: ToolSetError) -> Self {
+        Self::Prompt(e.into())
+    }
+}
+
+/// 判断匹配失败是否由超出预算导致
+pub fn budget_exceeded(e: &anyhow::Error) -> Option<&BudgetExceeded> {
+    match e.downcast_ref::<MultiTurnError>() {
+        Some(MultiTurnError::BudgetExceeded(budget)) => Some(budget),
+        _ => e.downcast_ref::<BudgetExceeded>(),
+    }
+}
+
 struct MultiTurnAgent<M: rig::completion::CompletionModel> {
     agent: rig::agent::Agent<M>,
     chat_history: Vec<completion::Message>,
+    budget: MatchBudget,
+    rate_limiter: Option<Arc<DefaultDirectRateLimiter>>,
+    usage_reader: UsageReader<M::Response>,
+    usage: TokenUsage,
+    tool_calls: usize,
+    tool_records: Vec<ToolCallRecord>,
+    output: Option<String>,
 }
 
 impl<M: rig::completion::CompletionModel> MultiTurnAgent<M> {
+    fn new(agent: rig::agent::Agent<M>) -> Self {
+        Self {
+            agent,
+            chat_history: Vec::new(),
+            budget: MatchBudget::unlimited(),
+            rate_limiter: None,
+            usage_reader: |_| None,
+            usage: TokenUsage::default(),
+            tool_calls: 0,
+            tool_records: Vec::new(),
+            output: None,
+        }
+    }
+
     async fn multi_turn_prompt(
         &mut self,
-        _prompt: impl Into<Message> + Send,
-    ) -> Result<String, PromptError> {
-        let mut prompt: Message = _prompt.into();
+        prompt: impl Into<Message> + Send,
+    ) -> Result<String, MultiTurnError> {
+        let prompt = prompt.into();
+        match self.budget.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.run_turns(prompt))
+                .await
+                .map_err(|_| BudgetExceeded::Timeout(timeout))?,
+            None => self.run_turns(prompt).await,
+        }
+    }
 
+    async fn run_turns(&mut self, mut prompt: Message) -> Result<String, MultiTurnError> {
         loop {
             info!("当前提示: {:?}\n", prompt);
 
+            // 每一轮都会重新发送完整的对话历史，发送前按估算的输入token检查预算
+            let estimated_prompt = estimate_message_tokens(&prompt)
+                + self
+                    .chat_history
+                    .iter()
+                    .map(estimate_message_tokens)
+                    .sum::<u64>();
+            self.budget.check_usage(&TokenUsage::new(
+                self.usage.prompt_tokens + estimated_prompt,
+                self.usage.completion_tokens,
+            ))?;
+
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.until_ready().await;
+            }
             let resp = self
                 .agent
                 .completion(prompt.clone(), self.chat_history.clone())
@@ -136,71 +233,469 @@ impl<M: rig::completion::CompletionModel> MultiTurnAgent<M> {
                 .send()
                 .await?;
 
-            let mut output = None;
+            let usage = (self.usage_reader)(&resp.raw_response).unwrap_or_else(|| {
+                let estimated_completion = resp
+                    .choice
+                    .iter()
+                    .map(|content| {
+                        estimate_message_tokens(&Message::Assistant {
+                            content: OneOrMany::one(content.clone()),
+                        })
+                    })
+                    .sum::<u64>();
+                TokenUsage::new(estimated_prompt, estimated_completion)
+            });
+            self.usage.add(&usage);
+            self.budget.check_usage(&self.usage)?;
 
-            for content in resp.choice.into_iter() {
+            let mut output = None;
+            let mut tool_calls = Vec::new();
+            for content in resp.choice.iter() {
                 match content {
                     AssistantContent::Text(text) => {
                         info!("中间响应: {:?}\n", text.text);
                         output = Some(text.text.clone());
-                        self.chat_history.push(prompt.clone());
-                        let response_message = Message::Assistant {
-                            content: OneOrMany::one(AssistantContent::text(&text.text)),
-                        };
-                        self.chat_history.push(response_message);
                     }
-                    AssistantContent::ToolCall(content) => {
-                        info!("工具调用: {:?}\n", content);
-                        self.chat_history.push(prompt.clone());
-                        let tool_call_msg = AssistantContent::ToolCall(content.clone());
-                        self.chat_history.push(Message::Assistant {
-                            content: OneOrMany::one(tool_call_msg),
-                        });
-
-                        let ToolCall {
-                            id,
-                            function: ToolFunction { name, arguments },
-                        } = content;
-
-                        if name == SubmitTool::NAME {
-                            let json_output = arguments.to_string();
-                            info!("提交匹配结果结果: {:?}\n", json_output);
-                            return Ok(json_output);
-                        }
-
-                        let tool_result =
-                            self.agent.tools.call(&name, arguments.to_string()).await?;
-
-                        info!("工具调用结果: {:?}\n", tool_result);
-
-                        self.chat_history.push(Message::User {
-                            content: OneOrMany::one(UserContent::tool_result(
-                                id,
-                                OneOrMany::one(ToolResultContent::text(tool_result)),
-                            )),
-                        });
-
-                        prompt = Message::User {
-                            content: OneOrMany::one(UserContent::text("resume")),
-                        };
-
-                        output = None;
-                        break;
+                    AssistantContent::ToolCall(tool_call) => {
+                        info!("工具调用: {:?}\n", tool_call);
+                        tool_calls.push(tool_call.clone());
                     }
                 }
             }
 
-            if let Some(text) = output {
-                return Ok(text);
+            self.chat_history.push(prompt.clone());
+            self.chat_history.push(Message::Assistant {
+                content: resp.choice,
+            });
+
+            if tool_calls.is_empty() {
+                let output = output.unwrap_or_default();
+                self.output = Some(output.clone());
+                return Ok(output);
+            }
+
+            // 同一轮中出现submit时直接结束对话，其余工具调用不再执行
+            if let Some(submit) = tool_calls
+                .iter()
+                .find(|tool_call| tool_call.function.name == SubmitTool::NAME)
+            {
+                let json_output = submit.function.arguments.to_string();
+                info!("提交匹配结果结果: {:?}\n", json_output);
+                self.tool_records.push(ToolCallRecord {
+                    name: submit.function.name.clone(),
+                    arguments: submit.function.arguments.clone(),
+                    result: None,
+                    error: None,
+                });
+                self.output = Some(json_output.clone());
+                return Ok(json_output);
+            }
+
+            self.tool_calls += tool_calls.len();
+            self.budget.check_tool_calls(self.tool_calls)?;
+
+            // 同一轮的工具调用并发执行，结果按调用顺序合并为一条消息返回给模型
+            let results = join_all(tool_calls.iter().map(|tool_call| {
+                self.agent.tools.call(
+                    &tool_call.function.name,
+                    tool_call.function.arguments.to_string(),
+                )
+            }))
+            .await;
+
+            let mut tool_results = Vec::with_capacity(results.len());
+            for (ToolCall { id, function }, result) in tool_calls.into_iter().zip(results) {
+                self.tool_records.push(ToolCallRecord {
+                    name: function.name,
+                    arguments: function.arguments,
+                    result: result.as_ref().ok().cloned(),
+                    error: result.as_ref().err().map(|e| e.to_string()),
+                });
+                // 单个工具失败时把错误返回给模型，由模型决定换个参数重试或放弃
+                let tool_result = result.unwrap_or_else(|e| format!("工具调用失败: {}", e));
+                info!("工具调用结果: {:?}\n", tool_result);
+                tool_results.push(UserContent::tool_result(
+                    id,
+                    OneOrMany::one(ToolResultContent::text(tool_result)),
+                ));
             }
+
+            prompt = Message::User {
+                content: OneOrMany::many(tool_results).expect("tool results should not be empty"),
+            };
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
 pub struct MatchResult {
     pub id: Option<i32>,
     pub name: Option<String>,
     pub season: Option<i32>,
     pub confidence_score: Option<i32>,
+    /// 模型考虑过的候选条目，按置信度从高到低排列
+    #[serde(default)]
+    pub candidates: Vec<MatchCandidate>,
+}
+
+/// 一个候选条目及模型给出的理由
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct MatchCandidate {
+    pub id: i32,
+    pub name: Option<String>,
+    pub season: Option<i32>,
+    pub confidence_score: Option<i32>,
+    pub reason: Option<String>,
+    /// 提出该候选的模型（provider/model），由程序填写
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip)]
+    pub source: Option<String>,
+}
+
+impl MatchResult {
+    pub fn from_candidate(candidate: MatchCandidate) -> Self {
+        Self {
+            id: Some(candidate.id),
+            name: candidate.name.clone(),
+            season: candidate.season,
+            confidence_score: candidate.confidence_score,
+            candidates: vec![candidate],
+        }
+    }
+
+    /// 候选按置信度排序，并保证选中的条目出现在候选列表中
+    pub fn normalize(mut self) -> Self {
+        self.candidates
+            .sort_by_key(|candidate| std::cmp::Reverse(candidate.confidence_score.unwrap_or(0)));
+
+        if let Some(id) = self.id {
+            let position = self
+                .candidates
+                .iter()
+                .position(|candidate| candidate.id == id && candidate.season == self.season);
+            match position {
+                Some(position) => {
+                    let selected = self.candidates.remove(position);
+                    self.candidates.insert(0, selected);
+                }
+                None => self.candidates.insert(
+                    0,
+                    MatchCandidate {
+                        id,
+                        name: self.name.clone(),
+                        season: self.season,
+                        confidence_score: self.confidence_score,
+                        reason: None,
+                        source: None,
+                    },
+                ),
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::mock::{ScriptedCompletionModel, ScriptedTurn};
+    use rig::agent::AgentBuilder;
+    use rig::completion::ToolDefinition;
+    use serde_json::json;
+
+    #[derive(Deserialize)]
+    struct EchoArgs {
+        text: String,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("echo error")]
+    struct EchoError;
+
+    struct EchoTool;
+
+    impl Tool for EchoTool {
+        const NAME: &'static str = "echo";
+
+        type Error = EchoError;
+        type Args = EchoArgs;
+        type Output = String;
+
+        async fn definition(&self, _prompt: String) -> ToolDefinition {
+            ToolDefinition {
+                name: "echo".to_string(),
+                description: "Echo the input text".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "text": { "type": "string" }
+                    },
+                    "required": ["text"]
+                }),
+            }
+        }
+
+        async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+            Ok(format!("echo: {}", args.text))
+        }
+    }
+
+    /// 提取用户消息中的工具结果 (id, 内容)
+    fn tool_results(message: &Message) -> Vec<(String, String)> {
+        match message {
+            Message::User { content } => content
+                .iter()
+                .filter_map(|content| match content {
+                    UserContent::ToolResult(result) => match result.content.first() {
+                        ToolResultContent::Text(text) => Some((result.id.clone(), text.text)),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn new_multi_turn_agent(
+        model: &ScriptedCompletionModel,
+    ) -> MultiTurnAgent<ScriptedCompletionModel> {
+        MultiTurnAgent::new(
+            AgentBuilder::new(model.clone())
+                .tool(EchoTool)
+                .tool(SubmitTool::new())
+                .build(),
+        )
+    }
+
+    fn new_matcher_agent(
+        model: &ScriptedCompletionModel,
+        extractor_model: &ScriptedCompletionModel,
+    ) -> AnimeMatcherAgent<ScriptedCompletionModel> {
+        AnimeMatcherAgent {
+            agent: new_multi_turn_agent(model),
+            extractor: ExtractorBuilder::new(extractor_model.clone()).build(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_text_response() {
+        let model = ScriptedCompletionModel::new([ScriptedTurn::text("no match")]);
+        let mut agent = new_multi_turn_agent(&model);
+
+        let output = agent.multi_turn_prompt("query").await.unwrap();
+        assert_eq!(output, "no match");
+        assert_eq!(model.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_then_submit() {
+        let model = ScriptedCompletionModel::new([
+            ScriptedTurn::tool_call("echo", json!({"text": "ひらやすみ"})),
+            ScriptedTurn::submit(json!({"id": 42, "name": "ひらやすみ", "confidence_score": 95})),
+            ScriptedTurn::text("never reached"),
+        ]);
+        let mut agent = new_multi_turn_agent(&model);
+
+        let output = agent.multi_turn_prompt("query").await.unwrap();
+        let result: MatchResult = serde_json::from_str(&output).unwrap();
+        assert_eq!(result.id, Some(42));
+        assert_eq!(result.confidence_score, Some(95));
+
+        // submit 之后不再请求模型
+        assert_eq!(model.remaining(), 1);
+
+        // 第二轮请求的历史包含原始提示与工具调用，提示为工具结果
+        let requests = model.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].chat_history.len(), 2);
+        let results = tool_results(&requests[1].prompt);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "call_1_0");
+        assert!(results[0].1.contains("echo: ひらやすみ"));
+    }
+
+    #[tokio::test]
+    async fn test_parallel_tool_calls() {
+        let model = ScriptedCompletionModel::new([
+            ScriptedTurn::ToolCalls(vec![
+                ("echo".to_string(), json!({"text": "a"})),
+                ("echo".to_string(), json!({"text": "b"})),
+            ]),
+            ScriptedTurn::submit(json!({"id": 1, "confidence_score": 90})),
+        ]);
+        let mut agent = new_multi_turn_agent(&model);
+
+        agent.multi_turn_prompt("query").await.unwrap();
+
+        let requests = model.requests();
+        assert_eq!(requests.len(), 2);
+        let results = tool_results(&requests[1].prompt);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "call_1_0");
+        assert!(results[0].1.contains("echo: a"));
+        assert_eq!(results[1].0, "call_1_1");
+        assert!(results[1].1.contains("echo: b"));
+        assert_eq!(agent.tool_calls, 2);
+        assert_eq!(agent.tool_records.len(), 3);
+        assert_eq!(agent.tool_records[2].name, "submit");
+    }
+
+    #[tokio::test]
+    async fn test_submit_with_other_tool_calls() {
+        let model = ScriptedCompletionModel::new([ScriptedTurn::ToolCalls(vec![
+            ("echo".to_string(), json!({"text": "a"})),
+            (
+                "submit".to_string(),
+                json!({"id": 3, "confidence_score": 80}),
+            ),
+        ])]);
+        let mut agent = new_multi_turn_agent(&model);
+
+        let output = agent.multi_turn_prompt("query").await.unwrap();
+        let result: MatchResult = serde_json::from_str(&output).unwrap();
+        assert_eq!(result.id, Some(3));
+        assert_eq!(agent.tool_calls, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_tool_call() {
+        let model = ScriptedCompletionModel::new([
+            ScriptedTurn::ToolCalls(vec![
+                ("missing".to_string(), json!({})),
+                ("echo".to_string(), json!({"text": "a"})),
+            ]),
+            ScriptedTurn::submit(json!({"id": 1, "confidence_score": 90})),
+        ]);
+        let mut agent = new_multi_turn_agent(&model);
+
+        agent.multi_turn_prompt("query").await.unwrap();
+
+        // 失败的调用以错误信息作为结果，其余调用不受影响
+        let results = tool_results(&model.requests()[1].prompt);
+        assert_eq!(results.len(), 2);
+        assert!(results[0].1.contains("工具调用失败"));
+        assert!(results[1].1.contains("echo: a"));
+        assert!(agent.tool_records[0].error.is_some());
+        assert!(agent.tool_records[0].result.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_provider_error() {
+        let model = ScriptedCompletionModel::new([ScriptedTurn::Error("rate limited".to_string())]);
+        let extractor_model = ScriptedCompletionModel::default();
+        let mut agent = new_matcher_agent(&model, &extractor_model);
+
+        assert!(agent.match_anime("query").await.is_err());
+        assert!(extractor_model.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_match_anime_submit() {
+        let model = ScriptedCompletionModel::new([ScriptedTurn::submit(
+            json!({"id": 7, "name": "うずまき", "season": 1, "confidence_score": 90}),
+        )]);
+        let extractor_model = ScriptedCompletionModel::default();
+        let mut agent = new_matcher_agent(&model, &extractor_model);
+
+        let result = agent.match_anime("query").await.unwrap();
+        assert_eq!(result.id, Some(7));
+        assert_eq!(result.season, Some(1));
+        assert!(extractor_model.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_match_anime_candidates() {
+        let model = ScriptedCompletionModel::new([ScriptedTurn::submit(json!({
+            "id": 7,
+            "name": "うずまき",
+            "confidence_score": 90,
+            "candidates": [
+                {"id": 8, "name": "うずまき 特別編", "confidence_score": 40, "reason": "特别篇"},
+                {"id": 9, "name": "うずまき (2000)", "confidence_score": 60, "reason": "真人电影"},
+            ]
+        }))]);
+        let extractor_model = ScriptedCompletionModel::default();
+        let mut agent = new_matcher_agent(&model, &extractor_model);
+
+        let result = agent.match_anime("query").await.unwrap();
+        let ids: Vec<i32> = result.candidates.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![7, 9, 8]);
+        assert_eq!(result.candidates[0].confidence_score, Some(90));
+    }
+
+    #[tokio::test]
+    async fn test_match_anime_extractor_fallback() {
+        let model = ScriptedCompletionModel::new([ScriptedTurn::text(
+            "The best match is うずまき with id 7",
+        )]);
+        let extractor_model = ScriptedCompletionModel::new([ScriptedTurn::submit(
+            json!({"id": 7, "name": "うずまき"}),
+        )]);
+        let mut agent = new_matcher_agent(&model, &extractor_model);
+
+        let result = agent.match_anime("query").await.unwrap();
+        assert_eq!(result.id, Some(7));
+        assert_eq!(result.name.as_deref(), Some("うずまき"));
+        assert_eq!(extractor_model.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_match_anime_extractor_failure() {
+        let model = ScriptedCompletionModel::new([ScriptedTurn::text("I am not sure")]);
+        let extractor_model = ScriptedCompletionModel::new([ScriptedTurn::text("nothing")]);
+        let mut agent = new_matcher_agent(&model, &extractor_model);
+
+        assert!(agent.match_anime("query").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_budget() {
+        let model = ScriptedCompletionModel::new([
+            ScriptedTurn::tool_call("echo", json!({"text": "a"})),
+            ScriptedTurn::tool_call("echo", json!({"text": "b"})),
+            ScriptedTurn::submit(json!({"id": 1, "confidence_score": 90})),
+        ]);
+        let mut agent = new_multi_turn_agent(&model);
+        agent.budget.max_tool_calls = Some(1);
+
+        let err = agent.multi_turn_prompt("query").await.unwrap_err();
+        assert!(matches!(
+            err,
+            MultiTurnError::BudgetExceeded(BudgetExceeded::ToolCalls(1))
+        ));
+        assert_eq!(model.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_token_budget() {
+        let model = ScriptedCompletionModel::new([ScriptedTurn::text("x".repeat(400))]);
+        let extractor_model = ScriptedCompletionModel::default();
+        let mut agent = new_matcher_agent(&model, &extractor_model).with_budget(MatchBudget {
+            max_completion_tokens: Some(10),
+            ..MatchBudget::unlimited()
+        });
+
+        let err = agent.match_anime("query").await.unwrap_err();
+        assert_eq!(
+            budget_exceeded(&err),
+            Some(&BudgetExceeded::CompletionTokens(10))
+        );
+        assert!(agent.usage().completion_tokens > 10);
+        assert!(extractor_model.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reported_usage() {
+        let model = ScriptedCompletionModel::new([
+            ScriptedTurn::tool_call("echo", json!({"text": "a"})),
+            ScriptedTurn::text("x".repeat(400)),
+        ]);
+        let mut agent = new_multi_turn_agent(&model);
+        agent.usage_reader = |_| Some(TokenUsage::new(7, 3));
+
+        agent.multi_turn_prompt("query").await.unwrap();
+        // provider 报告了消耗时不再使用估算值
+        assert_eq!(agent.usage, TokenUsage::new(14, 6));
+    }
 }
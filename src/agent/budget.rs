@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use rig::message::Message;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_MAX_TOOL_CALLS: usize = 16;
+const DEFAULT_MAX_PROMPT_TOKENS: u64 = 200_000;
+const DEFAULT_MAX_COMPLETION_TOKENS: u64 = 20_000;
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// 单次匹配允许消耗的资源上限，`None` 表示不限制
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchBudget {
+    pub max_tool_calls: Option<usize>,
+    pub max_prompt_tokens: Option<u64>,
+    pub max_completion_tokens: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+impl MatchBudget {
+    pub fn unlimited() -> Self {
+        Self {
+            max_tool_calls: None,
+            max_prompt_tokens: None,
+            max_completion_tokens: None,
+            timeout: None,
+        }
+    }
+
+    /// 从环境变量读取，未设置时使用默认值，设置为0表示不限制
+    pub fn from_env() -> Self {
+        fn read(key: &str, default: Option<u64>) -> Option<u64> {
+            match std::env::var(key)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+            {
+                Some(value) => (value > 0).then_some(value),
+                None => default,
+            }
+        }
+
+        let default = Self::default();
+        Self {
+            max_tool_calls: read(
+                "MATCH_MAX_TOOL_CALLS",
+                default.max_tool_calls.map(|value| value as u64),
+            )
+            .map(|value| value as usize),
+            max_prompt_tokens: read("MATCH_MAX_PROMPT_TOKENS", default.max_prompt_tokens),
+            max_completion_tokens: read(
+                "MATCH_MAX_COMPLETION_TOKENS",
+                default.max_completion_tokens,
+            ),
+            timeout: read(
+                "MATCH_TIMEOUT_SECS",
+                default.timeout.map(|timeout| timeout.as_secs()),
+            )
+            .map(Duration::from_secs),
+        }
+    }
+}
+
+impl Default for MatchBudget {
+    fn default() -> Self {
+        Self {
+            max_tool_calls: Some(DEFAULT_MAX_TOOL_CALLS),
+            max_prompt_tokens: Some(DEFAULT_MAX_PROMPT_TOKENS),
+            max_completion_tokens: Some(DEFAULT_MAX_COMPLETION_TOKENS),
+            timeout: Some(Duration::from_secs(DEFAULT_TIMEOUT_SECS)),
+        }
+    }
+}
+
+/// 匹配过程中的 token 消耗
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// 从模型的原始响应中读取 provider 报告的 token 消耗，未报告时返回None
+pub type UsageReader<R> = fn(&R) -> Option<TokenUsage>;
+
+impl TokenUsage {
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+}
+
+/// 超出预算的原因
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Serialize, Deserialize)]
+pub enum BudgetExceeded {
+    #[error("工具调用次数超出预算: {0}")]
+    ToolCalls(usize),
+    #[error("输入token超出预算: {0}")]
+    PromptTokens(u64),
+    #[error("输出token超出预算: {0}")]
+    CompletionTokens(u64),
+    #[error("匹配超时: {0:?}")]
+    Timeout(Duration),
+}
+
+impl MatchBudget {
+    pub fn check_tool_calls(&self, tool_calls: usize) -> Result<(), BudgetExceeded> {
+        match self.max_tool_calls {
+            Some(max) if tool_calls > max => Err(BudgetExceeded::ToolCalls(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_usage(&self, usage: &TokenUsage) -> Result<(), BudgetExceeded> {
+        if let Some(max) = self.max_prompt_tokens {
+            if usage.prompt_tokens > max {
+                return Err(BudgetExceeded::PromptTokens(max));
+            }
+        }
+        if let Some(max) = self.max_completion_tokens {
+            if usage.completion_tokens > max {
+                return Err(BudgetExceeded::CompletionTokens(max));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// provider 未报告消耗时使用的粗略估算：ASCII 约4个字符一个 token，其他字符（如中日文）按每字一个 token 计算
+pub fn estimate_tokens(text: &str) -> u64 {
+    let (ascii, other) = text.chars().fold((0u64, 0u64), |(ascii, other), c| {
+        if c.is_ascii() {
+            (ascii + 1, other)
+        } else {
+            (ascii, other + 1)
+        }
+    });
+    ascii.div_ceil(4) + other
+}
+
+/// 估算消息的 token 数
+pub fn estimate_message_tokens(message: &Message) -> u64 {
+    estimate_tokens(&serde_json::to_string(message).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens("うずまき"), 4);
+    }
+
+    #[test]
+    fn test_check_usage() {
+        let budget = MatchBudget {
+            max_prompt_tokens: Some(100),
+            max_completion_tokens: Some(10),
+            ..MatchBudget::unlimited()
+        };
+        let mut usage = TokenUsage::default();
+        assert!(budget.check_usage(&usage).is_ok());
+
+        usage.completion_tokens = 11;
+        assert_eq!(
+            budget.check_usage(&usage),
+            Err(BudgetExceeded::CompletionTokens(10))
+        );
+        assert!(MatchBudget::unlimited().check_usage(&usage).is_ok());
+    }
+}
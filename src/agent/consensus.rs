@@ -0,0 +1,285 @@
+use serde::{Deserialize, Serialize};
+
+use crate::agent::agent::{MatchCandidate, MatchResult};
+
+/// 所有模型一致时，每多一个模型增加的置信度
+const CONSENSUS_BOOST: i32 = 10;
+
+/// 参与匹配的 provider/model 组合
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelRef {
+    pub provider: String,
+    pub model: String,
+}
+
+impl ModelRef {
+    pub fn new(provider: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            provider: provider.into(),
+            model: model.into(),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}/{}", self.provider, self.model)
+    }
+}
+
+/// 一次匹配（单模型或多模型）的最终结论
+#[derive(Debug, Clone)]
+pub enum MatchOutcome {
+    /// 找到了可信的匹配
+    Matched(MatchResult),
+    /// 没有找到可信的匹配
+    NoMatch(MatchResult),
+    /// 模型之间存在分歧，候选中包含每个模型的提议
+    Conflict(Vec<MatchCandidate>),
+    /// 提交的条目未通过校验
+    Rejected { result: MatchResult, reason: String },
+}
+
+impl MatchOutcome {
+    pub fn from_result(result: MatchResult) -> Self {
+        if result.id.is_some() {
+            Self::Matched(result)
+        } else {
+            Self::NoMatch(result)
+        }
+    }
+
+    /// 提交的条目被拒绝，该候选降到最后并注明原因，避免审核时被当作首选
+    pub fn rejected(mut result: MatchResult, reason: impl Into<String>) -> Self {
+        let reason = reason.into();
+        if let Some(position) = result
+            .candidates
+            .iter()
+            .position(|candidate| Some(candidate.id) == result.id)
+        {
+            let mut candidate = result.candidates.remove(position);
+            candidate.reason = Some(match candidate.reason.take() {
+                Some(previous) => format!("{}（已拒绝: {}）", previous, reason),
+                None => format!("已拒绝: {}", reason),
+            });
+            result.candidates.push(candidate);
+        }
+        Self::Rejected { result, reason }
+    }
+
+    /// 需要保存的候选列表
+    pub fn candidates(&self) -> &[MatchCandidate] {
+        match self {
+            Self::Matched(result) | Self::NoMatch(result) | Self::Rejected { result, .. } => {
+                &result.candidates
+            }
+            Self::Conflict(candidates) => candidates,
+        }
+    }
+}
+
+/// 根据多个模型的结果得出结论，`None` 表示该模型匹配失败
+///
+/// - 所有模型都成功且提交了相同的条目：匹配成功，置信度提升
+/// - 没有任何模型提交条目：未匹配
+/// - 其余情况：存在分歧，交给人工裁决
+pub fn decide_consensus(proposals: &[(ModelRef, Option<MatchResult>)]) -> MatchOutcome {
+    let results: Vec<MatchResult> = proposals
+        .iter()
+        .filter_map(|(model, result)| {
+            result.clone().map(|mut result| {
+                for candidate in result.candidates.iter_mut() {
+                    candidate.source.get_or_insert_with(|| model.label());
+                }
+                result
+            })
+        })
+        .collect();
+
+    let selected: Vec<(i32, Option<i32>)> = results
+        .iter()
+        .filter_map(|result| result.id.map(|id| (id, result.season)))
+        .collect();
+
+    if selected.is_empty() {
+        return MatchOutcome::NoMatch(MatchResult {
+            id: None,
+            name: None,
+            season: None,
+            confidence_score: None,
+            candidates: merge_candidates(&results),
+        });
+    }
+
+    let unanimous = results.len() == proposals.len()
+        && selected.len() == results.len()
+        && selected.iter().all(|item| *item == selected[0]);
+    if unanimous {
+        let max_score = results
+            .iter()
+            .filter_map(|result| result.confidence_score)
+            .max()
+            .unwrap_or_default();
+        let boost = CONSENSUS_BOOST * (results.len() as i32 - 1);
+        let first = &results[0];
+        let (id, season) = selected[0];
+        let mut candidates = merge_candidates(&results);
+        let sources = proposals
+            .iter()
+            .map(|(model, _)| model.label())
+            .collect::<Vec<_>>()
+            .join(", ");
+        for candidate in candidates.iter_mut() {
+            if candidate.id == id && candidate.season == season {
+                candidate.source = Some(sources.clone());
+            }
+        }
+        let result = MatchResult {
+            id: Some(id),
+            name: first.name.clone(),
+            season,
+            confidence_score: Some((max_score + boost).min(100)),
+            candidates,
+        };
+        return MatchOutcome::Matched(result.normalize());
+    }
+
+    // 每个提交了条目的模型对应一个候选，按置信度排序
+    let mut candidates: Vec<MatchCandidate> = proposals
+        .iter()
+        .filter_map(|(model, result)| {
+            let result = result.as_ref()?;
+            let id = result.id?;
+            let reason = result
+                .candidates
+                .iter()
+                .find(|candidate| candidate.id == id && candidate.season == result.season)
+                .and_then(|candidate| candidate.reason.clone());
+            Some(MatchCandidate {
+                id,
+                name: result.name.clone(),
+                season: result.season,
+                confidence_score: result.confidence_score,
+                reason,
+                source: Some(model.label()),
+            })
+        })
+        .collect();
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.confidence_score.unwrap_or(0)));
+    MatchOutcome::Conflict(candidates)
+}
+
+/// 合并所有模型的候选，相同条目只保留置信度最高的一个
+fn merge_candidates(results: &[MatchResult]) -> Vec<MatchCandidate> {
+    let mut merged: Vec<MatchCandidate> = Vec::new();
+    for candidate in results.iter().flat_map(|result| result.candidates.iter()) {
+        match merged
+            .iter_mut()
+            .find(|item| item.id == candidate.id && item.season == candidate.season)
+        {
+            Some(item) if item.confidence_score < candidate.confidence_score => {
+                *item = candidate.clone()
+            }
+            Some(_) => {}
+            None => merged.push(candidate.clone()),
+        }
+    }
+    merged.sort_by_key(|candidate| std::cmp::Reverse(candidate.confidence_score.unwrap_or(0)));
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(id: Option<i32>, score: i32) -> MatchResult {
+        MatchResult {
+            id,
+            name: None,
+            season: Some(1),
+            confidence_score: Some(score),
+            candidates: Vec::new(),
+        }
+        .normalize()
+    }
+
+    #[test]
+    fn test_unanimous() {
+        let proposals = vec![
+            (ModelRef::new("openai", "a"), Some(result(Some(1), 80))),
+            (ModelRef::new("gemini", "b"), Some(result(Some(1), 70))),
+        ];
+        match decide_consensus(&proposals) {
+            MatchOutcome::Matched(result) => {
+                assert_eq!(result.id, Some(1));
+                assert_eq!(result.confidence_score, Some(90));
+                assert_eq!(
+                    result.candidates[0].source.as_deref(),
+                    Some("openai/a, gemini/b")
+                );
+            }
+            outcome => panic!("unexpected outcome: {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn test_conflict() {
+        let proposals = vec![
+            (ModelRef::new("openai", "a"), Some(result(Some(1), 80))),
+            (ModelRef::new("gemini", "b"), Some(result(Some(2), 90))),
+            (ModelRef::new("xai", "c"), Some(result(None, 0))),
+        ];
+        match decide_consensus(&proposals) {
+            MatchOutcome::Conflict(candidates) => {
+                let ids: Vec<i32> = candidates.iter().map(|c| c.id).collect();
+                assert_eq!(ids, vec![2, 1]);
+                assert_eq!(candidates[0].source.as_deref(), Some("gemini/b"));
+            }
+            outcome => panic!("unexpected outcome: {:?}", outcome),
+        }
+
+        // 部分模型失败时不能认为是一致的
+        let proposals = vec![
+            (ModelRef::new("openai", "a"), Some(result(Some(1), 80))),
+            (ModelRef::new("gemini", "b"), None),
+        ];
+        assert!(matches!(
+            decide_consensus(&proposals),
+            MatchOutcome::Conflict(_)
+        ));
+    }
+
+    #[test]
+    fn test_rejected() {
+        let mut proposal = result(Some(1), 80);
+        proposal.candidates.push(MatchCandidate {
+            id: 2,
+            name: None,
+            season: Some(1),
+            confidence_score: Some(60),
+            reason: None,
+            source: None,
+        });
+
+        let outcome = MatchOutcome::rejected(proposal, "集数不一致");
+        let ids: Vec<i32> = outcome.candidates().iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![2, 1]);
+        assert_eq!(
+            outcome.candidates()[1].reason.as_deref(),
+            Some("已拒绝: 集数不一致")
+        );
+        assert!(
+            matches!(outcome, MatchOutcome::Rejected { ref reason, .. } if reason == "集数不一致")
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        let proposals = vec![
+            (ModelRef::new("openai", "a"), Some(result(None, 0))),
+            (ModelRef::new("gemini", "b"), None),
+        ];
+        assert!(matches!(
+            decide_consensus(&proposals),
+            MatchOutcome::NoMatch(_)
+        ));
+    }
+}
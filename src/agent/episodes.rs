@@ -0,0 +1,302 @@
+use anyhow::{Result, anyhow};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::agent::tool_bgm_tv::BgmTVSearchTool;
+use crate::agent::tool_tmdb::{TMDBEpisode, fetch_tmdb_episode_groups, tmdb_get};
+use crate::models::anime::Model as Anime;
+use crate::models::db::DB;
+use crate::models::enums::{MediaType, Platform, ReviewStatus};
+
+/// BgmTV 中本篇章节的类型
+const BGM_EPISODE_TYPE_MAIN: i32 = 0;
+/// 按开播日期定位首集时允许的提前天数，避免时区差异
+const AIR_DATE_TOLERANCE_DAYS: i64 = 1;
+
+/// 平台上的一集
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteEpisode {
+    pub id: String,
+    /// TMDB 的季数，BgmTV 为空
+    pub season: Option<i32>,
+    /// 季内或条目内的集数
+    pub number: i32,
+    pub air_date: Option<NaiveDate>,
+}
+
+/// 首集在平台章节列表中的定位方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EpisodeAnchor {
+    /// 优先使用动画的 episode_number（TMDB 季内集数），其次是开播日期
+    EpisodeNumber,
+    /// 只按开播日期定位
+    AirDate,
+}
+
+/// AniList 中的一集与平台章节的对应关系
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpisodeMapping {
+    pub episode: i32,
+    pub season: Option<i32>,
+    pub platform_episode: i32,
+    pub platform_episode_id: Option<String>,
+    pub air_date: Option<String>,
+}
+
+fn parse_date(date: Option<&str>) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date?, "%Y-%m-%d").ok()
+}
+
+/// 找到动画第一集在章节列表中的位置
+fn find_start(anime: &Anime, episodes: &[RemoteEpisode], anchor: EpisodeAnchor) -> Option<usize> {
+    if anchor == EpisodeAnchor::EpisodeNumber {
+        if let Some(number) = anime.episode_number {
+            if let Some(index) = episodes.iter().position(|e| e.number == number) {
+                return Some(index);
+            }
+        }
+    }
+
+    match parse_date(anime.start_date.as_deref()) {
+        Some(start_date) => {
+            let earliest = start_date - Duration::days(AIR_DATE_TOLERANCE_DAYS);
+            episodes
+                .iter()
+                .position(|e| e.air_date.is_some_and(|date| date >= earliest))
+        }
+        None => (!episodes.is_empty()).then_some(0),
+    }
+}
+
+/// 根据首集位置和集数推导每一集的对应关系
+pub fn derive_episode_mappings(
+    anime: &Anime,
+    episodes: &[RemoteEpisode],
+    anchor: EpisodeAnchor,
+) -> Vec<EpisodeMapping> {
+    let Some(start) = find_start(anime, episodes, anchor) else {
+        return vec![];
+    };
+    let count = anime
+        .episode_count
+        .map(|count| count.max(0) as usize)
+        .unwrap_or(episodes.len() - start);
+
+    episodes[start..]
+        .iter()
+        .take(count)
+        .enumerate()
+        .map(|(index, episode)| EpisodeMapping {
+            episode: index as i32 + 1,
+            season: episode.season,
+            platform_episode: episode.number,
+            platform_episode_id: Some(episode.id.clone()),
+            air_date: episode.air_date.map(|date| date.to_string()),
+        })
+        .collect()
+}
+
+impl From<TMDBEpisode> for RemoteEpisode {
+    fn from(episode: TMDBEpisode) -> Self {
+        Self {
+            id: episode.id.to_string(),
+            season: Some(episode.season_number),
+            number: episode.episode_number,
+            air_date: parse_date(episode.air_date.as_deref()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TMDBSeasonDetails {
+    episodes: Vec<TMDBEpisode>,
+}
+
+/// 获取 TMDB 剧集的章节列表
+///
+/// 优先使用正式的季；季不存在时（匹配时选择的是剧集组中的季）使用剧集组中对应顺序的分组，
+/// 分组中的章节仍然记录正式的季数和集数
+async fn fetch_tmdb_episodes(tv_id: i32, season: i32) -> Result<Vec<RemoteEpisode>> {
+    let details: Option<TMDBSeasonDetails> = tmdb_get(&format!("/tv/{}/season/{}", tv_id, season))
+        .await
+        .map_err(|e| anyhow!("获取TMDB季详情失败: {}", e))?;
+    if let Some(details) = details.filter(|details| !details.episodes.is_empty()) {
+        return Ok(details.episodes.into_iter().map(Into::into).collect());
+    }
+
+    let groups = fetch_tmdb_episode_groups(tv_id)
+        .await
+        .map_err(|e| anyhow!("{}", e))?;
+    Ok(groups
+        .into_iter()
+        .find(|group| group.order == season)
+        .map(|group| group.episodes.into_iter().map(Into::into).collect())
+        .unwrap_or_default())
+}
+
+/// 获取 BgmTV 条目的本篇章节列表
+async fn fetch_bgm_tv_episodes(subject_id: i32) -> Result<Vec<RemoteEpisode>> {
+    let mut episodes = BgmTVSearchTool::new()
+        .get_episodes(subject_id)
+        .await
+        .map_err(|e| anyhow!("获取BgmTV章节失败: {}", e))?;
+    episodes.retain(|episode| episode.episode_type == BGM_EPISODE_TYPE_MAIN);
+    episodes.sort_by(|a, b| a.sort.total_cmp(&b.sort));
+
+    Ok(episodes
+        .into_iter()
+        .map(|episode| RemoteEpisode {
+            id: episode.id.to_string(),
+            season: None,
+            number: episode.ep.unwrap_or(episode.sort) as i32,
+            air_date: parse_date(Some(&episode.airdate)),
+        })
+        .collect())
+}
+
+/// 根据已确认的映射重新生成动画的分集映射并保存
+pub async fn sync_episode_mappings(
+    db: &DB,
+    anilist_id: i32,
+    platform: Platform,
+) -> Result<Vec<EpisodeMapping>> {
+    let (anime, mappings) = db.get_anime(anilist_id).await?;
+    let anime = anime.ok_or_else(|| anyhow!("动画不存在: {}", anilist_id))?;
+    if anime.media_type == MediaType::Movie {
+        return Err(anyhow!("剧场版没有分集"));
+    }
+
+    let platform_id = mappings
+        .into_iter()
+        .find(|mapping| {
+            mapping.platform == platform
+                && matches!(
+                    mapping.review_status,
+                    ReviewStatus::Accepted | ReviewStatus::Ready
+                )
+        })
+        .and_then(|mapping| mapping.platform_id)
+        .ok_or_else(|| anyhow!("没有已确认的映射: {} {:?}", anilist_id, platform))?;
+    let platform_id: i32 = platform_id
+        .parse()
+        .map_err(|_| anyhow!("无效的平台id: {}", platform_id))?;
+
+    let episodes = match platform {
+        Platform::Tmdb => {
+            let season = anime.season_number.unwrap_or(1);
+            let episodes = fetch_tmdb_episodes(platform_id, season).await?;
+            derive_episode_mappings(&anime, &episodes, EpisodeAnchor::EpisodeNumber)
+        }
+        Platform::BgmTv => {
+            let episodes = fetch_bgm_tv_episodes(platform_id).await?;
+            derive_episode_mappings(&anime, &episodes, EpisodeAnchor::AirDate)
+        }
+    };
+    info!(
+        "生成分集映射: anilist_id={}, platform={:?}, episodes={}",
+        anilist_id,
+        platform,
+        episodes.len()
+    );
+
+    db.save_episode_mappings(anilist_id, platform, &episodes)
+        .await?;
+    Ok(episodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::cassette::install_test_replay;
+
+    fn anime(
+        start_date: Option<&str>,
+        episode_count: Option<i32>,
+        episode_number: Option<i32>,
+    ) -> Anime {
+        Anime {
+            start_date: start_date.map(|date| date.to_string()),
+            episode_count,
+            season_number: Some(1),
+            episode_number,
+            ..Anime::test(1)
+        }
+    }
+
+    /// 每周一集的章节列表
+    fn weekly_episodes(count: i32, first_air_date: &str) -> Vec<RemoteEpisode> {
+        let first = NaiveDate::parse_from_str(first_air_date, "%Y-%m-%d").unwrap();
+        (1..=count)
+            .map(|number| RemoteEpisode {
+                id: format!("e{}", number),
+                season: Some(1),
+                number,
+                air_date: Some(first + Duration::weeks(number as i64 - 1)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_derive_by_episode_number() {
+        // 分割放送的第二部分从第13集开始
+        let episodes = weekly_episodes(24, "2024-01-06");
+        let mappings = derive_episode_mappings(
+            &anime(None, Some(12), Some(13)),
+            &episodes,
+            EpisodeAnchor::EpisodeNumber,
+        );
+        assert_eq!(mappings.len(), 12);
+        assert_eq!(mappings[0].episode, 1);
+        assert_eq!(mappings[0].platform_episode, 13);
+        assert_eq!(mappings[11].platform_episode, 24);
+        assert_eq!(mappings[0].platform_episode_id.as_deref(), Some("e13"));
+    }
+
+    #[test]
+    fn test_derive_by_air_date() {
+        let episodes = weekly_episodes(24, "2024-01-06");
+        // 开播日期比第13集早一天，仍然定位到第13集
+        let mappings = derive_episode_mappings(
+            &anime(Some("2024-03-29"), None, None),
+            &episodes,
+            EpisodeAnchor::AirDate,
+        );
+        assert_eq!(mappings.len(), 12);
+        assert_eq!(mappings[0].platform_episode, 13);
+        assert_eq!(mappings[0].air_date.as_deref(), Some("2024-03-30"));
+
+        // 没有开播日期时从第一集开始
+        let mappings = derive_episode_mappings(
+            &anime(None, Some(3), None),
+            &episodes,
+            EpisodeAnchor::AirDate,
+        );
+        assert_eq!(mappings.len(), 3);
+        assert_eq!(mappings[0].platform_episode, 1);
+
+        // 章节都早于开播日期时无法对应
+        let mappings = derive_episode_mappings(
+            &anime(Some("2025-01-01"), None, None),
+            &episodes,
+            EpisodeAnchor::AirDate,
+        );
+        assert!(mappings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_tmdb_episodes_from_group() {
+        install_test_replay();
+
+        // 正式的季不存在时使用剧集组中对应顺序的分组，章节保留正式的季数和集数
+        let episodes = fetch_tmdb_episodes(300004, 2).await.unwrap();
+        let numbers: Vec<(Option<i32>, i32)> = episodes
+            .iter()
+            .map(|episode| (episode.season, episode.number))
+            .collect();
+        assert_eq!(numbers, vec![(Some(1), 3), (Some(1), 4)]);
+        assert_eq!(episodes[0].id, "3000043");
+        assert_eq!(episodes[0].air_date, NaiveDate::from_ymd_opt(2024, 10, 6));
+    }
+}
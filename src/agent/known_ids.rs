@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::enums::Platform;
+
+/// 其他平台上已确认的映射
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownMapping {
+    pub platform: Platform,
+    pub id: String,
+}
+
+/// 动画在其他平台和站点上已知的id，匹配时作为交叉参考
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KnownIds {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mappings: Vec<KnownMapping>,
+    /// 导入数据中的其他站点id，键为站点名称，如 mal、anidb、thetvdb、imdb
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub external_ids: BTreeMap<String, String>,
+    /// 是否为剧场版，用于确定 TMDB id 属于电影还是剧集
+    #[serde(skip)]
+    pub is_movie: bool,
+}
+
+impl KnownIds {
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty() && self.external_ids.is_empty()
+    }
+
+    pub fn mapping(&self, platform: &Platform) -> Option<&str> {
+        self.mappings
+            .iter()
+            .find(|mapping| &mapping.platform == platform)
+            .map(|mapping| mapping.id.as_str())
+    }
+
+    /// 去掉正在匹配的平台自身的映射，只保留可用于交叉参考的部分
+    pub fn for_platform(mut self, platform: &Platform) -> Self {
+        self.mappings
+            .retain(|mapping| &mapping.platform != platform);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_ids_for_platform() {
+        let known_ids = KnownIds {
+            mappings: vec![
+                KnownMapping {
+                    platform: Platform::BgmTv,
+                    id: "1958".to_string(),
+                },
+                KnownMapping {
+                    platform: Platform::Tmdb,
+                    id: "9320".to_string(),
+                },
+            ],
+            external_ids: BTreeMap::from([("mal".to_string(), "1395".to_string())]),
+            is_movie: false,
+        };
+
+        let known_ids = known_ids.for_platform(&Platform::Tmdb);
+        assert_eq!(known_ids.mapping(&Platform::BgmTv), Some("1958"));
+        assert_eq!(known_ids.mapping(&Platform::Tmdb), None);
+        assert!(!known_ids.is_empty());
+
+        let json = serde_json::to_value(&known_ids).unwrap();
+        assert_eq!(json["external_ids"]["mal"], "1395");
+        assert!(json.get("is_movie").is_none());
+    }
+}
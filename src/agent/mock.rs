@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use rig::{
+    OneOrMany,
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    message::{AssistantContent, Message, ToolCall, ToolFunction},
+};
+use serde_json::Value;
+
+/// 预先编排的一轮模型输出
+#[derive(Debug, Clone)]
+pub enum ScriptedTurn {
+    /// 纯文本回复
+    Text(String),
+    /// 一次或多次工具调用，(工具名, 参数)
+    ToolCalls(Vec<(String, Value)>),
+    /// 模拟 provider 返回错误
+    Error(String),
+}
+
+impl ScriptedTurn {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(text.into())
+    }
+
+    pub fn tool_call(name: impl Into<String>, arguments: Value) -> Self {
+        Self::ToolCalls(vec![(name.into(), arguments)])
+    }
+
+    pub fn submit(arguments: Value) -> Self {
+        Self::tool_call("submit", arguments)
+    }
+}
+
+/// 模型收到的一次请求
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub preamble: Option<String>,
+    pub prompt: Message,
+    pub chat_history: Vec<Message>,
+    pub tools: Vec<String>,
+}
+
+/// 按脚本依次返回输出的离线模型，用于在没有网络的情况下测试 agent
+#[derive(Clone, Default)]
+pub struct ScriptedCompletionModel {
+    turns: Arc<Mutex<VecDeque<ScriptedTurn>>>,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl ScriptedCompletionModel {
+    pub fn new(turns: impl IntoIterator<Item = ScriptedTurn>) -> Self {
+        Self {
+            turns: Arc::new(Mutex::new(turns.into_iter().collect())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// 已收到的请求记录
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// 尚未消费的脚本数量
+    pub fn remaining(&self) -> usize {
+        self.turns.lock().unwrap().len()
+    }
+}
+
+impl CompletionModel for ScriptedCompletionModel {
+    type Response = ();
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let index = {
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(RecordedRequest {
+                preamble: request.preamble.clone(),
+                prompt: request.prompt.clone(),
+                chat_history: request.chat_history.clone(),
+                tools: request.tools.iter().map(|tool| tool.name.clone()).collect(),
+            });
+            requests.len()
+        };
+
+        let turn = self.turns.lock().unwrap().pop_front();
+        let choice = match turn {
+            Some(ScriptedTurn::Text(text)) => OneOrMany::one(AssistantContent::text(text)),
+            Some(ScriptedTurn::ToolCalls(calls)) => {
+                let calls = calls
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (name, arguments))| {
+                        AssistantContent::ToolCall(ToolCall {
+                            id: format!("call_{}_{}", index, i),
+                            function: ToolFunction { name, arguments },
+                        })
+                    })
+                    .collect();
+                OneOrMany::many(calls).map_err(|_| {
+                    CompletionError::ResponseError("脚本中的工具调用为空".to_string())
+                })?
+            }
+            Some(ScriptedTurn::Error(message)) => {
+                return Err(CompletionError::ProviderError(message));
+            }
+            None => {
+                return Err(CompletionError::ResponseError(
+                    "脚本已耗尽，没有更多的模型输出".to_string(),
+                ));
+            }
+        };
+
+        Ok(CompletionResponse {
+            choice,
+            raw_response: (),
+        })
+    }
+}
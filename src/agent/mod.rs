@@ -1,5 +1,18 @@
 pub mod agent;
+pub mod budget;
+pub mod consensus;
+pub mod episodes;
+pub mod known_ids;
+#[cfg(test)]
+pub(crate) mod mock;
+pub mod prematch;
+pub mod pricing;
+pub mod prompt;
+pub mod provider;
 pub mod runner;
 mod tool_bgm_tv;
+mod tool_known_ids;
 mod tool_submit;
 mod tool_tmdb;
+pub mod transcript;
+pub mod verifier;
@@ -0,0 +1,406 @@
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use rig::tool::Tool;
+use tracing::info;
+
+use crate::agent::agent::{MatchCandidate, MatchResult};
+use crate::agent::tool_bgm_tv::{
+    BGM_SUBJECT_TYPE_ANIME, BgmTVSearchArgs, BgmTVSearchTool, InfoboxValue, Subject,
+};
+use crate::agent::tool_tmdb::{
+    TMDBMovieSearchArgs, TMDBMovieSearchTool, TMDBSearchArgs, TMDBSearchTool,
+};
+use crate::models::anime::Model as Anime;
+use crate::models::enums::{MediaType, Platform};
+
+/// 高置信度匹配所需的最低分数
+const MIN_TOTAL_SCORE: u8 = 90;
+/// 标题相似度的最低要求
+const MIN_TITLE_SCORE: f64 = 0.95;
+/// 开播日期相似度的最低要求
+const MIN_DATE_SCORE: f64 = 0.95;
+/// 最佳候选与次佳候选之间的最小分差，避免在多个相近候选中随意选择
+const MIN_SCORE_MARGIN: u8 = 10;
+
+const TITLE_WEIGHT: f64 = 0.6;
+const DATE_WEIGHT: f64 = 0.3;
+const EPISODE_WEIGHT: f64 = 0.1;
+
+/// 待评分的候选条目
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub id: i32,
+    pub name: String,
+    pub names: Vec<String>,
+    pub date: Option<NaiveDate>,
+    pub episodes: Option<i32>,
+}
+
+impl Candidate {
+    /// 从 BgmTV 条目构造候选，别名取自中文名与信息框
+    pub fn from_subject(subject: Subject) -> Self {
+        let mut names = vec![subject.name.clone()];
+        names.extend(subject.name_cn.clone().filter(|name| !name.is_empty()));
+        for item in &subject.infobox {
+            match &item.value {
+                InfoboxValue::String(value) => names.push(value.clone()),
+                InfoboxValue::Array(values) => {
+                    names.extend(values.iter().map(|value| value.v.clone()))
+                }
+            }
+        }
+        Candidate {
+            id: subject.id,
+            name: subject.name,
+            names,
+            date: subject.date,
+            episodes: Some(subject.eps).filter(|eps| *eps > 0),
+        }
+    }
+
+    fn to_match_candidate(&self, score: &CandidateScore, season: Option<i32>) -> MatchCandidate {
+        MatchCandidate {
+            id: self.id,
+            name: Some(self.name.clone()),
+            season,
+            confidence_score: Some(score.total as i32),
+            reason: Some(format!(
+                "预匹配: 标题相似度 {:.2}, 日期相似度 {:?}, 集数相似度 {:?}",
+                score.title, score.date, score.episodes
+            )),
+            source: Some("prematch".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateScore {
+    pub title: f64,
+    pub date: Option<f64>,
+    pub episodes: Option<f64>,
+    pub total: u8,
+}
+
+/// 预匹配所需的动画信息
+#[derive(Debug, Clone)]
+pub struct PreMatchQuery {
+    pub titles: Vec<String>,
+    pub year: i32,
+    pub start_date: Option<NaiveDate>,
+    pub episodes: Option<i32>,
+}
+
+impl PreMatchQuery {
+    pub fn from_anime(anime: &Anime) -> Result<Self> {
+        let titles: Vec<String> = serde_json::from_str(&anime.titles)?;
+        Ok(Self {
+            titles,
+            year: anime.year,
+            start_date: anime
+                .start_date
+                .as_deref()
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()),
+            episodes: anime.episode_count,
+        })
+    }
+}
+
+/// 不依赖模型的确定性匹配，只在结果足够明确时返回
+pub async fn prematch(platform: &Platform, anime: &Anime) -> Result<Option<MatchResult>> {
+    let query = PreMatchQuery::from_anime(anime)?;
+
+    let result = match platform {
+        Platform::BgmTv => {
+            let candidates = search_bgm_tv(&query).await?;
+            select_candidate(&query, &candidates).map(|(candidate, score)| {
+                MatchResult::from_candidate(candidate.to_match_candidate(&score, None))
+            })
+        }
+        Platform::Tmdb => {
+            // 非第一季需要确定季度信息，交给模型处理
+            if anime.season_number.is_some_and(|season| season > 1) {
+                return Ok(None);
+            }
+            let is_movie = anime.media_type == MediaType::Movie;
+            let candidates = if is_movie {
+                search_tmdb_movie(&query).await?
+            } else {
+                search_tmdb_tv(&query).await?
+            };
+            select_candidate(&query, &candidates).map(|(candidate, score)| {
+                let season = if is_movie { None } else { Some(1) };
+                MatchResult::from_candidate(candidate.to_match_candidate(&score, season))
+            })
+        }
+    };
+
+    if let Some(ref result) = result {
+        info!(
+            "预匹配成功: anilist_id={}, id={:?}, name={:?}, score={:?}",
+            anime.anilist_id, result.id, result.name, result.confidence_score
+        );
+    }
+
+    Ok(result)
+}
+
+async fn search_bgm_tv(query: &PreMatchQuery) -> Result<Vec<Candidate>> {
+    let tool = BgmTVSearchTool::new();
+    for title in query.titles.iter().take(2) {
+        let resp = tool
+            .call(BgmTVSearchArgs {
+                query: title.clone(),
+                start_date: Some(query.year.to_string()),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| anyhow!("BgmTV搜索失败: {}", e))?;
+
+        let candidates: Vec<Candidate> = resp
+            .data
+            .into_iter()
+            .filter(|subject| subject.subject_type == BGM_SUBJECT_TYPE_ANIME)
+            .map(Candidate::from_subject)
+            .collect();
+
+        if !candidates.is_empty() {
+            return Ok(candidates);
+        }
+    }
+    Ok(vec![])
+}
+
+async fn search_tmdb_tv(query: &PreMatchQuery) -> Result<Vec<Candidate>> {
+    let tool = TMDBSearchTool::new();
+    for title in query.titles.iter().take(2) {
+        let resp = tool
+            .call(TMDBSearchArgs {
+                query: title.clone(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| anyhow!("TMDB搜索失败: {}", e))?;
+
+        let candidates: Vec<Candidate> = resp
+            .data
+            .into_iter()
+            .map(|show| Candidate {
+                id: show.inner.id as i32,
+                name: show.inner.name.clone(),
+                names: vec![show.inner.name, show.inner.original_name],
+                date: show.inner.first_air_date,
+                episodes: None,
+            })
+            .collect();
+
+        if !candidates.is_empty() {
+            return Ok(candidates);
+        }
+    }
+    Ok(vec![])
+}
+
+async fn search_tmdb_movie(query: &PreMatchQuery) -> Result<Vec<Candidate>> {
+    let tool = TMDBMovieSearchTool::new();
+    for title in query.titles.iter().take(2) {
+        let resp = tool
+            .call(TMDBMovieSearchArgs {
+                query: title.clone(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| anyhow!("TMDB搜索失败: {}", e))?;
+
+        let candidates: Vec<Candidate> = resp
+            .data
+            .into_iter()
+            .map(|movie| Candidate {
+                id: movie.inner.id as i32,
+                name: movie.inner.title.clone(),
+                names: vec![movie.inner.title, movie.inner.original_title],
+                date: movie.inner.release_date,
+                episodes: None,
+            })
+            .collect();
+
+        if !candidates.is_empty() {
+            return Ok(candidates);
+        }
+    }
+    Ok(vec![])
+}
+
+/// 选出唯一的高置信度候选，不满足条件时返回None
+pub fn select_candidate<'a>(
+    query: &PreMatchQuery,
+    candidates: &'a [Candidate],
+) -> Option<(&'a Candidate, CandidateScore)> {
+    let mut scored: Vec<(&Candidate, CandidateScore)> = candidates
+        .iter()
+        .map(|candidate| (candidate, score_candidate(query, candidate)))
+        .collect();
+    scored.sort_by(|a, b| b.1.total.cmp(&a.1.total));
+
+    let mut iter = scored.into_iter();
+    let (best, best_score) = iter.next()?;
+    if let Some((_, second_score)) = iter.next() {
+        if best_score.total.saturating_sub(second_score.total) < MIN_SCORE_MARGIN {
+            return None;
+        }
+    }
+
+    let confident = best_score.total >= MIN_TOTAL_SCORE
+        && best_score.title >= MIN_TITLE_SCORE
+        && best_score.date.is_some_and(|date| date >= MIN_DATE_SCORE);
+    confident.then_some((best, best_score))
+}
+
+pub fn score_candidate(query: &PreMatchQuery, candidate: &Candidate) -> CandidateScore {
+    let title = query
+        .titles
+        .iter()
+        .flat_map(|title| {
+            candidate
+                .names
+                .iter()
+                .map(move |name| title_similarity(title, name))
+        })
+        .fold(0.0, f64::max);
+    let date = date_similarity(query.start_date, candidate.date);
+    let episodes = episode_similarity(query.episodes, candidate.episodes);
+
+    let mut weighted = title * TITLE_WEIGHT;
+    let mut weights = TITLE_WEIGHT;
+    if let Some(date) = date {
+        weighted += date * DATE_WEIGHT;
+        weights += DATE_WEIGHT;
+    }
+    if let Some(episodes) = episodes {
+        weighted += episodes * EPISODE_WEIGHT;
+        weights += EPISODE_WEIGHT;
+    }
+
+    CandidateScore {
+        title,
+        date,
+        episodes,
+        total: (weighted / weights * 100.0).round() as u8,
+    }
+}
+
+/// 归一化标题：全角转半角、转小写、去除空白与标点
+pub fn normalize_title(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            _ => c,
+        })
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// 基于字符二元组的 Dice 系数
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = normalize_title(a).chars().collect();
+    let b: Vec<char> = normalize_title(b).chars().collect();
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    if a.len() < 2 || b.len() < 2 {
+        return 0.0;
+    }
+
+    let mut b_bigrams: Vec<(char, char)> = b.windows(2).map(|w| (w[0], w[1])).collect();
+    let a_len = a.len() - 1;
+    let b_len = b_bigrams.len();
+    let mut intersection = 0;
+    for w in a.windows(2) {
+        if let Some(pos) = b_bigrams.iter().position(|item| *item == (w[0], w[1])) {
+            b_bigrams.swap_remove(pos);
+            intersection += 1;
+        }
+    }
+    (2 * intersection) as f64 / (a_len + b_len) as f64
+}
+
+/// 开播日期相差3天以内视为一致，超过90天视为完全不同
+fn date_similarity(a: Option<NaiveDate>, b: Option<NaiveDate>) -> Option<f64> {
+    let days = (a? - b?).num_days().abs() as f64;
+    Some(if days <= 3.0 {
+        1.0
+    } else {
+        (1.0 - (days - 3.0) / 87.0).max(0.0)
+    })
+}
+
+fn episode_similarity(a: Option<i32>, b: Option<i32>) -> Option<f64> {
+    let a = a.filter(|eps| *eps > 0)? as f64;
+    let b = b.filter(|eps| *eps > 0)? as f64;
+    Some(1.0 - (a - b).abs() / a.max(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query() -> PreMatchQuery {
+        PreMatchQuery {
+            titles: vec!["うずまき".to_string(), "Uzumaki".to_string()],
+            year: 2024,
+            start_date: NaiveDate::from_ymd_opt(2024, 9, 29),
+            episodes: Some(4),
+        }
+    }
+
+    fn candidate(id: i32, name: &str, date: Option<NaiveDate>) -> Candidate {
+        Candidate {
+            id,
+            name: name.to_string(),
+            names: vec![name.to_string()],
+            date,
+            episodes: Some(4),
+        }
+    }
+
+    #[test]
+    fn test_normalize_title() {
+        assert_eq!(
+            normalize_title("響け！ユーフォニアム３"),
+            "響けユーフォニアム3"
+        );
+        assert_eq!(normalize_title("Sound! Euphonium 3"), "soundeuphonium3");
+    }
+
+    #[test]
+    fn test_select_exact_match() {
+        let candidates = vec![
+            candidate(1, "うずまき", NaiveDate::from_ymd_opt(2024, 9, 28)),
+            candidate(2, "うずまき", NaiveDate::from_ymd_opt(2000, 1, 1)),
+        ];
+        let (best, score) = select_candidate(&query(), &candidates).unwrap();
+        assert_eq!(best.id, 1);
+        assert_eq!(score.total, 100);
+    }
+
+    #[test]
+    fn test_reject_ambiguous_match() {
+        let date = NaiveDate::from_ymd_opt(2024, 9, 29);
+        let candidates = vec![
+            candidate(1, "うずまき", date),
+            candidate(2, "うずまき", date),
+        ];
+        assert!(select_candidate(&query(), &candidates).is_none());
+    }
+
+    #[test]
+    fn test_reject_without_date() {
+        let candidates = vec![candidate(1, "うずまき", None)];
+        assert!(select_candidate(&query(), &candidates).is_none());
+    }
+}
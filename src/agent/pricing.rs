@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::budget::TokenUsage;
+
+const DEFAULT_PRICING_CONFIG: &str = "pricing.json";
+
+/// 模型单价，单位为美元/百万token
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.input + usage.completion_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+/// 模型价格表，键为 `provider/model` 或 `model`，前者优先
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceTable {
+    #[serde(default)]
+    pub prices: HashMap<String, ModelPrice>,
+}
+
+impl PriceTable {
+    /// 从 `PRICING_CONFIG` 指定的文件读取（默认 pricing.json），文件不存在时价格表为空
+    pub fn from_env() -> Result<Self> {
+        let path =
+            std::env::var("PRICING_CONFIG").unwrap_or_else(|_| DEFAULT_PRICING_CONFIG.to_string());
+        let path = Path::new(&path);
+        if path.exists() {
+            Self::from_file(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn get(&self, provider: &str, model: &str) -> Option<&ModelPrice> {
+        self.prices
+            .get(&format!("{}/{}", provider, model))
+            .or_else(|| self.prices.get(model))
+    }
+
+    /// 计算费用，价格表中没有该模型时返回None
+    pub fn cost(&self, provider: &str, model: &str, usage: &TokenUsage) -> Option<f64> {
+        self.get(provider, model).map(|price| price.cost(usage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost() {
+        let table: PriceTable = serde_json::from_str(
+            r#"{"prices": {
+                "gpt-4o": {"input": 2.5, "output": 10.0},
+                "openrouter/gpt-4o": {"input": 3.0, "output": 12.0}
+            }}"#,
+        )
+        .unwrap();
+        let usage = TokenUsage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 100_000,
+        };
+        assert_eq!(table.cost("openai", "gpt-4o", &usage), Some(3.5));
+        assert_eq!(table.cost("openrouter", "gpt-4o", &usage), Some(4.2));
+        assert_eq!(table.cost("openai", "unknown", &usage), None);
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Result, anyhow};
+use tracing::info;
+
+use crate::models::enums::Platform;
+
+const DEFAULT_PROMPTS_DIR: &str = "prompts";
+/// 内置的提示词版本，模板目录不存在时使用
+pub const BUILTIN_VERSION: &str = "v1";
+const DEFAULT_LANGUAGE: &str = "zh-CN";
+
+/// 一个版本中包含的提示词
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PromptKind {
+    MatchBgm,
+    MatchTmdb,
+    ExtractBgm,
+    ExtractTmdb,
+}
+
+impl PromptKind {
+    pub const ALL: [PromptKind; 4] = [
+        PromptKind::MatchBgm,
+        PromptKind::MatchTmdb,
+        PromptKind::ExtractBgm,
+        PromptKind::ExtractTmdb,
+    ];
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            PromptKind::MatchBgm => "match_bgm.md",
+            PromptKind::MatchTmdb => "match_tmdb.md",
+            PromptKind::ExtractBgm => "extract_bgm.md",
+            PromptKind::ExtractTmdb => "extract_tmdb.md",
+        }
+    }
+
+    fn builtin(&self) -> &'static str {
+        match self {
+            PromptKind::MatchBgm => include_str!("../../prompts/v1/match_bgm.md"),
+            PromptKind::MatchTmdb => include_str!("../../prompts/v1/match_tmdb.md"),
+            PromptKind::ExtractBgm => include_str!("../../prompts/v1/extract_bgm.md"),
+            PromptKind::ExtractTmdb => include_str!("../../prompts/v1/extract_tmdb.md"),
+        }
+    }
+}
+
+/// 模板中可用的变量，使用 `{{name}}` 引用
+#[derive(Debug, Clone, Default)]
+pub struct PromptVars {
+    vars: HashMap<String, String>,
+}
+
+impl PromptVars {
+    /// 内置变量：platform（平台名称）、language（输出语言）、metadata（已知的动画信息）
+    pub fn new(platform: &Platform, metadata: &str) -> Self {
+        let platform = match platform {
+            Platform::BgmTv => "Bangumi",
+            Platform::Tmdb => "TMDB",
+        };
+        let language =
+            std::env::var("PROMPT_LANGUAGE").unwrap_or_else(|_| DEFAULT_LANGUAGE.to_string());
+        Self::default()
+            .with("platform", platform)
+            .with("language", language)
+            .with("metadata", metadata)
+    }
+
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// 替换模板中的变量，未定义的变量保持原样
+    pub fn render(&self, template: &str) -> String {
+        let mut output = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            match after.find("}}") {
+                Some(end) => {
+                    let name = after[..end].trim();
+                    match self.vars.get(name) {
+                        Some(value) => output.push_str(value),
+                        None => output.push_str(&rest[start..start + end + 4]),
+                    }
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    output.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+        output.push_str(rest);
+        output
+    }
+}
+
+/// 某个版本的全部提示词模板
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    pub version: String,
+    templates: HashMap<PromptKind, String>,
+}
+
+/// 渲染后可直接交给 agent 使用的提示词
+#[derive(Debug, Clone)]
+pub struct RenderedPrompt {
+    pub version: String,
+    pub preamble: String,
+    pub extractor: String,
+}
+
+impl PromptTemplate {
+    pub fn builtin() -> Self {
+        Self {
+            version: BUILTIN_VERSION.to_string(),
+            templates: PromptKind::ALL
+                .into_iter()
+                .map(|kind| (kind, kind.builtin().to_string()))
+                .collect(),
+        }
+    }
+
+    /// 从 `{dir}/{version}/` 读取，缺失的文件使用内置模板
+    pub fn load(dir: &Path, version: &str) -> Result<Self> {
+        let mut templates = HashMap::new();
+        for kind in PromptKind::ALL {
+            let path = dir.join(version).join(kind.file_name());
+            let template = if path.exists() {
+                std::fs::read_to_string(&path)?
+            } else {
+                kind.builtin().to_string()
+            };
+            templates.insert(kind, template);
+        }
+        Ok(Self {
+            version: version.to_string(),
+            templates,
+        })
+    }
+
+    pub fn get(&self, kind: PromptKind) -> &str {
+        self.templates
+            .get(&kind)
+            .map(|template| template.as_str())
+            .unwrap_or_else(|| kind.builtin())
+    }
+
+    pub fn render(&self, platform: &Platform, vars: &PromptVars) -> RenderedPrompt {
+        let (preamble, extractor) = match platform {
+            Platform::BgmTv => (PromptKind::MatchBgm, PromptKind::ExtractBgm),
+            Platform::Tmdb => (PromptKind::MatchTmdb, PromptKind::ExtractTmdb),
+        };
+        RenderedPrompt {
+            version: self.version.clone(),
+            preamble: vars.render(self.get(preamble)),
+            extractor: vars.render(self.get(extractor)),
+        }
+    }
+}
+
+/// 所有可用的提示词版本
+#[derive(Debug, Clone)]
+pub struct PromptRegistry {
+    default_version: String,
+    templates: HashMap<String, PromptTemplate>,
+}
+
+impl Default for PromptRegistry {
+    fn default() -> Self {
+        let builtin = PromptTemplate::builtin();
+        Self {
+            default_version: builtin.version.clone(),
+            templates: HashMap::from([(builtin.version.clone(), builtin)]),
+        }
+    }
+}
+
+impl PromptRegistry {
+    /// 从 `PROMPTS_DIR` 指定的目录读取（默认 prompts），默认版本由 `PROMPT_VERSION` 指定
+    pub fn from_env() -> Result<Self> {
+        let dir = std::env::var("PROMPTS_DIR").unwrap_or_else(|_| DEFAULT_PROMPTS_DIR.to_string());
+        let mut registry = Self::from_dir(dir)?;
+        if let Ok(version) = std::env::var("PROMPT_VERSION") {
+            registry.get(Some(&version))?;
+            registry.default_version = version;
+        }
+        Ok(registry)
+    }
+
+    /// 目录下每个子目录是一个版本，目录不存在时只有内置版本
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut registry = Self::default();
+        if !dir.exists() {
+            info!("提示词目录不存在，使用内置提示词: {}", dir.display());
+            return Ok(registry);
+        }
+
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let version = entry.file_name().to_string_lossy().to_string();
+            let template = PromptTemplate::load(dir, &version)?;
+            registry.templates.insert(version, template);
+        }
+        Ok(registry)
+    }
+
+    pub fn default_version(&self) -> &str {
+        &self.default_version
+    }
+
+    /// 获取指定版本，未指定时返回默认版本
+    pub fn get(&self, version: Option<&str>) -> Result<&PromptTemplate> {
+        let version = version.unwrap_or(&self.default_version);
+        self.templates
+            .get(version)
+            .ok_or_else(|| anyhow!("提示词版本不存在: {}", version))
+    }
+
+    pub fn versions(&self) -> Vec<&str> {
+        let mut versions: Vec<&str> = self.templates.keys().map(|v| v.as_str()).collect();
+        versions.sort();
+        versions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let vars = PromptVars::default()
+            .with("platform", "TMDB")
+            .with("language", "zh-CN");
+        assert_eq!(
+            vars.render("match on {{platform}} in {{ language }}, {{unknown}} {{"),
+            "match on TMDB in zh-CN, {{unknown}} {{"
+        );
+
+        let prompt = PromptTemplate::builtin().render(&Platform::Tmdb, &vars);
+        assert_eq!(prompt.version, BUILTIN_VERSION);
+        assert!(prompt.preamble.contains("on TMDB"));
+        assert!(!prompt.preamble.contains("{{"));
+    }
+
+    #[test]
+    fn test_render_metadata() {
+        let metadata = r#"{"titles":"[\"平野と鍵浦\"]","known_ids":{"external_ids":{"mal":"1"}}}"#;
+        let vars = PromptVars::new(&Platform::BgmTv, metadata);
+        assert_eq!(
+            vars.render("{{platform}}: {{metadata}}"),
+            format!("Bangumi: {}", metadata)
+        );
+    }
+
+    #[test]
+    fn test_load_versions() {
+        let dir = std::env::temp_dir().join(format!("prompts-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("v2")).unwrap();
+        std::fs::write(dir.join("v2").join("match_bgm.md"), "v2 {{platform}}").unwrap();
+
+        let registry = PromptRegistry::from_dir(&dir).unwrap();
+        assert_eq!(registry.versions(), vec![BUILTIN_VERSION, "v2"]);
+
+        let template = registry.get(Some("v2")).unwrap();
+        let prompt = template.render(&Platform::BgmTv, &PromptVars::default());
+        assert_eq!(prompt.version, "v2");
+        assert_eq!(prompt.preamble, "v2 {{platform}}");
+        // 缺失的文件使用内置模板
+        assert_eq!(
+            template.get(PromptKind::ExtractBgm),
+            PromptKind::ExtractBgm.builtin()
+        );
+        assert_eq!(registry.get(None).unwrap().version, BUILTIN_VERSION);
+        assert!(registry.get(Some("v3")).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,337 @@
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{Result, anyhow};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use rig::providers::{deepseek, gemini, openai, openrouter, xai};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+const DEFAULT_PROVIDERS_CONFIG: &str = "providers.json";
+
+/// 模型提供方的客户端类型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// OpenAI 以及任意兼容 OpenAI 接口的服务（如 Ollama）
+    OpenAI,
+    Xai,
+    Gemini,
+    DeepSeek,
+    OpenRouter,
+}
+
+/// 单个 provider 的配置项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub name: String,
+    pub kind: ProviderKind,
+    /// 只有 openai 类型支持自定义地址
+    #[serde(default)]
+    pub base_url: Option<String>,
+    pub api_key_env: String,
+    pub default_model: String,
+    /// 每分钟最多发出的模型请求数，不设置时不限制
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+    /// 同时进行的匹配数上限，所有任务共享，不设置时不限制
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
+    #[serde(skip)]
+    limiter: Arc<OnceLock<ProviderLimiter>>,
+}
+
+impl ProviderConfig {
+    fn api_key(&self) -> Result<String> {
+        std::env::var(&self.api_key_env).map_err(|_| {
+            anyhow!(
+                "provider {} 的API key未设置，请设置环境变量 {}",
+                self.name,
+                self.api_key_env
+            )
+        })
+    }
+
+    /// 解析实际使用的模型，未指定时使用默认模型
+    pub fn resolve_model(&self, model: Option<&str>) -> String {
+        match model {
+            Some(model) if !model.is_empty() => model.to_string(),
+            _ => self.default_model.clone(),
+        }
+    }
+
+    /// provider 的限流器，首次使用时根据配置创建
+    pub fn limiter(&self) -> &ProviderLimiter {
+        self.limiter
+            .get_or_init(|| ProviderLimiter::new(self.requests_per_minute, self.max_concurrency))
+    }
+
+    pub fn client(&self) -> Result<LlmClient> {
+        let api_key = self.api_key()?;
+        let client = match self.kind {
+            ProviderKind::OpenAI => {
+                let base_url = self
+                    .base_url
+                    .clone()
+                    .unwrap_or_else(|| "https://api.openai.com/v1".to_string());
+                LlmClient::OpenAI(openai::Client::from_url(&api_key, &base_url))
+            }
+            ProviderKind::Xai => LlmClient::Xai(xai::Client::new(&api_key)),
+            ProviderKind::Gemini => LlmClient::Gemini(gemini::Client::new(&api_key)),
+            ProviderKind::DeepSeek => LlmClient::DeepSeek(deepseek::Client::new(&api_key)),
+            ProviderKind::OpenRouter => LlmClient::OpenRouter(openrouter::Client::new(&api_key)),
+        };
+        Ok(client)
+    }
+}
+
+/// 同一 provider 的所有匹配共享的请求频率和并发限制
+#[derive(Default)]
+pub struct ProviderLimiter {
+    requests: Option<Arc<DefaultDirectRateLimiter>>,
+    concurrency: Option<Semaphore>,
+}
+
+impl ProviderLimiter {
+    pub fn new(requests_per_minute: Option<u32>, max_concurrency: Option<usize>) -> Self {
+        Self {
+            requests: requests_per_minute
+                .and_then(NonZeroU32::new)
+                .map(|limit| Arc::new(RateLimiter::direct(Quota::per_minute(limit)))),
+            concurrency: max_concurrency.map(|limit| Semaphore::new(limit.max(1))),
+        }
+    }
+
+    /// 占用一个并发名额，名额用完时等待，未设置并发上限时直接返回
+    pub async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        match &self.concurrency {
+            Some(semaphore) => semaphore.acquire().await.ok(),
+            None => None,
+        }
+    }
+
+    /// 模型请求的频率限制，每次请求前等待
+    pub fn requests(&self) -> Option<Arc<DefaultDirectRateLimiter>> {
+        self.requests.clone()
+    }
+}
+
+impl std::fmt::Debug for ProviderLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderLimiter")
+            .field("requests", &self.requests.is_some())
+            .field("concurrency", &self.concurrency)
+            .finish()
+    }
+}
+
+/// 已构建好的模型客户端
+pub enum LlmClient {
+    OpenAI(openai::Client),
+    Xai(xai::Client),
+    Gemini(gemini::Client),
+    DeepSeek(deepseek::Client),
+    OpenRouter(openrouter::Client),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderRegistry {
+    providers: Vec<ProviderConfig>,
+}
+
+impl ProviderRegistry {
+    /// 从 `PROVIDERS_CONFIG` 指定的文件加载，文件不存在时使用内置默认配置
+    pub fn from_env() -> Result<Self> {
+        let path = std::env::var("PROVIDERS_CONFIG")
+            .unwrap_or_else(|_| DEFAULT_PROVIDERS_CONFIG.to_string());
+        let path = Path::new(&path);
+        if path.exists() {
+            Self::from_file(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_json(&content)
+    }
+
+    pub fn from_json(content: &str) -> Result<Self> {
+        let registry: ProviderRegistry = serde_json::from_str(content)?;
+        for (i, provider) in registry.providers.iter().enumerate() {
+            if registry.providers[..i]
+                .iter()
+                .any(|item| item.name == provider.name)
+            {
+                return Err(anyhow!("重复的provider: {}", provider.name));
+            }
+            if provider.base_url.is_some() && provider.kind != ProviderKind::OpenAI {
+                return Err(anyhow!(
+                    "provider {} 的类型 {:?} 不支持 base_url",
+                    provider.name,
+                    provider.kind
+                ));
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ProviderConfig> {
+        self.providers.iter().find(|provider| provider.name == name)
+    }
+
+    pub fn resolve(&self, name: &str) -> Result<&ProviderConfig> {
+        self.get(name)
+            .ok_or_else(|| anyhow!("不支持的provider: {}", name))
+    }
+
+    pub fn providers(&self) -> &[ProviderConfig] {
+        &self.providers
+    }
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        let provider =
+            |name: &str, kind, base_url: Option<&str>, api_key_env: &str, model: &str| {
+                ProviderConfig {
+                    name: name.to_string(),
+                    kind,
+                    base_url: base_url.map(|url| url.to_string()),
+                    api_key_env: api_key_env.to_string(),
+                    default_model: model.to_string(),
+                    requests_per_minute: None,
+                    max_concurrency: None,
+                    limiter: Default::default(),
+                }
+            };
+
+        let openai_base = std::env::var("OPENAI_API_CUSTOM_BASE")
+            .unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
+
+        Self {
+            providers: vec![
+                provider("xai", ProviderKind::Xai, None, "XAI_API_KEY", "grok-2"),
+                provider(
+                    "gemini",
+                    ProviderKind::Gemini,
+                    None,
+                    "GEMINI_API_KEY",
+                    "gemini-2.0-flash",
+                ),
+                provider(
+                    "deepseek",
+                    ProviderKind::DeepSeek,
+                    None,
+                    "DEEPSEEK_API_KEY",
+                    "deepseek-chat",
+                ),
+                provider(
+                    "openai",
+                    ProviderKind::OpenAI,
+                    Some(&openai_base),
+                    "OPENAI_API_KEY",
+                    "gpt-4o",
+                ),
+                provider(
+                    "openrouter",
+                    ProviderKind::OpenRouter,
+                    None,
+                    "OPENROUTER_API_KEY",
+                    "openai/gpt-4o",
+                ),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json() {
+        let registry = ProviderRegistry::from_json(
+            r#"{
+                "providers": [
+                    {
+                        "name": "ollama",
+                        "kind": "openai",
+                        "base_url": "http://localhost:11434/v1",
+                        "api_key_env": "OLLAMA_API_KEY",
+                        "default_model": "qwen2.5:14b"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let provider = registry.resolve("ollama").unwrap();
+        assert_eq!(provider.kind, ProviderKind::OpenAI);
+        assert_eq!(provider.resolve_model(None), "qwen2.5:14b");
+        assert_eq!(provider.resolve_model(Some("llama3")), "llama3");
+        assert!(registry.resolve("xai").is_err());
+        assert!(provider.limiter().requests().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_limiter() {
+        let registry = ProviderRegistry::from_json(
+            r#"{
+                "providers": [
+                    {
+                        "name": "deepseek",
+                        "kind": "deepseek",
+                        "api_key_env": "DEEPSEEK_API_KEY",
+                        "default_model": "deepseek-chat",
+                        "requests_per_minute": 60,
+                        "max_concurrency": 1
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let limiter = registry.resolve("deepseek").unwrap().limiter();
+        assert!(limiter.requests().is_some());
+        let permit = limiter.acquire().await;
+        assert!(permit.is_some());
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(10), limiter.acquire())
+                .await
+                .is_err()
+        );
+        drop(permit);
+        assert!(limiter.acquire().await.is_some());
+    }
+
+    #[test]
+    fn test_base_url_only_for_openai() {
+        let result = ProviderRegistry::from_json(
+            r#"{"providers": [{"name": "a", "kind": "xai", "base_url": "http://localhost", "api_key_env": "K", "default_model": "m"}]}"#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_missing_api_key() {
+        let registry = ProviderRegistry::from_json(
+            r#"{"providers": [{"name": "a", "kind": "xai", "api_key_env": "ANIME_MATCHER_MISSING_KEY", "default_model": "m"}]}"#,
+        )
+        .unwrap();
+        let error = registry.resolve("a").unwrap().client().err().unwrap();
+        assert!(error.to_string().contains("ANIME_MATCHER_MISSING_KEY"));
+    }
+
+    #[test]
+    fn test_duplicate_provider() {
+        let provider = r#"{"name": "a", "kind": "xai", "api_key_env": "K", "default_model": "m"}"#;
+        let result = ProviderRegistry::from_json(&format!(
+            r#"{{"providers": [{}, {}]}}"#,
+            provider, provider
+        ));
+        assert!(result.is_err());
+    }
+}
@@ -1,163 +1,455 @@
+use std::sync::Arc;
+use std::time::Instant;
+
 use anyhow::Result;
-use rig::providers::{deepseek, gemini, openai, openrouter, xai};
+use governor::DefaultDirectRateLimiter;
+use rig::agent::AgentBuilder;
+use rig::completion::CompletionModel;
+use rig::extractor::ExtractorBuilder;
+use serde_json::{Value, json};
 use tracing::error;
 
-use crate::agent::agent::{MatchResult, new_mapping_bgm_tv_agent, new_mapping_tmdb_agent};
+use crate::agent::agent::{
+    MatchResult, budget_exceeded, new_mapping_bgm_tv_agent, new_mapping_tmdb_agent,
+};
+use crate::agent::budget::{MatchBudget, TokenUsage, UsageReader};
+use crate::agent::known_ids::KnownIds;
+use crate::agent::prompt::{PromptTemplate, PromptVars, RenderedPrompt};
+use crate::agent::provider::{LlmClient, ProviderConfig};
+use crate::agent::transcript::MatchTranscript;
+use crate::models::enums::Platform;
 
-pub async fn run_mapping_bgm_tv_agent(
-    keywords: &str,
-    provider: &str,
+/// 匹配的执行参数
+#[derive(Debug, Clone)]
+pub struct MatchOptions {
+    pub budget: MatchBudget,
+    /// 失败时的最大尝试次数
+    pub retry_count: u32,
+    /// 两次尝试之间的等待秒数
+    pub retry_delay: u64,
+}
+
+impl MatchOptions {
+    pub fn new(budget: MatchBudget, retry_count: u32, retry_delay: u64) -> Self {
+        Self {
+            budget,
+            retry_count,
+            retry_delay,
+        }
+    }
+}
+
+/// 匹配的输入
+#[derive(Debug, Clone, Default)]
+pub struct MatchQuery {
+    /// 发送给模型的查询内容
+    pub keywords: String,
+    /// 其他平台已知的id，供模型交叉参考
+    pub known_ids: KnownIds,
+    /// 审核时被拒绝的条目id，搜索时排除
+    pub rejected_ids: Vec<String>,
+}
+
+impl MatchQuery {
+    pub fn new(keywords: impl Into<String>) -> Self {
+        Self {
+            keywords: keywords.into(),
+            known_ids: KnownIds::default(),
+            rejected_ids: Vec::new(),
+        }
+    }
+
+    pub fn with_known_ids(mut self, known_ids: KnownIds) -> Self {
+        self.known_ids = known_ids;
+        self
+    }
+
+    pub fn with_rejected_ids(mut self, rejected_ids: Vec<String>) -> Self {
+        self.rejected_ids = rejected_ids;
+        self
+    }
+
+    /// 提示词模板中的 metadata 变量：查询内容及已知id、已拒绝的条目
+    pub fn metadata(&self) -> String {
+        let mut metadata = match serde_json::from_str(&self.keywords) {
+            Ok(Value::Object(object)) => Value::Object(object),
+            _ => json!({ "keywords": self.keywords }),
+        };
+        if !self.known_ids.is_empty() {
+            metadata["known_ids"] = json!(self.known_ids);
+        }
+        if !self.rejected_ids.is_empty() {
+            metadata["rejected_ids"] = json!(self.rejected_ids);
+        }
+        metadata.to_string()
+    }
+}
+
+/// 单次匹配所需的参数
+struct MatchRequest<'a> {
+    platform: &'a Platform,
+    query: &'a MatchQuery,
+    provider: &'a str,
+    model: &'a str,
+    budget: &'a MatchBudget,
+    prompt: &'a RenderedPrompt,
+    rate_limiter: Option<Arc<DefaultDirectRateLimiter>>,
+}
+
+async fn match_with<M: CompletionModel>(
+    agent: AgentBuilder<M>,
+    extractor: ExtractorBuilder<MatchResult, M>,
+    usage_reader: UsageReader<M::Response>,
+    request: &MatchRequest<'_>,
+) -> (Result<MatchResult>, MatchTranscript) {
+    let query = request.query;
+    let agent = match request.platform {
+        Platform::BgmTv => new_mapping_bgm_tv_agent(
+            agent,
+            extractor,
+            request.prompt,
+            query.known_ids.clone(),
+            query.rejected_ids.clone(),
+        ),
+        Platform::Tmdb => new_mapping_tmdb_agent(
+            agent,
+            extractor,
+            request.prompt,
+            query.known_ids.clone(),
+            query.rejected_ids.clone(),
+        ),
+    };
+    let mut agent = agent
+        .with_budget(request.budget.clone())
+        .with_rate_limit(request.rate_limiter.clone())
+        .with_usage_reader(usage_reader);
+
+    let started = Instant::now();
+    let result = agent.match_anime(&request.query.keywords).await;
+
+    let transcript = MatchTranscript {
+        platform: request.platform.clone(),
+        provider: request.provider.to_string(),
+        model: request.model.to_string(),
+        prompt_version: request.prompt.version.clone(),
+        prompt: format!("{}\n\n{}", request.prompt.preamble, request.query.keywords),
+        tool_calls: agent.tool_calls().to_vec(),
+        output: agent.output().map(|output| output.to_string()),
+        result: result.as_ref().ok().cloned(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+        usage: agent.usage(),
+        cost: None,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    (result, transcript)
+}
+
+async fn match_once(
+    client: &LlmClient,
+    request: &MatchRequest<'_>,
+) -> (Result<MatchResult>, MatchTranscript) {
+    let model = request.model;
+    // 各 provider 在原始响应中报告 token 消耗的字段不同
+    match client {
+        LlmClient::OpenAI(c) => {
+            match_with(
+                c.agent(model),
+                c.extractor(model),
+                |r| {
+                    r.usage.as_ref().map(|u| {
+                        TokenUsage::new(
+                            u.prompt_tokens as u64,
+                            u.total_tokens.saturating_sub(u.prompt_tokens) as u64,
+                        )
+                    })
+                },
+                request,
+            )
+            .await
+        }
+        LlmClient::Xai(c) => {
+            match_with(
+                c.agent(model),
+                c.extractor(model),
+                |r| {
+                    Some(TokenUsage::new(
+                        r.usage.prompt_tokens as u64,
+                        r.usage.completion_tokens as u64,
+                    ))
+                },
+                request,
+            )
+            .await
+        }
+        LlmClient::Gemini(c) => {
+            match_with(
+                c.agent(model),
+                c.extractor(model),
+                |r| {
+                    r.usage_metadata.as_ref().map(|u| {
+                        TokenUsage::new(
+                            u.prompt_token_count as u64,
+                            u.candidates_token_count as u64,
+                        )
+                    })
+                },
+                request,
+            )
+            .await
+        }
+        LlmClient::DeepSeek(c) => {
+            match_with(
+                c.agent(model),
+                c.extractor(model),
+                |r| {
+                    Some(TokenUsage::new(
+                        r.usage.prompt_tokens as u64,
+                        r.usage.completion_tokens as u64,
+                    ))
+                },
+                request,
+            )
+            .await
+        }
+        LlmClient::OpenRouter(c) => {
+            match_with(
+                c.agent(model),
+                c.extractor(model),
+                |r| {
+                    r.usage.as_ref().map(|u| {
+                        TokenUsage::new(u.prompt_tokens as u64, u.completion_tokens as u64)
+                    })
+                },
+                request,
+            )
+            .await
+        }
+    }
+}
+
+pub async fn run_mapping_agent(
+    platform: Platform,
+    query: &MatchQuery,
+    provider: &ProviderConfig,
     model: &str,
-    retry_count: u32,
-    retry_delay: u64,
+    prompt: &PromptTemplate,
+    options: &MatchOptions,
+    transcripts: &mut Vec<MatchTranscript>,
 ) -> Result<MatchResult> {
     let mut attempts = 0;
-    let max_attempts = retry_count;
+    let max_attempts = options.retry_count;
     let mut result = None;
     let mut last_error = None;
 
+    let prompt = prompt.render(&platform, &PromptVars::new(&platform, &query.metadata()));
+    let request = MatchRequest {
+        platform: &platform,
+        query,
+        provider: &provider.name,
+        model,
+        budget: &options.budget,
+        prompt: &prompt,
+        rate_limiter: provider.limiter().requests(),
+    };
+
+    let client = provider.client()?;
     while attempts < max_attempts {
-        let r = match provider {
-            "xai" => {
-                let client = xai::Client::from_env();
-                let mut agent =
-                    new_mapping_bgm_tv_agent(client.agent(model), client.extractor(model));
-                agent.match_anime(&keywords).await
-            }
-            "gemini" => {
-                let client = gemini::Client::from_env();
-                let mut agent =
-                    new_mapping_bgm_tv_agent(client.agent(model), client.extractor(model));
-                agent.match_anime(&keywords).await
-            }
-            "deepseek" => {
-                let client = deepseek::Client::from_env();
-                let mut agent =
-                    new_mapping_bgm_tv_agent(client.agent(model), client.extractor(model));
-                agent.match_anime(&keywords).await
-            }
-            "openai" => {
-                let api_key = std::env::var("OPENAI_API_KEY").unwrap_or_else(|_| "".to_string());
-                let api_base = std::env::var("OPENAI_API_CUSTOM_BASE")
-                    .unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
-                let client = openai::Client::from_url(&api_key, &api_base);
-                let mut agent =
-                    new_mapping_bgm_tv_agent(client.agent(model), client.extractor(model));
-                agent.match_anime(&keywords).await
-            }
-            "openrouter" => {
-                let client = openrouter::Client::from_env();
-                let mut agent =
-                    new_mapping_bgm_tv_agent(client.agent(model), client.extractor(model));
-                agent.match_anime(&keywords).await
-            }
-            _ => {
-                return Err(anyhow::anyhow!("不支持的provider: {}", provider));
-            }
+        let (r, transcript) = {
+            // 占用 provider 的并发名额，等待重试期间不占用
+            let _permit = provider.limiter().acquire().await;
+            match_once(&client, &request).await
         };
-
+        transcripts.push(transcript);
         match r {
             Ok(r) => {
                 result = Some(r);
                 break;
             }
+            // 超出预算时重试只会继续消耗，直接返回
+            Err(e) if budget_exceeded(&e).is_some() => return Err(e),
             Err(e) => {
                 error!(
-                    "mapping_bgm_tv_agent 失败:{}, 等待后重试 ({}/{})",
-                    e, attempts, max_attempts
+                    "mapping_agent({:?}) 失败:{}, 等待后重试 ({}/{})",
+                    platform, e, attempts, max_attempts
                 );
                 last_error = Some(e);
             }
         }
 
         attempts += 1;
-        tokio::time::sleep(std::time::Duration::from_secs(retry_delay)).await;
+        tokio::time::sleep(std::time::Duration::from_secs(options.retry_delay)).await;
     }
 
     result.ok_or_else(|| {
         anyhow::anyhow!(
-            "mapping_bgm_tv_agent 失败，尝试了 {} 次, error: {:?}",
+            "mapping_agent({:?}) 失败，尝试了 {} 次, error: {:?}",
+            platform,
             max_attempts,
             last_error
         )
     })
 }
 
+pub async fn run_mapping_bgm_tv_agent(
+    keywords: &str,
+    provider: &ProviderConfig,
+    model: &str,
+    prompt: &PromptTemplate,
+    options: &MatchOptions,
+) -> Result<MatchResult> {
+    run_mapping_agent(
+        Platform::BgmTv,
+        &MatchQuery::new(keywords),
+        provider,
+        model,
+        prompt,
+        options,
+        &mut Vec::new(),
+    )
+    .await
+}
+
 pub async fn run_mapping_tmdb_agent(
     keywords: &str,
-    provider: &str,
+    provider: &ProviderConfig,
     model: &str,
-    retry_count: u32,
-    retry_delay: u64,
+    prompt: &PromptTemplate,
+    options: &MatchOptions,
 ) -> Result<MatchResult> {
-    let mut attempts = 0;
-    let max_attempts = retry_count;
-    let mut result = None;
-    let mut last_error = None;
+    run_mapping_agent(
+        Platform::Tmdb,
+        &MatchQuery::new(keywords),
+        provider,
+        model,
+        prompt,
+        options,
+        &mut Vec::new(),
+    )
+    .await
+}
 
-    while attempts < max_attempts {
-        let r = match provider {
-            "xai" => {
-                let client = xai::Client::from_env();
-                let mut agent =
-                    new_mapping_tmdb_agent(client.agent(model), client.extractor(model));
-                agent.match_anime(&keywords).await
-            }
-            "gemini" => {
-                let client = gemini::Client::from_env();
-                let mut agent =
-                    new_mapping_tmdb_agent(client.agent(model), client.extractor(model));
-                agent.match_anime(&keywords).await
-            }
-            "deepseek" => {
-                let client = deepseek::Client::from_env();
-                let mut agent =
-                    new_mapping_tmdb_agent(client.agent(model), client.extractor(model));
-                agent.match_anime(&keywords).await
-            }
-            "openai" => {
-                let api_key = std::env::var("OPENAI_API_KEY").unwrap_or_else(|_| "".to_string());
-                let api_base = std::env::var("OPENAI_API_CUSTOM_BASE")
-                    .unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
-                let client = openai::Client::from_url(&api_key, &api_base);
-                let mut agent =
-                    new_mapping_tmdb_agent(client.agent(model), client.extractor(model));
-                agent.match_anime(&keywords).await
-            }
-            "openrouter" => {
-                let client = openrouter::Client::from_env();
-                let mut agent =
-                    new_mapping_tmdb_agent(client.agent(model), client.extractor(model));
-                agent.match_anime(&keywords).await
-            }
-            _ => {
-                return Err(anyhow::anyhow!("不支持的provider: {}", provider));
-            }
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::known_ids::KnownMapping;
+    use crate::agent::mock::{ScriptedCompletionModel, ScriptedTurn};
+    use crate::http::cassette::install_test_replay;
 
-        match r {
-            Ok(r) => {
-                result = Some(r);
-                break;
-            }
-            Err(e) => {
-                error!(
-                    "mapping_tmdb_agent 失败:{}, 等待后重试 ({}/{})",
-                    e, attempts, max_attempts
-                );
-                last_error = Some(e);
-            }
-        }
+    #[test]
+    fn test_query_metadata() {
+        let query = MatchQuery::new(r#"{"titles":"[\"平野と鍵浦\"]","year":2025}"#)
+            .with_known_ids(KnownIds {
+                mappings: vec![KnownMapping {
+                    platform: Platform::Tmdb,
+                    id: "300001".to_string(),
+                }],
+                ..Default::default()
+            })
+            .with_rejected_ids(vec!["500001".to_string()]);
+        let metadata: Value = serde_json::from_str(&query.metadata()).unwrap();
+        assert_eq!(metadata["year"], 2025);
+        assert_eq!(metadata["known_ids"]["mappings"][0]["id"], "300001");
+        assert_eq!(metadata["rejected_ids"], json!(["500001"]));
 
-        attempts += 1;
-        tokio::time::sleep(std::time::Duration::from_secs(retry_delay)).await;
+        let metadata: Value =
+            serde_json::from_str(&MatchQuery::new("平野と鍵浦").metadata()).unwrap();
+        assert_eq!(metadata, json!({ "keywords": "平野と鍵浦" }));
     }
 
-    result.ok_or_else(|| {
-        anyhow::anyhow!(
-            "mapping_tmdb_agent 失败，尝试了 {} 次, error: {:?}",
-            max_attempts,
-            last_error
+    async fn replay_match(
+        platform: Platform,
+        keywords: &str,
+        model: &ScriptedCompletionModel,
+    ) -> (Result<MatchResult>, MatchTranscript) {
+        install_test_replay();
+
+        let query = MatchQuery::new(keywords);
+        let prompt = PromptTemplate::builtin()
+            .render(&platform, &PromptVars::new(&platform, &query.metadata()));
+        let budget = MatchBudget::unlimited();
+        let request = MatchRequest {
+            platform: &platform,
+            query: &query,
+            provider: "mock",
+            model: "scripted",
+            budget: &budget,
+            prompt: &prompt,
+            rate_limiter: None,
+        };
+        match_with(
+            AgentBuilder::new(model.clone()),
+            ExtractorBuilder::new(model.clone()),
+            |_| None,
+            &request,
         )
-    })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_bgm_tv_pipeline_replay() {
+        let model = ScriptedCompletionModel::new([
+            ScriptedTurn::tool_call(
+                "bgm_tv_search",
+                json!({"query": "平野と鍵浦", "start_date": "2023"}),
+            ),
+            ScriptedTurn::tool_call("bgm_tv_subject", json!({"id": 100001})),
+            ScriptedTurn::submit(
+                json!({"id": 100001, "name": "平野と鍵浦", "confidence_score": 95}),
+            ),
+        ]);
+
+        let (result, transcript) = replay_match(Platform::BgmTv, "平野と鍵浦", &model).await;
+        let result = result.unwrap();
+        assert_eq!(result.id, Some(100001));
+        assert_eq!(model.remaining(), 0);
+
+        // 记录中保存完整的提示词
+        let preamble = model.requests()[0].preamble.clone().unwrap();
+        assert!(transcript.prompt.starts_with(&preamble));
+        assert!(transcript.prompt.ends_with("平野と鍵浦"));
+
+        // 工具结果来自磁带，并如实记录在匹配记录中
+        assert_eq!(transcript.tool_calls.len(), 3);
+        assert!(
+            transcript.tool_calls[..2]
+                .iter()
+                .all(|call| call.error.is_none())
+        );
+        assert!(
+            transcript.tool_calls[0]
+                .result
+                .as_deref()
+                .unwrap()
+                .contains("平野と鍵浦")
+        );
+        assert!(
+            transcript.tool_calls[1]
+                .result
+                .as_deref()
+                .unwrap()
+                .contains(r#""platform":"TV""#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tmdb_pipeline_replay() {
+        let model = ScriptedCompletionModel::new([
+            ScriptedTurn::tool_call("tmdb_search_tv_show", json!({"query": "平野と鍵浦"})),
+            ScriptedTurn::submit(json!({
+                "id": 300001,
+                "name": "平野与键浦",
+                "season": 1,
+                "confidence_score": 90
+            })),
+        ]);
+
+        let (result, transcript) = replay_match(Platform::Tmdb, "平野と鍵浦", &model).await;
+        let result = result.unwrap();
+        assert_eq!(result.id, Some(300001));
+        assert_eq!(result.season, Some(1));
+
+        let search = &transcript.tool_calls[0];
+        assert_eq!(search.error, None);
+        assert!(search.result.as_deref().unwrap().contains("300001"));
+    }
 }
@@ -1,14 +1,92 @@
 use chrono::NaiveDate;
-use reqwest::header::USER_AGENT;
 use rig::{completion::ToolDefinition, tool::Tool};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::json;
-use tokio_retry::{Retry, strategy::FixedInterval};
-#[derive(Deserialize, Serialize)]
+use tracing::debug;
+
+use crate::http::client::HttpClient;
+use crate::http::{Service, cache};
+
+/// 搜索结果每页的最大数量
+const MAX_SEARCH_LIMIT: u32 = 25;
+
+/// 获取章节列表时每页的数量
+const EPISODES_PAGE_SIZE: usize = 100;
+
+/// BgmTV 中动画条目的类型
+pub const BGM_SUBJECT_TYPE_ANIME: i32 = 2;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct BgmTVSearchArgs {
-    query: String,
-    start_date: Option<String>,
-    end_date: Option<String>,
+    pub query: String,
+    /// 放送日期下限（包含），如 2024 或 2024-04-01
+    pub start_date: Option<String>,
+    /// 放送日期上限（包含）
+    pub end_date: Option<String>,
+    /// 条目类型，不指定时只搜索动画
+    #[serde(default)]
+    pub subject_types: Option<Vec<i32>>,
+    /// 标签，条目需同时包含所有标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 排名范围
+    pub min_rank: Option<i32>,
+    pub max_rank: Option<i32>,
+    /// 排序方式：match、heat、rank、score，默认 rank
+    pub sort: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+impl BgmTVSearchArgs {
+    /// 构造请求体中的 filter
+    fn filter(&self) -> serde_json::Value {
+        let mut air_date = vec![];
+        if let Some(start_date) = &self.start_date {
+            air_date.push(format!(">={}", start_date));
+        }
+        if let Some(end_date) = &self.end_date {
+            // 只有年份时包含该年全年
+            if end_date.len() == 4 && end_date.chars().all(|c| c.is_ascii_digit()) {
+                air_date.push(format!("<={}-12-31", end_date));
+            } else {
+                air_date.push(format!("<={}", end_date));
+            }
+        }
+
+        let mut rank = vec![];
+        if let Some(min_rank) = self.min_rank {
+            rank.push(format!(">={}", min_rank));
+        }
+        if let Some(max_rank) = self.max_rank {
+            rank.push(format!("<={}", max_rank));
+        }
+
+        let mut filter = json!({
+            "nsfw": true,
+            "air_date": air_date,
+            "type": self
+                .subject_types
+                .clone()
+                .unwrap_or_else(|| vec![BGM_SUBJECT_TYPE_ANIME]),
+        });
+        if !self.tags.is_empty() {
+            filter["tag"] = json!(self.tags);
+        }
+        if !rank.is_empty() {
+            filter["rank"] = json!(rank);
+        }
+        filter
+    }
+
+    /// 构造搜索请求体，排序方式与关键词同在顶层
+    fn body(&self, keyword: &str) -> serde_json::Value {
+        json!({
+            "keyword": keyword,
+            "sort": self.sort.as_deref().unwrap_or("rank"),
+            "filter": self.filter(),
+        })
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -26,6 +104,17 @@ pub struct PageResponse<T> {
     pub data: Vec<T>,
 }
 
+impl PageResponse<Subject> {
+    /// 去掉审核时被拒绝的条目，总数同步减少，保持分页提示一致
+    fn exclude(&mut self, rejected_ids: &[String]) {
+        let len = self.data.len();
+        self.data
+            .retain(|subject| !rejected_ids.contains(&subject.id.to_string()));
+        let removed = (len - self.data.len()) as i32;
+        self.pagination.total = (self.pagination.total - removed).max(0);
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
 #[serde(default)]
 pub struct Subject {
@@ -34,6 +123,8 @@ pub struct Subject {
     pub subject_type: i32,
     pub name: String,
     pub name_cn: Option<String>,
+    /// 放送平台，如 TV、剧场版、OVA、WEB
+    pub platform: Option<String>,
     pub series: bool,
     pub date: Option<NaiveDate>,
     pub eps: i32,
@@ -66,16 +157,148 @@ pub struct InfoboxArrayItem {
     pub v: String,
 }
 
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(default)]
+pub struct Episode {
+    pub id: i32,
+    /// 章节类型：0 本篇，1 SP，2 OP，3 ED
+    #[serde(rename = "type")]
+    pub episode_type: i32,
+    pub name: String,
+    pub name_cn: String,
+    /// 在整个系列中的序号
+    pub sort: f32,
+    /// 在本条目中的集数
+    pub ep: Option<f32>,
+    pub airdate: String,
+    pub duration: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(default)]
+pub struct RelatedSubject {
+    pub id: i32,
+    #[serde(rename = "type")]
+    pub subject_type: i32,
+    pub name: String,
+    pub name_cn: String,
+    /// 关联关系，如 前传、续集、番外篇、总集篇
+    pub relation: String,
+}
+
 pub struct BgmTVSearchTool {
-    client: reqwest::Client,
+    http: &'static HttpClient,
     base_url: String,
+    /// 审核时被拒绝的条目id，不出现在搜索结果中
+    rejected_ids: Vec<String>,
 }
 
 impl BgmTVSearchTool {
     pub fn new() -> Self {
-        let base_url = std::env::var("BGM_API_URL").unwrap_or("https://api.bgm.tv".to_string());
-        let client = reqwest::Client::new();
-        Self { client, base_url }
+        Self::with_base_url(Service::BgmTv.base_url())
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            http: HttpClient::shared(Service::BgmTv),
+            base_url: base_url.into(),
+            rejected_ids: Vec::new(),
+        }
+    }
+
+    pub fn with_rejected_ids(mut self, rejected_ids: Vec<String>) -> Self {
+        self.rejected_ids = rejected_ids;
+        self
+    }
+}
+
+impl BgmTVSearchTool {
+    /// GET 请求，资源不存在时返回None，响应按 `endpoint` 缓存
+    async fn get<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Option<T>, BgmTVError> {
+        let url = format!("{}{}", self.base_url, path);
+        let params = json!({ "path": path, "query": query });
+
+        let body = cache::cached_text(endpoint, &params, || async {
+            let response = self
+                .http
+                .send(self.http.get(&url).query(query))
+                .await
+                .map_err(|e| BgmTVError::new(format!("请求错误: {}", e)))?;
+
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            if !response.status().is_success() {
+                return Err(BgmTVError::new(format!("请求失败: {}", response.status())));
+            }
+
+            response
+                .text()
+                .await
+                .map(Some)
+                .map_err(|e| BgmTVError::new(format!("读取响应错误: {}", e)))
+        })
+        .await?;
+
+        body.map(|body| serde_json::from_str::<T>(&body))
+            .transpose()
+            .map_err(|e| BgmTVError::new(format!("解析响应错误: {}", e)))
+    }
+
+    /// 获取条目详情，条目不存在时返回None
+    pub async fn get_subject(&self, id: i32) -> Result<Option<Subject>, BgmTVError> {
+        self.get("bgm_tv/subject", &format!("/v0/subjects/{}", id), &[])
+            .await
+    }
+
+    /// 获取条目的全部章节，条目不存在时返回空列表
+    pub async fn get_episodes(&self, subject_id: i32) -> Result<Vec<Episode>, BgmTVError> {
+        let mut episodes = Vec::new();
+        loop {
+            let page: Option<PageResponse<Episode>> = self
+                .get(
+                    "bgm_tv/episodes",
+                    "/v0/episodes",
+                    &[
+                        ("subject_id", subject_id.to_string()),
+                        ("limit", EPISODES_PAGE_SIZE.to_string()),
+                        ("offset", episodes.len().to_string()),
+                    ],
+                )
+                .await?;
+            let Some(page) = page else {
+                break;
+            };
+            let total = page.pagination.total.max(0) as usize;
+            let is_empty = page.data.is_empty();
+            episodes.extend(page.data);
+            // 长篇连载的章节数超过一页
+            if is_empty || episodes.len() >= total {
+                break;
+            }
+        }
+        Ok(episodes)
+    }
+
+    /// 获取与条目关联的动画条目（前传、续集、番外篇等）
+    pub async fn get_related_subjects(&self, id: i32) -> Result<Vec<RelatedSubject>, BgmTVError> {
+        let subjects: Option<Vec<RelatedSubject>> = self
+            .get(
+                "bgm_tv/relations",
+                &format!("/v0/subjects/{}/subjects", id),
+                &[],
+            )
+            .await?;
+        Ok(subjects
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|subject| subject.subject_type == BGM_SUBJECT_TYPE_ANIME)
+            .collect())
     }
 }
 
@@ -111,12 +334,47 @@ impl Tool for BgmTVSearchTool {
                         "type": "string",
                         "description": "The search query for bgm tv"
                     },
-                    "start_air_year": {
+                    "start_date": {
+                        "type": "string",
+                        "description": "Only return subjects aired on or after this date, as a year or a date, example: 2024 or 2024-04-01"
+                    },
+                    "end_date": {
                         "type": "string",
-                        "description": "The start year for the search, example: 2024"
+                        "description": "Only return subjects aired on or before this date, as a year or a date, example: 2024-06-30"
+                    },
+                    "subject_types": {
+                        "type": "array",
+                        "items": { "type": "integer" },
+                        "description": "Subject types to search: 1 book, 2 anime, 3 music, 4 game, 6 real. Defaults to [2] (anime only)"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only return subjects having all of these tags, example: [\"TV\", \"原创\"]"
+                    },
+                    "min_rank": {
+                        "type": "integer",
+                        "description": "Only return subjects whose rank number is at least this value"
+                    },
+                    "max_rank": {
+                        "type": "integer",
+                        "description": "Only return subjects whose rank number is at most this value, example: 500 for the top 500"
+                    },
+                    "sort": {
+                        "type": "string",
+                        "enum": ["match", "heat", "rank", "score"],
+                        "description": "Sort order, defaults to rank"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Number of results per page, defaults to 10, at most 25"
+                    },
+                    "offset": {
+                        "type": "integer",
+                        "description": "Offset of the first result, use it to fetch the next page"
                     },
                 },
-                "required": ["query", "start_air_year"]
+                "required": ["query"]
             }),
         }
     }
@@ -129,58 +387,57 @@ impl Tool for BgmTVSearchTool {
             .collect::<Vec<&str>>()
             .join("+");
         let base_url = self.base_url.clone();
-        let client = self.client.clone();
+        let http = self.http;
+        let limit = args
+            .limit
+            .unwrap_or(10)
+            .clamp(1, MAX_SEARCH_LIMIT)
+            .to_string();
+        let offset = args.offset.unwrap_or_default().to_string();
+        let rejected_ids = self.rejected_ids.clone();
 
         // 使用spawn_blocking来处理阻塞操作
         tokio::spawn(async move {
             let url = format!("{}/v0/search/subjects", base_url);
 
-            let mut date_list = vec![];
-            if let Some(start_date) = args.start_date {
-                date_list.push(format!(">={}", start_date));
-            }
-            // 创建搜索过滤器
-            let search_query = json!({
-                "keyword": query,
-                "filter": {
-                    "sort": "rank",
-                    "nsfw": true,
-                    "air_date": date_list,
-                }
-            });
-            println!("{:?}", search_query);
+            let search_query = args.body(&query);
+            debug!("BgmTV搜索: {}", search_query);
 
             let body = match serde_json::to_string(&search_query) {
                 Ok(b) => b,
                 Err(e) => return Err(BgmTVError::new(format!("JSON序列化错误: {}", e))),
             };
 
-            // 使用tokio-retry实现重试逻辑
-            let retry_strategy = FixedInterval::from_millis(5000).take(5);
-
-            let response = match Retry::spawn(retry_strategy, || async {
-                client
+            let params = json!({
+                "search": search_query,
+                "limit": limit,
+                "offset": offset,
+            });
+            let response_text = cache::cached_text("bgm_tv/search", &params, || async {
+                let request = http
                     .post(&url)
-                    .header(USER_AGENT, "lyqingye/anime-matcher-agent")
-                    .query(&[("limit", "10"), ("offset", "0")])
-                    .body(body.clone())
-                    .send()
+                    .query(&[("limit", &limit), ("offset", &offset)])
+                    .body(body);
+                let response = http
+                    .send(request)
                     .await
-            })
-            .await
-            {
-                Ok(resp) => resp,
-                Err(e) => return Err(BgmTVError::new(format!("请求错误，已重试多次: {}", e))),
-            };
+                    .map_err(|e| BgmTVError::new(format!("请求错误: {}", e)))?;
+                if !response.status().is_success() {
+                    return Err(BgmTVError::new(format!("请求失败: {}", response.status())));
+                }
 
-            let response_text = match response.text().await {
-                Ok(text) => text,
-                Err(e) => return Err(BgmTVError::new(format!("读取响应错误: {}", e))),
-            };
-            println!("{}", response_text);
+                response
+                    .text()
+                    .await
+                    .map(Some)
+                    .map_err(|e| BgmTVError::new(format!("读取响应错误: {}", e)))
+            })
+            .await?
+            .unwrap_or_default();
 
             match serde_json::from_str::<PageResponse<Subject>>(&response_text) {
                 Ok(mut resp) => {
+                    resp.exclude(&rejected_ids);
                     resp.data.iter_mut().for_each(|item| {
                         item.infobox.retain(|item| {
                             item.key == "中文名" || item.key == "别名" || item.key == "英文名"
@@ -196,9 +453,167 @@ impl Tool for BgmTVSearchTool {
     }
 }
 
+#[derive(Deserialize, Serialize)]
+pub struct BgmTVSubjectArgs {
+    pub id: i32,
+}
+
+/// 查询条目详情（类型、放送平台、集数、放送日期、完整的 infobox）
+pub struct BgmTVSubjectTool {
+    api: BgmTVSearchTool,
+}
+
+impl BgmTVSubjectTool {
+    pub fn new() -> Self {
+        Self {
+            api: BgmTVSearchTool::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            api: BgmTVSearchTool::with_base_url(base_url),
+        }
+    }
+}
+
+impl Tool for BgmTVSubjectTool {
+    const NAME: &'static str = "bgm_tv_subject";
+
+    type Error = BgmTVError;
+    type Args = BgmTVSubjectArgs;
+    type Output = Subject;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Get the details of a BgmTV subject, including type, platform (TV, movie, OVA, WEB), number of episodes, air date and the full infobox".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "The BgmTV subject id"
+                    },
+                },
+                "required": ["id"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.api
+            .get_subject(args.id)
+            .await?
+            .ok_or_else(|| BgmTVError::new(format!("条目不存在: {}", args.id)))
+    }
+}
+
+/// 查询条目的章节列表
+pub struct BgmTVEpisodesTool {
+    api: BgmTVSearchTool,
+}
+
+impl BgmTVEpisodesTool {
+    pub fn new() -> Self {
+        Self {
+            api: BgmTVSearchTool::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            api: BgmTVSearchTool::with_base_url(base_url),
+        }
+    }
+}
+
+impl Tool for BgmTVEpisodesTool {
+    const NAME: &'static str = "bgm_tv_episodes";
+
+    type Error = BgmTVError;
+    type Args = BgmTVSubjectArgs;
+    type Output = Vec<Episode>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "List the episodes of a BgmTV subject with their numbers and air dates. `sort` is the number within the whole series and `ep` is the number within this subject, which tells a second cour apart from the first".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "The BgmTV subject id"
+                    },
+                },
+                "required": ["id"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.api.get_episodes(args.id).await
+    }
+}
+
+/// 查询与条目关联的动画条目
+pub struct BgmTVRelationsTool {
+    api: BgmTVSearchTool,
+}
+
+impl BgmTVRelationsTool {
+    pub fn new() -> Self {
+        Self {
+            api: BgmTVSearchTool::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            api: BgmTVSearchTool::with_base_url(base_url),
+        }
+    }
+}
+
+impl Tool for BgmTVRelationsTool {
+    const NAME: &'static str = "bgm_tv_relations";
+
+    type Error = BgmTVError;
+    type Args = BgmTVSubjectArgs;
+    type Output = Vec<RelatedSubject>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "List the anime subjects related to a BgmTV subject, such as prequel (前传), sequel (续集), side story (番外篇) and compilation (总集篇)".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "The BgmTV subject id"
+                    },
+                },
+                "required": ["id"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.api.get_related_subjects(args.id).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::cassette::{
+        CassetteMode, CassetteServer, Interaction, RecordedRequest, RecordedResponse,
+    };
 
     #[tokio::test]
     async fn test_bgm_tv_search() {
@@ -206,9 +621,177 @@ mod tests {
         let args = BgmTVSearchArgs {
             query: "平野と鍵浦".to_string(),
             start_date: Some("2023".to_string()),
-            end_date: None,
+            ..Default::default()
         };
         let result = tool.call(args).await.unwrap();
         println!("{:?}", result);
     }
+
+    #[test]
+    fn test_search_body() {
+        let args = BgmTVSearchArgs {
+            query: "test".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            args.body("test"),
+            json!({
+                "keyword": "test",
+                "sort": "rank",
+                "filter": {"nsfw": true, "air_date": [], "type": [2]},
+            })
+        );
+
+        let args = BgmTVSearchArgs {
+            query: "test".to_string(),
+            start_date: Some("2024-04-01".to_string()),
+            end_date: Some("2024-06-30".to_string()),
+            subject_types: Some(vec![2, 1]),
+            tags: vec!["TV".to_string()],
+            max_rank: Some(500),
+            sort: Some("match".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            args.body("test"),
+            json!({
+                "keyword": "test",
+                "sort": "match",
+                "filter": {
+                    "nsfw": true,
+                    "air_date": [">=2024-04-01", "<=2024-06-30"],
+                    "type": [2, 1],
+                    "tag": ["TV"],
+                    "rank": ["<=500"],
+                },
+            })
+        );
+
+        // 只有年份的结束日期包含全年
+        let args = BgmTVSearchArgs {
+            query: "test".to_string(),
+            start_date: Some("2024".to_string()),
+            end_date: Some("2024".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(args.filter()["air_date"], json!([">=2024", "<=2024-12-31"]));
+    }
+
+    #[test]
+    fn test_exclude_rejected() {
+        let subject = |id| Subject {
+            id,
+            ..Default::default()
+        };
+        let mut resp = PageResponse {
+            pagination: Pagination {
+                total: 30,
+                limit: 25,
+                offset: 0,
+            },
+            data: vec![subject(1), subject(2), subject(3)],
+        };
+        resp.exclude(&["2".to_string(), "4".to_string()]);
+        let ids: Vec<i32> = resp.data.iter().map(|subject| subject.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(resp.pagination.total, 29);
+    }
+
+    #[tokio::test]
+    async fn test_bgm_tv_search_replay() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("cassettes");
+        let server = CassetteServer::start(CassetteMode::Replay, dir)
+            .await
+            .unwrap();
+
+        let tool = BgmTVSearchTool::with_base_url(server.base_url(Service::BgmTv));
+        let args = BgmTVSearchArgs {
+            query: "平野と鍵浦".to_string(),
+            start_date: Some("2023".to_string()),
+            ..Default::default()
+        };
+        let result = tool.call(args).await.unwrap();
+        assert_eq!(result.data.len(), 1);
+        assert_eq!(result.data[0].name, "平野と鍵浦");
+        assert_eq!(result.data[0].infobox.len(), 2);
+
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_episodes_pages() {
+        let dir = std::env::temp_dir().join(format!("bgm-episodes-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let page = |offset: usize, count: usize| Interaction {
+            request: RecordedRequest {
+                method: "GET".to_string(),
+                path: "/v0/episodes".to_string(),
+                query: format!("limit=100&offset={}&subject_id=1", offset),
+                body: String::new(),
+            },
+            response: RecordedResponse {
+                status: 200,
+                content_type: Some("application/json".to_string()),
+                body: json!({
+                    "data": (offset..offset + count)
+                        .map(|i| json!({"id": i, "type": 0, "sort": i + 1, "ep": i + 1}))
+                        .collect::<Vec<_>>(),
+                    "total": 150,
+                    "limit": 100,
+                    "offset": offset,
+                })
+                .to_string(),
+            },
+        };
+        std::fs::write(
+            dir.join("bgm_tv.json"),
+            serde_json::to_string(&[page(0, 100), page(100, 50)]).unwrap(),
+        )
+        .unwrap();
+
+        let server = CassetteServer::start(CassetteMode::Replay, &dir)
+            .await
+            .unwrap();
+        let api = BgmTVSearchTool::with_base_url(server.base_url(Service::BgmTv));
+        let episodes = api.get_episodes(1).await.unwrap();
+        assert_eq!(episodes.len(), 150);
+        assert_eq!(episodes[149].ep, Some(150.0));
+
+        server.stop().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_bgm_tv_subject_tools_replay() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("cassettes");
+        let server = CassetteServer::start(CassetteMode::Replay, dir)
+            .await
+            .unwrap();
+        let base_url = server.base_url(Service::BgmTv);
+
+        let subject = BgmTVSubjectTool::with_base_url(&base_url)
+            .call(BgmTVSubjectArgs { id: 100001 })
+            .await
+            .unwrap();
+        assert_eq!(subject.platform.as_deref(), Some("TV"));
+        // 详情保留完整的 infobox
+        assert_eq!(subject.infobox.len(), 3);
+
+        let episodes = BgmTVEpisodesTool::with_base_url(&base_url)
+            .call(BgmTVSubjectArgs { id: 100001 })
+            .await
+            .unwrap();
+        assert_eq!(episodes.len(), 2);
+        assert_eq!(episodes[1].ep, Some(2.0));
+
+        let relations = BgmTVRelationsTool::with_base_url(&base_url)
+            .call(BgmTVSubjectArgs { id: 100001 })
+            .await
+            .unwrap();
+        // 只保留动画条目
+        assert_eq!(relations.len(), 1);
+        assert_eq!(relations[0].relation, "续集");
+
+        server.stop().await;
+    }
 }
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+use rig::{completion::ToolDefinition, tool::Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::agent::known_ids::KnownIds;
+use crate::agent::tool_bgm_tv::BgmTVSearchTool;
+use crate::agent::tool_tmdb::fetch_tmdb_details;
+use crate::models::enums::Platform;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct KnownIdsArgs {}
+
+/// 其他平台上已确认的条目及其远程信息
+#[derive(Debug, Serialize)]
+pub struct KnownSubject {
+    pub platform: Platform,
+    pub id: String,
+    pub metadata: Option<Value>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KnownIdsOutput {
+    pub subjects: Vec<KnownSubject>,
+    pub external_ids: BTreeMap<String, String>,
+}
+
+/// 查询动画在其他平台上已知的id及对应条目信息，用于区分续作和重制版
+pub struct KnownIdsTool {
+    known_ids: KnownIds,
+}
+
+impl KnownIdsTool {
+    pub fn new(known_ids: KnownIds) -> Self {
+        Self { known_ids }
+    }
+
+    async fn fetch_metadata(&self, platform: &Platform, id: &str) -> Result<Option<Value>, String> {
+        let id: i32 = id.parse().map_err(|_| format!("无效的id: {}", id))?;
+        match platform {
+            Platform::BgmTv => {
+                let subject = BgmTVSearchTool::new()
+                    .get_subject(id)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(subject.map(|mut subject| {
+                    subject.infobox.retain(|item| {
+                        item.key == "中文名" || item.key == "别名" || item.key == "英文名"
+                    });
+                    json!(subject)
+                }))
+            }
+            Platform::Tmdb => {
+                let details = fetch_tmdb_details(self.known_ids.is_movie, id)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(details.map(|details| json!(details)))
+            }
+        }
+    }
+}
+
+impl Tool for KnownIdsTool {
+    const NAME: &'static str = "lookup_known_ids";
+
+    type Error = Infallible;
+    type Args = KnownIdsArgs;
+    type Output = KnownIdsOutput;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Look up the ids this anime already has on other platforms (e.g. Bangumi, TMDB, MAL, AniDB, TheTVDB, IMDb) and the metadata of the confirmed entries. Use them as cross-references to tell sequels and remakes apart.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {},
+            }),
+        }
+    }
+
+    async fn call(&self, _args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let mut subjects = Vec::with_capacity(self.known_ids.mappings.len());
+        for mapping in self.known_ids.mappings.iter() {
+            let (metadata, error) = match self.fetch_metadata(&mapping.platform, &mapping.id).await
+            {
+                Ok(metadata) => (metadata, None),
+                Err(e) => (None, Some(e)),
+            };
+            subjects.push(KnownSubject {
+                platform: mapping.platform.clone(),
+                id: mapping.id.clone(),
+                metadata,
+                error,
+            });
+        }
+
+        Ok(KnownIdsOutput {
+            subjects,
+            external_ids: self.known_ids.external_ids.clone(),
+        })
+    }
+}
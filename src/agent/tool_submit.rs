@@ -1,7 +1,9 @@
 use rig::{completion::ToolDefinition, tool::Tool};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{Value, json};
+
+use crate::agent::agent::MatchCandidate;
 
 #[derive(Deserialize, Serialize, JsonSchema)]
 pub struct SubmitArgs {
@@ -9,6 +11,44 @@ pub struct SubmitArgs {
     name: Option<String>,
     season: Option<i32>,
     confidence_score: Option<i32>,
+    candidates: Option<Vec<MatchCandidate>>,
+}
+
+/// 候选列表的参数定义，TMDB 需要额外提供季度
+fn candidates_schema(with_season: bool) -> Value {
+    let mut properties = json!({
+        "id": {
+            "type": "number",
+            "description": "The id of the candidate"
+        },
+        "name": {
+            "type": "string",
+            "description": "The name of the candidate"
+        },
+        "confidence_score": {
+            "type": "number",
+            "description": "The confidence score of the candidate, value range from 0 to 100"
+        },
+        "reason": {
+            "type": "string",
+            "description": "A short rationale for the score"
+        },
+    });
+    if with_season {
+        properties["season"] = json!({
+            "type": "number",
+            "description": "The season number of the candidate"
+        });
+    }
+    json!({
+        "type": "array",
+        "description": "All candidates considered, ranked by confidence score (best first, at most 5)",
+        "items": {
+            "type": "object",
+            "properties": properties,
+            "required": ["id", "confidence_score"]
+        }
+    })
 }
 
 pub struct SubmitTool {}
@@ -55,6 +95,7 @@ impl Tool for SubmitTool {
                         "type": "number",
                         "description": "The confidence score of the match, value range from 0 to 100"
                     },
+                    "candidates": candidates_schema(true),
                 },
                 "required": ["confidence_score"]
             }),
@@ -94,6 +135,7 @@ impl Tool for SubmitBGMTool {
                         "type": "number",
                         "description": "The confidence score of the match, value range from 0 to 100"
                     },
+                    "candidates": candidates_schema(false),
                 },
                 "required": ["confidence_score"]
             }),
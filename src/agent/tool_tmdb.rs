@@ -1,40 +1,93 @@
-use std::sync::Arc;
-
+use chrono::NaiveDate;
 use rig::{completion::ToolDefinition, tool::Tool};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::json;
-use tmdb_api::{
-    Client,
-    client::reqwest::ReqwestExecutor,
-    movie::{MovieShort, search::MovieSearch},
-    prelude::Command,
-    tvshow::{
-        TVShowShort,
-        details::TVShowDetails,
-        episode::groups::{TVShowEpisodeGroups, TVShowEpisodeGroupsDetails},
-        search::TVShowSearch,
-    },
-};
-use tokio_retry::{Retry, strategy::FixedInterval};
+use tmdb_api::{movie::MovieShort, tvshow::TVShowShort};
 use tracing::info;
 
+use crate::http::client::HttpClient;
+use crate::http::{Service, cache, is_replaying};
+
+const DEFAULT_TMDB_LANGUAGE: &str = "zh-CN";
+
+/// 按日期过滤搜索结果时最多查看的页数
+const MAX_SEARCH_PAGES: u32 = 3;
+
+/// 搜索和详情使用的语言，由 `TMDB_LANGUAGE` 指定，默认 zh-CN
+pub fn tmdb_language() -> String {
+    std::env::var("TMDB_LANGUAGE").unwrap_or_else(|_| DEFAULT_TMDB_LANGUAGE.to_string())
+}
+
+/// 日期是否落在 [start, end] 区间内，日期未知时保留
+fn in_date_range(
+    date: Option<NaiveDate>,
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+) -> bool {
+    match date {
+        Some(date) => start.is_none_or(|start| date >= start) && end.is_none_or(|end| date <= end),
+        None => true,
+    }
+}
+
+/// 搜索工具共用的参数说明
+fn search_parameters(query_description: &str, date_name: &str) -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "query": {
+                "type": "string",
+                "description": query_description
+            },
+            "year": {
+                "type": "integer",
+                "description": format!("(Optional) Only return results whose {} is in this year, example: 2024", date_name)
+            },
+            "start_date": {
+                "type": "string",
+                "description": format!("(Optional) Only return results whose {} is on or after this date (YYYY-MM-DD). Only the first {} pages of results are searched, use a more specific query if nothing is found", date_name, MAX_SEARCH_PAGES)
+            },
+            "end_date": {
+                "type": "string",
+                "description": format!("(Optional) Only return results whose {} is on or before this date (YYYY-MM-DD). Only the first {} pages of results are searched", date_name, MAX_SEARCH_PAGES)
+            },
+            "language": {
+                "type": "string",
+                "description": "(Optional) Language of the returned names, example: ja-JP, en-US. Defaults to the configured language"
+            }
+        },
+        "required": ["query"]
+    })
+}
+
+#[derive(Default)]
 pub struct TMDBSearchTool {
-    client: Arc<Client<ReqwestExecutor>>,
+    /// 审核时被拒绝的条目id，不出现在搜索结果中
+    rejected_ids: Vec<String>,
 }
 
 impl TMDBSearchTool {
     pub fn new() -> Self {
-        let client = new_tmdb_client();
-        Self {
-            client: Arc::new(client),
-        }
+        Self::default()
+    }
+
+    pub fn with_rejected_ids(mut self, rejected_ids: Vec<String>) -> Self {
+        self.rejected_ids = rejected_ids;
+        self
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct TMDBSearchArgs {
-    query: String,
-    year: Option<i16>,
+    pub query: String,
+    /// 首播年份
+    pub year: Option<u16>,
+    /// 首播日期下限（包含）
+    pub start_date: Option<NaiveDate>,
+    /// 首播日期上限（包含）
+    pub end_date: Option<NaiveDate>,
+    /// 返回结果的语言，不指定时使用 `tmdb_language()`
+    pub language: Option<String>,
 }
 
 #[derive(Debug, thiserror::Error, Serialize)]
@@ -68,54 +121,37 @@ impl Tool for TMDBSearchTool {
         ToolDefinition {
             name: "tmdb_search_tv_show".to_string(),
             description: "Search for TV shows on TMDB".to_string(),
-            parameters: json!({
-                "type": "object",
-                "properties": {
-                    "query": {
-                        "type": "string",
-                        "description": "The search query for TV shows"
-                    },
-                    // "year": {
-                    //     "type": "number",
-                    //     "description": "(Optional) The year for the search, example: 2024"
-                    // }
-                },
-                "required": ["query"]
-            }),
+            parameters: search_parameters("The search query for TV shows", "first air date"),
         }
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        // 在每次调用时创建新的client实例
-        let client = self.client.clone();
-
-        // 使用spawn_blocking来处理阻塞操作
-        let query = args.query;
-        tokio::spawn(async move {
-            let cmd = TVShowSearch::new(query)
-                .with_language(Some("zh-CN".to_string()))
-                .with_year(args.year.map(|item| item as u16));
-
-            // 使用tokio-retry实现重试逻辑
-            let retry_strategy = FixedInterval::from_millis(5000).take(5);
-
-            let result =
-                Retry::spawn(retry_strategy, || async { cmd.execute(&client).await }).await;
-
-            match result {
-                Ok(result) => {
-                    return Ok(TMDBSearchResult {
-                        data: result.results,
-                    });
-                }
-                Err(e) => {
-                    info!("搜索失败，已重试多次: {}", e);
-                    Err(TMDBError::new(format!("搜索失败，已重试多次: {}", e)))
-                }
-            }
+        let mut query = vec![
+            ("query", args.query),
+            ("language", args.language.unwrap_or_else(tmdb_language)),
+        ];
+        if let Some(year) = args.year {
+            query.push(("first_air_date_year", year.to_string()));
+        }
+
+        let pages = if args.start_date.is_some() || args.end_date.is_some() {
+            MAX_SEARCH_PAGES
+        } else {
+            1
+        };
+        let results: Vec<TVShowShort> = search_pages("tmdb/search/tv", "/search/tv", &query, pages)
+            .await
+            .inspect_err(|e| info!("搜索失败: {}", e))?;
+
+        Ok(TMDBSearchResult {
+            data: results
+                .into_iter()
+                .filter(|show| {
+                    in_date_range(show.inner.first_air_date, args.start_date, args.end_date)
+                        && !self.rejected_ids.contains(&show.inner.id.to_string())
+                })
+                .collect(),
         })
-        .await
-        .unwrap_or(Err(TMDBError::new("search not found")))
     }
 }
 
@@ -124,42 +160,374 @@ pub struct TMDBSeasonArgs {
     tv_id: u64,
 }
 
-fn new_tmdb_client() -> Client<ReqwestExecutor> {
-    let api_key = std::env::var("TMDB_API_KEY").expect("TMDB_API_KEY not set");
-    let http_proxy = std::env::var("HTTP_PROXY");
-    let https_proxy = std::env::var("HTTPS_PROXY");
+/// TMDB 剧集或电影详情中用于校验的字段
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TMDBDetails {
+    pub id: i32,
+    pub name: Option<String>,
+    pub original_name: Option<String>,
+    pub title: Option<String>,
+    pub original_title: Option<String>,
+    pub first_air_date: Option<String>,
+    pub release_date: Option<String>,
+    pub number_of_episodes: Option<i32>,
+    pub genres: Vec<TMDBGenre>,
+    pub seasons: Vec<TMDBSeasonSummary>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TMDBGenre {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TMDBSeasonSummary {
+    pub id: u64,
+    pub name: String,
+    pub season_number: i32,
+    pub air_date: Option<String>,
+    pub episode_count: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TMDBEpisode {
+    pub id: u64,
+    pub season_number: i32,
+    pub episode_number: i32,
+    pub air_date: Option<String>,
+}
+
+/// 剧集的所有剧集组
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TMDBEpisodeGroups {
+    pub results: Vec<TMDBEpisodeGroupSummary>,
+}
 
-    let mut client_builder = reqwest::Client::builder();
+/// TMDB 中按播出季划分的剧集组类型
+pub const TMDB_EPISODE_GROUP_TYPE_TV: i32 = 6;
 
-    if let Ok(http_proxy) = http_proxy {
-        client_builder = client_builder.proxy(reqwest::Proxy::http(http_proxy).unwrap());
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TMDBEpisodeGroupSummary {
+    pub id: String,
+    /// 剧集组类型，见 `TMDB_EPISODE_GROUP_TYPE_TV`
+    #[serde(rename = "type")]
+    pub group_type: i32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TMDBEpisodeGroupDetails {
+    pub groups: Vec<TMDBEpisodeGroup>,
+}
+
+/// 剧集组中的一个分组，通常对应一季
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TMDBEpisodeGroup {
+    pub id: String,
+    pub name: String,
+    pub order: i32,
+    pub episodes: Vec<TMDBEpisode>,
+}
+
+/// 搜索接口返回的一页结果
+#[derive(Debug, Deserialize)]
+struct TMDBPage<T> {
+    #[serde(default = "Vec::new")]
+    results: Vec<T>,
+    #[serde(default)]
+    total_pages: u32,
+}
+
+impl<T> Default for TMDBPage<T> {
+    fn default() -> Self {
+        Self {
+            results: vec![],
+            total_pages: 0,
+        }
     }
+}
 
-    if let Ok(https_proxy) = https_proxy {
-        client_builder = client_builder.proxy(reqwest::Proxy::https(https_proxy).unwrap());
+/// 依次获取搜索结果的前 `max_pages` 页。
+/// 缓存的是未按日期过滤的搜索结果，不同日期范围可以共用
+async fn search_pages<T: DeserializeOwned>(
+    endpoint: &str,
+    path: &str,
+    query: &[(&str, String)],
+    max_pages: u32,
+) -> Result<Vec<T>, TMDBError> {
+    let mut results = Vec::new();
+    let mut page_number = 1;
+    loop {
+        let mut page_query = query.to_vec();
+        if page_number > 1 {
+            page_query.push(("page", page_number.to_string()));
+        }
+        let page: TMDBPage<T> = tmdb_query(endpoint, path, &page_query)
+            .await?
+            .unwrap_or_default();
+        results.extend(page.results);
+        if page_number >= page.total_pages.min(max_pages) {
+            break;
+        }
+        page_number += 1;
     }
+    Ok(results)
+}
+
+/// 通过共用的出站客户端请求 TMDB，资源不存在时返回None，响应按 `endpoint` 缓存
+async fn tmdb_query<T: DeserializeOwned>(
+    endpoint: &str,
+    path: &str,
+    query: &[(&str, String)],
+) -> Result<Option<T>, TMDBError> {
+    let http = HttpClient::shared(Service::Tmdb);
+    let url = format!("{}{}", Service::Tmdb.base_url(), path);
+    let params = json!({ "path": path, "query": query });
+
+    let body = cache::cached_text(endpoint, &params, || async {
+        let api_key = match std::env::var("TMDB_API_KEY") {
+            Ok(api_key) => api_key,
+            // 磁带中不保存 api_key，回放时不需要
+            Err(_) if is_replaying() => String::new(),
+            Err(_) => return Err(TMDBError::new("TMDB_API_KEY 未设置")),
+        };
+        let request = http
+            .get(&url)
+            .query(&[("api_key", api_key.as_str())])
+            .query(query);
+        let response = http
+            .send(request)
+            .await
+            .map_err(|e| TMDBError::new(format!("请求错误: {}", e)))?;
 
-    let client = client_builder.build().unwrap();
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(TMDBError::new(format!("请求失败: {}", response.status())));
+        }
+
+        response
+            .text()
+            .await
+            .map(Some)
+            .map_err(|e| TMDBError::new(format!("读取响应错误: {}", e)))
+    })
+    .await?;
+
+    body.map(|body| serde_json::from_str::<T>(&body))
+        .transpose()
+        .map_err(|e| TMDBError::new(format!("解析响应错误: {}", e)))
+}
+
+/// 选择用于划分季的剧集组：优先使用最后一个按播出季划分的剧集组，没有时使用第一个
+fn select_episode_group(groups: &[TMDBEpisodeGroupSummary]) -> Option<&TMDBEpisodeGroupSummary> {
+    groups
+        .iter()
+        .rev()
+        .find(|group| group.group_type == TMDB_EPISODE_GROUP_TYPE_TV)
+        .or(groups.first())
+}
+
+/// 获取剧集组中的分组，`tmdb_season` 返回的季和匹配结果中剧集组的季都按此解析
+pub async fn fetch_tmdb_episode_groups(tv_id: i32) -> Result<Vec<TMDBEpisodeGroup>, TMDBError> {
+    let groups: Option<TMDBEpisodeGroups> = tmdb_get(&format!("/tv/{}/episode_groups", tv_id))
+        .await
+        .map_err(|e| TMDBError::new(format!("获取剧集组失败: {}", e)))?;
+    let groups = groups.unwrap_or_default().results;
+    let Some(group) = select_episode_group(&groups) else {
+        return Ok(vec![]);
+    };
+
+    info!("获取剧集组详情: {}", group.id);
+    let details: Option<TMDBEpisodeGroupDetails> =
+        tmdb_get(&format!("/tv/episode_group/{}", group.id))
+            .await
+            .map_err(|e| TMDBError::new(format!("获取剧集组详情失败: {}", e)))?;
+    Ok(details.unwrap_or_default().groups)
+}
+
+/// 直接请求 TMDB 接口，使用配置的语言，资源不存在时返回None
+pub async fn tmdb_get<T: DeserializeOwned>(path: &str) -> Result<Option<T>, TMDBError> {
+    tmdb_query("tmdb/get", path, &[("language", tmdb_language())]).await
+}
+
+fn media_path(is_movie: bool) -> &'static str {
+    if is_movie { "movie" } else { "tv" }
+}
+
+/// 获取剧集或电影详情，条目不存在时返回None
+pub async fn fetch_tmdb_details(is_movie: bool, id: i32) -> Result<Option<TMDBDetails>, TMDBError> {
+    tmdb_get(&format!("/{}/{}", media_path(is_movie), id)).await
+}
+
+/// 别名或译名
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TMDBTitle {
+    /// 国家或地区，如 JP、CN
+    pub country: String,
+    /// 语言，别名没有语言信息
+    pub language: Option<String>,
+    pub title: String,
+    /// 别名类型，如 romaji
+    pub kind: Option<String>,
+}
 
-    let reqwest_executor = ReqwestExecutor::from(client);
-    tmdb_api::Client::builder()
-        .with_api_key(api_key)
-        .with_base_url("https://api.themoviedb.org/3")
-        .with_executor(reqwest_executor)
-        .build()
-        .unwrap()
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AlternativeTitlesResponse {
+    /// 剧集的别名列表
+    results: Vec<AlternativeTitle>,
+    /// 电影的别名列表
+    titles: Vec<AlternativeTitle>,
 }
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AlternativeTitle {
+    iso_3166_1: String,
+    title: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TranslationsResponse {
+    translations: Vec<Translation>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Translation {
+    iso_3166_1: String,
+    iso_639_1: String,
+    data: TranslationData,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TranslationData {
+    /// 剧集的译名
+    name: Option<String>,
+    /// 电影的译名
+    title: Option<String>,
+}
+
+/// 获取剧集或电影的所有别名和译名，条目不存在时返回空列表
+pub async fn fetch_tmdb_titles(is_movie: bool, id: u64) -> Result<Vec<TMDBTitle>, TMDBError> {
+    let path = media_path(is_movie);
+    let alternative_titles: Option<AlternativeTitlesResponse> =
+        tmdb_get(&format!("/{}/{}/alternative_titles", path, id)).await?;
+    let translations: Option<TranslationsResponse> =
+        tmdb_get(&format!("/{}/{}/translations", path, id)).await?;
+
+    let alternative_titles = alternative_titles.unwrap_or_default();
+    let mut titles: Vec<TMDBTitle> = alternative_titles
+        .results
+        .into_iter()
+        .chain(alternative_titles.titles)
+        .map(|title| TMDBTitle {
+            country: title.iso_3166_1,
+            language: None,
+            title: title.title,
+            kind: Some(title.kind).filter(|kind| !kind.is_empty()),
+        })
+        .collect();
+    titles.extend(
+        translations
+            .unwrap_or_default()
+            .translations
+            .into_iter()
+            .filter_map(|translation| {
+                let title = translation.data.name.or(translation.data.title)?;
+                Some(TMDBTitle {
+                    country: translation.iso_3166_1,
+                    language: Some(translation.iso_639_1),
+                    title,
+                    kind: None,
+                })
+            })
+            .filter(|title| !title.title.is_empty()),
+    );
+    Ok(titles)
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TMDBTitlesArgs {
+    pub id: u64,
+    #[serde(default)]
+    pub is_movie: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TMDBTitlesResult {
+    pub id: u64,
+    pub titles: Vec<TMDBTitle>,
+}
+
+/// 查询剧集或电影在各语言下的别名和译名
+pub struct TMDBAlternativeTitlesTool;
+
+impl Tool for TMDBAlternativeTitlesTool {
+    const NAME: &'static str = "tmdb_alternative_titles";
+
+    type Error = TMDBError;
+    type Args = TMDBTitlesArgs;
+    type Output = TMDBTitlesResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Get all alternative titles and translated names of a TMDB TV show or movie, including Japanese and romaji titles, to compare them with the titles in the query".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "The TMDB ID of the TV show or movie"
+                    },
+                    "is_movie": {
+                        "type": "boolean",
+                        "description": "Whether the id is a movie, defaults to false (TV show)"
+                    }
+                },
+                "required": ["id"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let titles = fetch_tmdb_titles(args.is_movie, args.id).await?;
+        Ok(TMDBTitlesResult {
+            id: args.id,
+            titles,
+        })
+    }
+}
+
+#[derive(Default)]
 pub struct TMDBMovieSearchTool {
-    client: Arc<Client<ReqwestExecutor>>,
+    /// 审核时被拒绝的条目id，不出现在搜索结果中
+    rejected_ids: Vec<String>,
 }
 
 impl TMDBMovieSearchTool {
     pub fn new() -> Self {
-        let client = new_tmdb_client();
-        Self {
-            client: Arc::new(client),
-        }
+        Self::default()
+    }
+
+    pub fn with_rejected_ids(mut self, rejected_ids: Vec<String>) -> Self {
+        self.rejected_ids = rejected_ids;
+        self
     }
 }
 
@@ -169,10 +537,17 @@ pub struct TMDBMovieSearchResult {
     pub data: Vec<MovieShort>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct TMDBMovieSearchArgs {
-    query: String,
-    year: Option<i16>,
+    pub query: String,
+    /// 上映年份
+    pub year: Option<u16>,
+    /// 上映日期下限（包含）
+    pub start_date: Option<NaiveDate>,
+    /// 上映日期上限（包含）
+    pub end_date: Option<NaiveDate>,
+    /// 返回结果的语言，不指定时使用 `tmdb_language()`
+    pub language: Option<String>,
 }
 
 impl Tool for TMDBMovieSearchTool {
@@ -186,68 +561,47 @@ impl Tool for TMDBMovieSearchTool {
         ToolDefinition {
             name: "tmdb_search_movie".to_string(),
             description: "Search for movies on TMDB".to_string(),
-            parameters: json!({
-                "type": "object",
-                "properties": {
-                    "query": {
-                        "type": "string",
-                        "description": "The search query for movies"
-                    },
-                    // "year": {
-                    //     "type": "number",
-                    //     "description": "(Optional) The year for the search, example: 2024"
-                    // }
-                },
-                "required": ["query"]
-            }),
+            parameters: search_parameters("The search query for movies", "release date"),
         }
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        // 在每次调用时创建新的client实例
-        let client = self.client.clone();
-
-        // 使用spawn_blocking来处理阻塞操作
-        let query = args.query;
-        tokio::spawn(async move {
-            let cmd = MovieSearch::new(query)
-                .with_language(Some("zh-CN".to_string()))
-                .with_year(args.year.map(|item| item as u16));
-
-            // 使用tokio-retry实现重试逻辑
-            let retry_strategy = FixedInterval::from_millis(5000).take(5);
-
-            let result =
-                Retry::spawn(retry_strategy, || async { cmd.execute(&client).await }).await;
-
-            match result {
-                Ok(result) => {
-                    return Ok(TMDBMovieSearchResult {
-                        data: result.results,
-                    });
-                }
-                Err(e) => {
-                    info!("搜索失败，已重试多次: {}", e);
-                    Err(TMDBError::new(format!("搜索失败，已重试多次: {}", e)))
-                }
-            }
+        let mut query = vec![
+            ("query", args.query),
+            ("language", args.language.unwrap_or_else(tmdb_language)),
+        ];
+        if let Some(year) = args.year {
+            query.push(("year", year.to_string()));
+        }
+
+        let pages = if args.start_date.is_some() || args.end_date.is_some() {
+            MAX_SEARCH_PAGES
+        } else {
+            1
+        };
+        let results: Vec<MovieShort> =
+            search_pages("tmdb/search/movie", "/search/movie", &query, pages)
+                .await
+                .inspect_err(|e| info!("搜索失败: {}", e))?;
+
+        Ok(TMDBMovieSearchResult {
+            data: results
+                .into_iter()
+                .filter(|movie| {
+                    in_date_range(movie.inner.release_date, args.start_date, args.end_date)
+                        && !self.rejected_ids.contains(&movie.inner.id.to_string())
+                })
+                .collect(),
         })
-        .await
-        .unwrap_or(Err(TMDBError::new("search not found")))
     }
 }
 
 /// ------------------------------------------------
-pub struct TMDBSeasonTool {
-    client: Arc<Client<ReqwestExecutor>>,
-}
+pub struct TMDBSeasonTool;
 
 impl TMDBSeasonTool {
     pub fn new() -> Self {
-        let client = new_tmdb_client();
-        Self {
-            client: Arc::new(client),
-        }
+        Self
     }
 }
 
@@ -290,94 +644,119 @@ impl Tool for TMDBSeasonTool {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        // 保存参数为本地变量
         let tv_id = args.tv_id;
 
-        let client = self.client.clone();
-        tokio::spawn(async move {
-            let retry_strategy = FixedInterval::from_millis(5000).take(5);
-            let cmd = TVShowEpisodeGroups::new(tv_id);
-
-            let ep_groups = match Retry::spawn(retry_strategy.clone(), || async {
-                cmd.execute(&client).await
+        let mut seasons = fetch_tmdb_episode_groups(tv_id as i32)
+            .await?
+            .into_iter()
+            .map(|item| Season {
+                id: item.id,
+                name: item.name,
+                number: item.order,
+                first_air_date: item.episodes.first().and_then(|item| item.air_date.clone()),
             })
+            .collect::<Vec<Season>>();
+
+        let tv_details = fetch_tmdb_details(false, tv_id as i32)
             .await
-            {
-                Ok(result) => result,
-                Err(e) => return Err(TMDBError::new(format!("获取剧集组失败: {}", e))),
-            };
-
-            let mut group_id = None;
-            for item in &ep_groups.results {
-                if item.group_type == 6 {
-                    group_id = Some(item.id.clone());
-                }
-            }
+            .map_err(|e| TMDBError::new(format!("获取TV详情失败: {}", e)))?
+            .ok_or_else(|| TMDBError::new(format!("TV不存在: {}", tv_id)))?;
 
-            if group_id.is_none() && ep_groups.results.len() > 0 {
-                group_id = Some(ep_groups.results[0].id.clone());
-            }
+        seasons.extend(tv_details.seasons.into_iter().map(|item| Season {
+            id: item.id.to_string(),
+            name: item.name,
+            number: item.season_number,
+            first_air_date: item.air_date,
+        }));
 
-            let mut seasons = vec![];
+        Ok(TMDBSeasonResult { data: seasons })
+    }
+}
 
-            if let Some(group_id) = group_id {
-                info!("获取季度详情: {}", group_id);
-                let cmd_details = TVShowEpisodeGroupsDetails::new(group_id)
-                    .with_language(Some("zh-CN".to_string()));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::cassette::install_test_replay;
+
+    #[test]
+    fn test_select_episode_group() {
+        let group = |id: &str, group_type| TMDBEpisodeGroupSummary {
+            id: id.to_string(),
+            group_type,
+        };
+        let groups = vec![
+            group("a", 1),
+            group("b", TMDB_EPISODE_GROUP_TYPE_TV),
+            group("c", TMDB_EPISODE_GROUP_TYPE_TV),
+        ];
+        assert_eq!(select_episode_group(&groups).unwrap().id, "c");
+        assert_eq!(select_episode_group(&groups[..1]).unwrap().id, "a");
+        assert!(select_episode_group(&[]).is_none());
+    }
 
-                let details = match Retry::spawn(retry_strategy.clone(), || async {
-                    cmd_details.execute(&client).await
-                })
-                .await
-                {
-                    Ok(result) => result,
-                    Err(e) => return Err(TMDBError::new(format!("获取季度详情失败: {}", e))),
-                };
-
-                if !details.groups.is_empty() {
-                    seasons = details
-                        .groups
-                        .iter()
-                        .map(|item| Season {
-                            id: item.id.clone(),
-                            name: item.name.clone(),
-                            number: item.order as i32,
-                            first_air_date: item
-                                .episodes
-                                .first()
-                                .map(|item| item.air_date.to_string()),
-                        })
-                        .collect::<Vec<Season>>();
-                }
-            }
+    #[test]
+    fn test_in_date_range() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok();
+        let start = date("2024-04-01");
+        let end = date("2024-06-30");
+
+        assert!(in_date_range(date("2024-04-01"), start, end));
+        assert!(in_date_range(date("2024-06-30"), start, end));
+        assert!(!in_date_range(date("2024-03-31"), start, end));
+        assert!(!in_date_range(date("2024-07-01"), None, end));
+        assert!(in_date_range(date("2024-07-01"), start, None));
+        // 日期未知的结果保留，交给模型判断
+        assert!(in_date_range(None, start, end));
+    }
 
-            let cmd_details = TVShowDetails::new(tv_id).with_language(Some("zh-CN".to_string()));
+    #[tokio::test]
+    async fn test_search_date_filter_pages() {
+        install_test_replay();
+
+        // 符合日期范围的结果在第二页
+        let args = TMDBSearchArgs {
+            query: "鍵浦".to_string(),
+            start_date: NaiveDate::from_ymd_opt(2025, 1, 1),
+            ..Default::default()
+        };
+        let result = TMDBSearchTool::new().call(args).await.unwrap();
+        let ids: Vec<u64> = result.data.iter().map(|show| show.inner.id).collect();
+        assert_eq!(ids, vec![300003]);
+
+        // 不按日期过滤时只查看第一页
+        let args = TMDBSearchArgs {
+            query: "鍵浦".to_string(),
+            ..Default::default()
+        };
+        let result = TMDBSearchTool::new().call(args).await.unwrap();
+        let ids: Vec<u64> = result.data.iter().map(|show| show.inner.id).collect();
+        assert_eq!(ids, vec![300002]);
+    }
 
-            let tv_details = match Retry::spawn(retry_strategy.clone(), || async {
-                cmd_details.execute(&client).await
-            })
-            .await
-            {
-                Ok(result) => result,
-                Err(e) => return Err(TMDBError::new(format!("获取TV详情失败: {}", e))),
-            };
-
-            seasons.extend(
-                tv_details
-                    .seasons
-                    .iter()
-                    .map(|item| Season {
-                        id: item.inner.id.to_string(),
-                        name: item.inner.name.clone(),
-                        number: item.inner.season_number as i32,
-                        first_air_date: item.inner.air_date.map(|item| item.to_string()),
-                    })
-                    .collect::<Vec<Season>>(),
-            );
-
-            Ok(TMDBSeasonResult { data: seasons })
-        })
-        .await
-        .unwrap_or(Err(TMDBError::new("season not found")))
+    #[tokio::test]
+    async fn test_fetch_tmdb_titles() {
+        install_test_replay();
+
+        let titles = fetch_tmdb_titles(false, 300001).await.unwrap();
+        assert_eq!(
+            titles,
+            vec![
+                TMDBTitle {
+                    country: "JP".to_string(),
+                    language: None,
+                    title: "Hirano to Kagiura".to_string(),
+                    kind: Some("romaji".to_string()),
+                },
+                TMDBTitle {
+                    country: "CN".to_string(),
+                    language: Some("zh".to_string()),
+                    title: "平野与键浦".to_string(),
+                    kind: None,
+                },
+            ]
+        );
+
+        // 条目不存在时返回空列表
+        assert!(fetch_tmdb_titles(true, 404).await.unwrap().is_empty());
     }
 }
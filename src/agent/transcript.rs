@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::agent::agent::MatchResult;
+use crate::agent::budget::TokenUsage;
+use crate::models::enums::Platform;
+
+/// 一次工具调用及其结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    pub name: String,
+    pub arguments: Value,
+    pub result: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 一次匹配尝试的完整记录，用于审核时追溯模型的判断依据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchTranscript {
+    pub platform: Platform,
+    pub provider: String,
+    pub model: String,
+    /// 使用的提示词版本
+    pub prompt_version: String,
+    /// 渲染后的系统提示词和发送给模型的查询内容
+    pub prompt: String,
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// 模型最终输出的原始文本
+    pub output: Option<String>,
+    pub result: Option<MatchResult>,
+    pub error: Option<String>,
+    pub usage: TokenUsage,
+    /// 按价格表计算的费用（美元），价格未知时为None
+    #[serde(default)]
+    pub cost: Option<f64>,
+    pub duration_ms: u64,
+}
+
+/// 预匹配记录中使用的 provider 名称
+pub const PREMATCH_PROVIDER: &str = "prematch";
+
+impl MatchTranscript {
+    /// 预匹配直接确定结果时的记录，没有模型参与
+    pub fn prematch(
+        platform: Platform,
+        query: &str,
+        result: &MatchResult,
+        duration_ms: u64,
+    ) -> Self {
+        Self {
+            platform,
+            provider: PREMATCH_PROVIDER.to_string(),
+            model: PREMATCH_PROVIDER.to_string(),
+            prompt_version: String::new(),
+            prompt: query.to_string(),
+            tool_calls: Vec::new(),
+            output: serde_json::to_string(result).ok(),
+            result: Some(result.clone()),
+            error: None,
+            usage: TokenUsage::default(),
+            cost: None,
+            duration_ms,
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use tracing::info;
+
+use crate::agent::agent::MatchResult;
+use crate::agent::prematch::{Candidate, PreMatchQuery, score_candidate};
+use crate::agent::tool_bgm_tv::{BGM_SUBJECT_TYPE_ANIME, BgmTVSearchTool};
+use crate::agent::tool_tmdb::{
+    TMDBDetails, TMDBEpisodeGroup, fetch_tmdb_details, fetch_tmdb_episode_groups,
+};
+use crate::models::anime::Model as Anime;
+use crate::models::enums::{MediaType, Platform};
+
+/// TMDB 中动画的类型id
+const TMDB_GENRE_ANIMATION: i32 = 16;
+/// 开播日期相差超过该天数时认为不是同一部作品
+const MAX_DATE_DIFF_DAYS: i64 = 180;
+/// 日期无法确认时，标题相似度的最低要求
+const MIN_TITLE_SCORE: f64 = 0.3;
+/// 日期相差在该天数以内时，认为日期可以确认匹配
+const CONFIRM_DATE_DIFF_DAYS: i64 = 30;
+
+/// 从远程获取的条目信息
+#[derive(Debug, Clone)]
+pub struct RemoteSubject {
+    pub candidate: Candidate,
+    pub is_anime: bool,
+}
+
+/// 校验结论
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// 校验通过，附带调整后的分数
+    Accepted { score: u8 },
+    /// 校验不通过
+    Rejected { reason: String },
+}
+
+/// 重新获取模型提交的条目，与动画信息比对后给出结论
+pub async fn verify_match(
+    platform: &Platform,
+    anime: &Anime,
+    result: &MatchResult,
+) -> Result<Verdict> {
+    let Some(id) = result.id else {
+        return Err(anyhow!("没有需要校验的条目"));
+    };
+    let query = PreMatchQuery::from_anime(anime)?;
+    let subject = match platform {
+        Platform::BgmTv => fetch_bgm_tv_subject(id).await?,
+        Platform::Tmdb => {
+            let is_movie = anime.media_type == MediaType::Movie;
+            fetch_tmdb_subject(is_movie, id, result.season).await?
+        }
+    };
+
+    let proposed_score = result.confidence_score.unwrap_or_default().clamp(0, 100) as u8;
+    let verdict = check_subject(&query, proposed_score, subject.as_ref());
+    info!(
+        "校验匹配结果: anilist_id={}, id={}, verdict={:?}",
+        anime.anilist_id, id, verdict
+    );
+    Ok(verdict)
+}
+
+async fn fetch_bgm_tv_subject(id: i32) -> Result<Option<RemoteSubject>> {
+    let subject = BgmTVSearchTool::new()
+        .get_subject(id)
+        .await
+        .map_err(|e| anyhow!("获取BgmTV条目失败: {}", e))?;
+    Ok(subject.map(|subject| RemoteSubject {
+        is_anime: subject.subject_type == BGM_SUBJECT_TYPE_ANIME,
+        candidate: Candidate::from_subject(subject),
+    }))
+}
+
+async fn fetch_tmdb_subject(
+    is_movie: bool,
+    id: i32,
+    season: Option<i32>,
+) -> Result<Option<RemoteSubject>> {
+    let Some(details) = fetch_tmdb_details(is_movie, id)
+        .await
+        .map_err(|e| anyhow!("获取TMDB详情失败: {}", e))?
+    else {
+        return Ok(None);
+    };
+
+    // 正式的季中没有时，模型选择的是剧集组中的季
+    let season = season.filter(|_| !is_movie);
+    let group = match season {
+        Some(season)
+            if !details
+                .seasons
+                .iter()
+                .any(|item| item.season_number == season) =>
+        {
+            fetch_tmdb_episode_groups(id)
+                .await
+                .map_err(|e| anyhow!("{}", e))?
+                .into_iter()
+                .find(|group| group.order == season)
+        }
+        _ => None,
+    };
+    Ok(Some(tmdb_subject(details, season, group)))
+}
+
+fn parse_date(date: Option<&str>) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date?, "%Y-%m-%d").ok()
+}
+
+/// 剧集指定季度时，日期与集数取自对应的季或剧集组中的分组，都找不到时不比对日期和集数
+fn tmdb_subject(
+    details: TMDBDetails,
+    season: Option<i32>,
+    group: Option<TMDBEpisodeGroup>,
+) -> RemoteSubject {
+    let official = season.and_then(|season| {
+        details
+            .seasons
+            .iter()
+            .find(|item| item.season_number == season)
+    });
+    let (date, episodes) = match (season, official, group) {
+        (_, Some(official), _) => (
+            parse_date(official.air_date.as_deref()),
+            official.episode_count,
+        ),
+        (_, None, Some(group)) => (
+            parse_date(
+                group
+                    .episodes
+                    .first()
+                    .and_then(|episode| episode.air_date.as_deref()),
+            ),
+            Some(group.episodes.len() as i32),
+        ),
+        (Some(_), None, None) => (None, None),
+        (None, None, None) => (
+            parse_date(
+                details
+                    .first_air_date
+                    .as_deref()
+                    .or(details.release_date.as_deref()),
+            ),
+            details.number_of_episodes,
+        ),
+    };
+
+    let names: Vec<String> = [
+        &details.name,
+        &details.original_name,
+        &details.title,
+        &details.original_title,
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|name| !name.is_empty())
+    .cloned()
+    .collect();
+
+    RemoteSubject {
+        is_anime: details
+            .genres
+            .iter()
+            .any(|genre| genre.id == TMDB_GENRE_ANIMATION),
+        candidate: Candidate {
+            id: details.id,
+            name: names.first().cloned().unwrap_or_default(),
+            names,
+            date,
+            episodes: episodes.filter(|eps| *eps > 0),
+        },
+    }
+}
+
+/// 比对条目与动画信息，通过时将模型分数与比对分数取平均
+pub fn check_subject(
+    query: &PreMatchQuery,
+    proposed_score: u8,
+    subject: Option<&RemoteSubject>,
+) -> Verdict {
+    let Some(subject) = subject else {
+        return Verdict::Rejected {
+            reason: "条目不存在".to_string(),
+        };
+    };
+    if !subject.is_anime {
+        return Verdict::Rejected {
+            reason: "条目不是动画".to_string(),
+        };
+    }
+
+    let date_diff = match (query.start_date, subject.candidate.date) {
+        (Some(a), Some(b)) => Some((a - b).num_days().abs()),
+        _ => None,
+    };
+    if let Some(days) = date_diff.filter(|days| *days > MAX_DATE_DIFF_DAYS) {
+        return Verdict::Rejected {
+            reason: format!("开播日期相差{}天", days),
+        };
+    }
+
+    let score = score_candidate(query, &subject.candidate);
+    let date_confirmed = date_diff.is_some_and(|days| days <= CONFIRM_DATE_DIFF_DAYS);
+    if !date_confirmed && score.title < MIN_TITLE_SCORE {
+        return Verdict::Rejected {
+            reason: format!("标题相似度过低: {:.2}", score.title),
+        };
+    }
+
+    Verdict::Accepted {
+        score: ((proposed_score as u16 + score.total as u16) / 2) as u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::cassette::install_test_replay;
+
+    fn query() -> PreMatchQuery {
+        PreMatchQuery {
+            titles: vec!["ひらやすみ".to_string()],
+            year: 2025,
+            start_date: NaiveDate::from_ymd_opt(2025, 10, 3),
+            episodes: Some(12),
+        }
+    }
+
+    fn subject(name: &str, date: Option<NaiveDate>, is_anime: bool) -> RemoteSubject {
+        RemoteSubject {
+            candidate: Candidate {
+                id: 1,
+                name: name.to_string(),
+                names: vec![name.to_string()],
+                date,
+                episodes: Some(12),
+            },
+            is_anime,
+        }
+    }
+
+    #[test]
+    fn test_accept_matching_subject() {
+        let subject = subject("ひらやすみ", NaiveDate::from_ymd_opt(2025, 10, 3), true);
+        assert_eq!(
+            check_subject(&query(), 80, Some(&subject)),
+            Verdict::Accepted { score: 90 }
+        );
+    }
+
+    #[test]
+    fn test_reject_mismatched_subject() {
+        assert!(matches!(
+            check_subject(&query(), 90, None),
+            Verdict::Rejected { .. }
+        ));
+
+        let manga = subject("ひらやすみ", NaiveDate::from_ymd_opt(2021, 1, 1), false);
+        assert!(matches!(
+            check_subject(&query(), 90, Some(&manga)),
+            Verdict::Rejected { .. }
+        ));
+
+        let old = subject("ひらやすみ", NaiveDate::from_ymd_opt(2015, 10, 3), true);
+        assert!(matches!(
+            check_subject(&query(), 90, Some(&old)),
+            Verdict::Rejected { .. }
+        ));
+
+        let unrelated = subject("うずまき", None, true);
+        assert!(matches!(
+            check_subject(&query(), 90, Some(&unrelated)),
+            Verdict::Rejected { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tmdb_subject_from_episode_group() {
+        install_test_replay();
+
+        // 分割放送的第二部分只存在于剧集组中
+        let subject = fetch_tmdb_subject(false, 300004, Some(2))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(subject.candidate.date, NaiveDate::from_ymd_opt(2024, 10, 6));
+        assert_eq!(subject.candidate.episodes, Some(2));
+
+        let subject = fetch_tmdb_subject(false, 300004, Some(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(subject.candidate.date, NaiveDate::from_ymd_opt(2024, 1, 7));
+        assert_eq!(subject.candidate.episodes, Some(4));
+
+        // 找不到指定的季时不比对日期
+        let subject = fetch_tmdb_subject(false, 300004, Some(3))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(subject.candidate.date, None);
+        assert_eq!(subject.candidate.episodes, None);
+    }
+}
@@ -1,20 +1,15 @@
 #![allow(unused)]
 
 use anyhow::{Result, anyhow};
-use governor::{
-    Quota, RateLimiter,
-    clock::DefaultClock,
-    middleware::NoOpMiddleware,
-    state::{InMemoryState, NotKeyed},
-};
-use nonzero_ext::nonzero;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::{fs::File, io::copy, path::Path, sync::Arc, time::Duration};
 use tokio::{fs::File as TokioFile, io::AsyncWriteExt, time::sleep};
 use tracing::{info, warn};
 
-const ANILIST_API_URL: &str = "https://graphql.anilist.co";
+use crate::http::client::HttpClient;
+use crate::http::{Service, cache};
+
 const ANILIST_MEDIA_LIST_QUERY: &str = r#"
 query($page:Int = 1, $type:MediaType, $year:String, $format:[MediaFormat]) {
   Page(page:$page,perPage:50) {
@@ -104,92 +99,98 @@ query ($id: Int) {
 }
 "#;
 
-// 1分钟20次请求的限流配置
-const RATE_LIMIT: u32 = 20;
-const RATE_LIMIT_WINDOW: u64 = 60;
-const RETRY_WAIT_TIME: Duration = Duration::from_secs(60);
-const MAX_RETRIES: u32 = 3;
+const ANILIST_SEASON_QUERY: &str = r#"
+query($page:Int = 1, $season:MediaSeason, $seasonYear:Int) {
+  Page(page:$page,perPage:50) {
+    pageInfo {
+      total
+      perPage
+      currentPage
+      lastPage
+      hasNextPage
+    }
+    media(
+      type:ANIME
+      season:$season
+      seasonYear:$seasonYear
+    ) {
+      id
+      format
+      season
+      seasonYear
+      episodes
+      title {
+        english
+        native
+        romaji
+      }
+      synonyms
+      startDate {
+        year
+        month
+        day
+      }
+    }
+  }
+}
+"#;
+
+/// 响应缓存中 AniList 查询的接口名称
+const ANILIST_QUERY_ENDPOINT: &str = "anilist/query";
+/// 季度列表会随新番公布而变化，单独设置较短的缓存有效期
+const ANILIST_SEASON_ENDPOINT: &str = "anilist/season";
 
 pub struct AniListClient {
-    client: reqwest::Client,
+    http: &'static HttpClient,
+    base_url: String,
     timeout: Duration,
-    rate_limiter: Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
 }
 
 impl AniListClient {
     pub fn new() -> Self {
-        Self {
-            client: reqwest::Client::new(),
-            timeout: Duration::from_secs(60),
-            rate_limiter: Arc::new(RateLimiter::direct(Quota::per_minute(nonzero!(RATE_LIMIT)))),
-        }
+        Self::with_timeout(Duration::from_secs(60))
     }
 
     pub fn with_timeout(timeout: Duration) -> Self {
-        Self {
-            client: reqwest::Client::new(),
-            timeout,
-            rate_limiter: Arc::new(RateLimiter::direct(Quota::per_minute(nonzero!(RATE_LIMIT)))),
-        }
+        Self::with_base_url(Service::AniList.base_url(), timeout)
     }
 
-    async fn handle_rate_limit_and_retry<T, F, Fut>(&self, operation: F) -> Result<T>
-    where
-        F: Fn() -> Fut,
-        Fut: std::future::Future<Output = Result<T>>,
-    {
-        let mut retries = 0;
-        loop {
-            match operation().await {
-                Ok(response) => return Ok(response),
-                Err(e) => {
-                    if let Some(err) = e.downcast_ref::<reqwest::Error>() {
-                        if let Some(status) = err.status() {
-                            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
-                                if retries >= MAX_RETRIES {
-                                    return Err(anyhow!("达到最大重试次数 ({MAX_RETRIES})"));
-                                }
-                                warn!("请求被限流，等待{}秒后重试...", RETRY_WAIT_TIME.as_secs());
-                                sleep(RETRY_WAIT_TIME).await;
-                                retries += 1;
-                                continue;
-                            }
-                        }
-                    }
-                    return Err(e);
-                }
-            }
+    pub fn with_base_url(base_url: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            http: HttpClient::shared(Service::AniList),
+            base_url: base_url.into(),
+            timeout,
         }
     }
 
     async fn send_query<T: for<'de> Deserialize<'de>>(
         &self,
+        endpoint: &str,
         query: &str,
         variables: serde_json::Value,
     ) -> Result<T> {
-        self.handle_rate_limit_and_retry(|| async {
-            // 等待限流器允许请求
-            self.rate_limiter.until_ready().await;
-
-            let response = self
-                .client
-                .post(ANILIST_API_URL)
+        let params = json!({
+            "query": query,
+            "variables": variables
+        });
+        let body = cache::cached_text(endpoint, &params, || async {
+            let request = self
+                .http
+                .post(&self.base_url)
                 .timeout(self.timeout)
-                .json(&json!({
-                    "query": query,
-                    "variables": variables
-                }))
-                .send()
-                .await?;
+                .json(&params);
+            let response = self.http.send(request).await?;
 
             if !response.status().is_success() {
                 return Err(anyhow!("API返回错误状态码: {}", response.status()));
             }
 
-            let response_data = response.json().await?;
-            Ok(response_data)
+            Ok(Some(response.text().await?))
         })
-        .await
+        .await?
+        .ok_or_else(|| anyhow!("API返回空响应"))?;
+
+        Ok(serde_json::from_str(&body)?)
     }
 
     pub async fn get_anime(&self, id: i32) -> Result<AniListMediaDetail> {
@@ -197,7 +198,9 @@ impl AniListClient {
             "id": id
         });
 
-        let response: MediaResponse = self.send_query(ANILIST_MEDIA_QUERY, variables).await?;
+        let response: MediaResponse = self
+            .send_query(ANILIST_QUERY_ENDPOINT, ANILIST_MEDIA_QUERY, variables)
+            .await?;
         Ok(response.data.media)
     }
 
@@ -217,7 +220,44 @@ impl AniListClient {
             "format": format,
         });
 
-        self.send_query(ANILIST_MEDIA_LIST_QUERY, variables).await
+        self.send_query(ANILIST_QUERY_ENDPOINT, ANILIST_MEDIA_LIST_QUERY, variables)
+            .await
+    }
+
+    pub async fn query_season_media(
+        &self,
+        page: i32,
+        season: AniListSeason,
+        season_year: i32,
+    ) -> Result<AniListSeasonResponse> {
+        let variables = json!({
+            "page": page,
+            "season": season,
+            "seasonYear": season_year,
+        });
+
+        self.send_query(ANILIST_SEASON_ENDPOINT, ANILIST_SEASON_QUERY, variables)
+            .await
+    }
+
+    /// 获取指定季度的所有动画
+    pub async fn get_season_media(
+        &self,
+        season: AniListSeason,
+        season_year: i32,
+    ) -> Result<Vec<AniListSeasonMedia>> {
+        let mut media = Vec::new();
+        let mut page = 1;
+        loop {
+            let response = self.query_season_media(page, season, season_year).await?;
+            let page_info = response.data.page.page_info;
+            media.extend(response.data.page.media);
+            if !page_info.has_next_page {
+                break;
+            }
+            page += 1;
+        }
+        Ok(media)
     }
 
     pub async fn download_image(
@@ -264,30 +304,20 @@ impl AniListClient {
             tokio::fs::create_dir_all(parent).await?;
         }
 
-        self.handle_rate_limit_and_retry(|| async {
-            // 等待限流器允许请求
-            self.rate_limiter.until_ready().await;
+        // 下载图片
+        let request = self.http.get(&image_url).timeout(self.timeout);
+        let response = self.http.send(request).await?;
 
-            // 下载图片
-            let response = self
-                .client
-                .get(&image_url)
-                .timeout(self.timeout)
-                .send()
-                .await?;
-
-            if !response.status().is_success() {
-                return Err(anyhow!("下载图片失败: {}", response.status()));
-            }
+        if !response.status().is_success() {
+            return Err(anyhow!("下载图片失败: {}", response.status()));
+        }
 
-            // 将图片内容写入文件
-            let mut file = TokioFile::create(&full_path).await?;
-            let bytes = response.bytes().await?;
-            file.write_all(&bytes).await?;
+        // 将图片内容写入文件
+        let mut file = TokioFile::create(&full_path).await?;
+        let bytes = response.bytes().await?;
+        file.write_all(&bytes).await?;
 
-            Ok(Some(filename.clone()))
-        })
-        .await
+        Ok(Some(filename))
     }
 }
 
@@ -354,6 +384,80 @@ pub struct AniListResponse {
     pub data: AniListData,
 }
 
+/// AniList 的播出季度
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AniListSeason {
+    Winter,
+    Spring,
+    Summer,
+    Fall,
+}
+
+impl AniListSeason {
+    /// 月份所在的季度，1-3月为冬季
+    pub fn from_month(month: u32) -> Self {
+        match month {
+            1..=3 => Self::Winter,
+            4..=6 => Self::Spring,
+            7..=9 => Self::Summer,
+            _ => Self::Fall,
+        }
+    }
+
+    /// 下一个季度及其年份
+    pub fn next(self, year: i32) -> (Self, i32) {
+        match self {
+            Self::Winter => (Self::Spring, year),
+            Self::Spring => (Self::Summer, year),
+            Self::Summer => (Self::Fall, year),
+            Self::Fall => (Self::Winter, year + 1),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Winter => "WINTER",
+            Self::Spring => "SPRING",
+            Self::Summer => "SUMMER",
+            Self::Fall => "FALL",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AniListSeasonMedia {
+    pub id: i32,
+    pub format: Option<String>,
+    pub season: Option<String>,
+    #[serde(rename = "seasonYear")]
+    pub season_year: Option<i32>,
+    pub episodes: Option<i32>,
+    pub title: AniListTitle,
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+    #[serde(rename = "startDate")]
+    pub start_date: AniListDate,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AniListSeasonPage {
+    #[serde(rename = "pageInfo")]
+    pub page_info: PageInfo,
+    pub media: Vec<AniListSeasonMedia>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AniListSeasonData {
+    #[serde(rename = "Page")]
+    pub page: AniListSeasonPage,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AniListSeasonResponse {
+    pub data: AniListSeasonData,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CoverImage {
     pub large: Option<String>,
@@ -427,6 +531,7 @@ struct MediaData {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::cassette::{CassetteMode, CassetteServer};
     use tokio::fs;
 
     #[tokio::test]
@@ -446,6 +551,48 @@ mod tests {
         println!("{:?}", response);
     }
 
+    #[test]
+    fn test_season() {
+        assert_eq!(AniListSeason::from_month(1), AniListSeason::Winter);
+        assert_eq!(AniListSeason::from_month(6), AniListSeason::Spring);
+        assert_eq!(AniListSeason::from_month(10), AniListSeason::Fall);
+        assert_eq!(
+            AniListSeason::Fall.next(2024),
+            (AniListSeason::Winter, 2025)
+        );
+        assert_eq!(
+            AniListSeason::Spring.next(2025),
+            (AniListSeason::Summer, 2025)
+        );
+        assert_eq!(
+            serde_json::to_value(AniListSeason::Summer).unwrap(),
+            json!("SUMMER")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_season_media_replay() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("cassettes");
+        let server = CassetteServer::start(CassetteMode::Replay, dir)
+            .await
+            .unwrap();
+
+        let client = AniListClient::with_base_url(
+            server.base_url(Service::AniList),
+            Duration::from_secs(10),
+        );
+        let media = client
+            .get_season_media(AniListSeason::Spring, 2025)
+            .await
+            .unwrap();
+        assert_eq!(media.len(), 2);
+        assert_eq!(media[0].id, 400001);
+        assert_eq!(media[0].title.native.as_deref(), Some("平野と鍵浦"));
+        assert_eq!(media[1].format.as_deref(), Some("MOVIE"));
+
+        server.stop().await;
+    }
+
     #[tokio::test]
     async fn test_download_image() {
         let client = AniListClient::new();
@@ -1,5 +1,9 @@
-use crate::api::types::{ManualMappingRequest, Summary, YearStatistics};
+use crate::agent::episodes::sync_episode_mappings;
+use crate::api::types::{ManualMappingRequest, Summary, UsageStatistics, YearStatistics};
 use crate::errors::Result;
+use crate::models::enums::Platform;
+use crate::models::episode_mappings::Model as EpisodeMapping;
+use crate::models::transcripts::Model as Transcript;
 use crate::{
     api::types::{Anime, Mapping, Pagination, QueryAnimes, Resp},
     server::AppState,
@@ -79,3 +83,41 @@ pub async fn year_statistics(state: web::Data<AppState>) -> Result<Json<Resp<Yea
     let statistics = state.db.get_year_statistics().await?;
     Ok(Json(Resp::ok(Some(statistics))))
 }
+
+#[get("/api/animes/usage-statistics")]
+pub async fn usage_statistics(state: web::Data<AppState>) -> Result<Json<Resp<UsageStatistics>>> {
+    let statistics = state.db.get_usage_statistics().await?;
+    Ok(Json(Resp::ok(Some(statistics))))
+}
+
+#[get("/api/anime/{anilist_id}/transcripts/{platform}")]
+pub async fn anime_transcripts(
+    state: web::Data<AppState>,
+    path: web::Path<(i32, Platform)>,
+) -> Result<Json<Resp<Vec<Transcript>>>> {
+    let (anilist_id, platform) = path.into_inner();
+    let transcripts = state.db.get_transcripts(anilist_id, platform).await?;
+    Ok(Json(Resp::ok(Some(transcripts))))
+}
+
+#[get("/api/anime/{anilist_id}/episodes/{platform}")]
+pub async fn anime_episodes(
+    state: web::Data<AppState>,
+    path: web::Path<(i32, Platform)>,
+) -> Result<Json<Resp<Vec<EpisodeMapping>>>> {
+    let (anilist_id, platform) = path.into_inner();
+    let episodes = state.db.get_episode_mappings(anilist_id, platform).await?;
+    Ok(Json(Resp::ok(Some(episodes))))
+}
+
+/// 根据已确认的映射重新生成分集映射
+#[post("/api/anime/{anilist_id}/episodes/{platform}/sync")]
+pub async fn sync_anime_episodes(
+    state: web::Data<AppState>,
+    path: web::Path<(i32, Platform)>,
+) -> Result<Json<Resp<Vec<EpisodeMapping>>>> {
+    let (anilist_id, platform) = path.into_inner();
+    sync_episode_mappings(&state.db, anilist_id, platform.clone()).await?;
+    let episodes = state.db.get_episode_mappings(anilist_id, platform).await?;
+    Ok(Json(Resp::ok(Some(episodes))))
+}
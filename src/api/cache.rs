@@ -0,0 +1,34 @@
+use crate::api::types::{CacheFilter, CacheStatistics, Resp};
+use crate::errors::Result;
+use crate::http::cache::cache_statistics;
+use crate::server::AppState;
+use actix_web::{
+    get, post,
+    web::{self, Json},
+};
+
+#[get("/api/cache/statistics")]
+pub async fn cache_stats(state: web::Data<AppState>) -> Result<Json<Resp<CacheStatistics>>> {
+    let statistics = cache_statistics(&state.db).await?;
+    Ok(Json(Resp::ok(Some(statistics))))
+}
+
+/// 删除缓存条目，返回删除的条目数
+#[post("/api/cache/purge")]
+pub async fn purge_cache(
+    state: web::Data<AppState>,
+    filter: web::Json<CacheFilter>,
+) -> Result<Json<Resp<u64>>> {
+    let count = state.db.purge_response_cache(&filter).await?;
+    Ok(Json(Resp::ok(Some(count))))
+}
+
+/// 将缓存条目标记为过期，返回标记的条目数
+#[post("/api/cache/refresh")]
+pub async fn refresh_cache(
+    state: web::Data<AppState>,
+    filter: web::Json<CacheFilter>,
+) -> Result<Json<Resp<u64>>> {
+    let count = state.db.expire_response_cache(&filter).await?;
+    Ok(Json(Resp::ok(Some(count))))
+}
@@ -1,24 +1,32 @@
 use std::fs::File;
 use std::path::Path;
 
-use crate::api::types::{CompactAnime, CompactMapping, Resp};
+use crate::api::types::{CompactAnime, CompactEpisode, CompactMapping, Resp};
 use crate::errors::Result;
+use crate::models::db::DB;
 use crate::models::export::ExportAnime;
 use crate::server::AppState;
 use actix_web::web;
 use actix_web::{get, web::Json};
 
 const EXPORT_DIR: &str = "export";
+
+/// 将指定年份的动画写入导出目录
+pub async fn write_export(db: &DB, year: i32) -> anyhow::Result<()> {
+    let animes = db.export_animes(year).await?;
+    let file_path = format!("{}/{}.json", EXPORT_DIR, year);
+    let file = File::create(file_path)?;
+    serde_json::to_writer_pretty(file, &animes)?;
+    Ok(())
+}
+
 #[get("/api/export/animes/{year}")]
 pub async fn export_animes(
     state: web::Data<AppState>,
     path: web::Path<i32>,
 ) -> Result<Json<Resp<()>>> {
     let year = path.into_inner();
-    let animes = state.db.export_animes(year).await?;
-    let file_path = format!("{}/{}.json", EXPORT_DIR, year);
-    let file = File::create(file_path)?;
-    serde_json::to_writer_pretty(file, &animes)?;
+    write_export(&state.db, year).await?;
     Ok(Json(Resp::ok(None)))
 }
 
@@ -37,9 +45,15 @@ pub async fn import_animes(
 
 #[get("/api/compact/animes/dir")]
 pub async fn compact_export_dir(_: web::Data<AppState>) -> Result<Json<Resp<()>>> {
+    compact_exports()?;
+    Ok(Json(Resp::ok(None)))
+}
+
+/// 合并导出目录中各年份的文件，生成 dist.json
+pub fn compact_exports() -> anyhow::Result<()> {
     let dir = Path::new(EXPORT_DIR);
     if !dir.exists() {
-        return Ok(Json(Resp::ok(None)));
+        return Ok(());
     }
 
     let mut all_compact_animes: Vec<CompactAnime> = Vec::new();
@@ -69,6 +83,16 @@ pub async fn compact_export_dir(_: web::Data<AppState>) -> Result<Json<Resp<()>>
                         .mappings
                         .into_iter()
                         .map(|mapping| CompactMapping {
+                            episodes: anime
+                                .episodes
+                                .iter()
+                                .filter(|episode| episode.platform == mapping.platform)
+                                .map(|episode| CompactEpisode {
+                                    episode: episode.episode,
+                                    season: episode.season,
+                                    platform_episode: episode.platform_episode,
+                                })
+                                .collect(),
                             id: mapping.platform_id,
                             platform: mapping.platform,
                         })
@@ -87,5 +111,5 @@ pub async fn compact_export_dir(_: web::Data<AppState>) -> Result<Json<Resp<()>>
     let compact_file = File::create(compact_file_path)?;
     serde_json::to_writer(compact_file, &all_compact_animes)?;
 
-    Ok(Json(Resp::ok(None)))
+    Ok(())
 }
@@ -1,82 +1,188 @@
+use crate::api::types::{
+    CreateConsensusJobRequest, CreateJobRequest, PromptVersions, Resp, UpdateMaxCostRequest,
+};
 use crate::errors::Result;
 use crate::job::mapping_bgm::JobDetails;
 use crate::server::AppState;
-use crate::{api::types::Resp, models::enums::Platform};
 use actix_web::{
-    get,
+    HttpRequest, HttpResponse, get, post,
     web::{self, Json},
 };
+use actix_ws::Message;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
 
-#[get("/api/job/{platform}/create/{year}/{provider}/{model}")]
+#[post("/api/job/create")]
 pub async fn create_job(
     state: web::Data<AppState>,
-    path: web::Path<(Platform, i32, String, String)>,
-) -> Result<Json<Resp<()>>> {
-    let (platform, year, provider, model) = path.into_inner();
-    {
-        let mut job_runner = state.job_runner.lock().unwrap();
+    request: web::Json<CreateJobRequest>,
+) -> Result<Json<Resp<i32>>> {
+    let request = request.into_inner();
+    let id = {
+        let mut job_runner = state.job_runner.lock().await;
+        // 模型为空时在创建任务时解析为 provider 的默认模型
         job_runner
-            .create_job(platform, year, provider, model)
-            .await?;
-    }
-    Ok(Json(Resp::ok(Some(()))))
+            .create_job(
+                request.platform,
+                request.filter,
+                request.provider,
+                request.model.unwrap_or_default(),
+                request.options,
+            )
+            .await?
+    };
+    Ok(Json(Resp::ok(Some(id))))
 }
 
-#[get("/api/job/{platform}/run/{year}")]
-pub async fn run_job(
+#[post("/api/job/create/consensus")]
+pub async fn create_consensus_job(
     state: web::Data<AppState>,
-    path: web::Path<(Platform, i32)>,
-) -> Result<Json<Resp<()>>> {
-    let (platform, year) = path.into_inner();
-    {
-        let job_runner = state.job_runner.lock().unwrap();
-        job_runner.run(platform, year).await;
-    }
-    Ok(Json(Resp::ok(Some(()))))
+    request: web::Json<CreateConsensusJobRequest>,
+) -> Result<Json<Resp<i32>>> {
+    let request = request.into_inner();
+    let id = {
+        let mut job_runner = state.job_runner.lock().await;
+        job_runner
+            .create_consensus_job(
+                request.platform,
+                request.filter,
+                request.models,
+                request.options,
+            )
+            .await?
+    };
+    Ok(Json(Resp::ok(Some(id))))
 }
 
 #[get("/api/job/list")]
 pub async fn list_jobs(state: web::Data<AppState>) -> Result<Json<Resp<Vec<JobDetails>>>> {
-    let job_runner = state.job_runner.lock().unwrap();
+    let job_runner = state.job_runner.lock().await;
     let jobs = job_runner.list_jobs().await?;
     Ok(Json(Resp::ok(Some(jobs))))
 }
 
-#[get("/api/job/{platform}/pause/{year}")]
-pub async fn pause_job(
+#[get("/api/job/{id}/run")]
+pub async fn run_job(state: web::Data<AppState>, path: web::Path<i32>) -> Result<Json<Resp<()>>> {
+    let id = path.into_inner();
+    {
+        let job_runner = state.job_runner.lock().await;
+        job_runner.run(id).await;
+    }
+    Ok(Json(Resp::ok(Some(()))))
+}
+
+#[get("/api/job/{id}/pause")]
+pub async fn pause_job(state: web::Data<AppState>, path: web::Path<i32>) -> Result<Json<Resp<()>>> {
+    let id = path.into_inner();
+    {
+        let job_runner = state.job_runner.lock().await;
+        job_runner.pause_job(id).await?;
+    }
+    Ok(Json(Resp::ok(Some(()))))
+}
+
+#[get("/api/job/{id}/resume")]
+pub async fn resume_job(
     state: web::Data<AppState>,
-    path: web::Path<(Platform, i32)>,
+    path: web::Path<i32>,
 ) -> Result<Json<Resp<()>>> {
-    let (platform, year) = path.into_inner();
+    let id = path.into_inner();
     {
-        let job_runner = state.job_runner.lock().unwrap();
-        job_runner.pause_job(platform, year).await?;
+        let job_runner = state.job_runner.lock().await;
+        job_runner.resume_job(id).await?;
     }
     Ok(Json(Resp::ok(Some(()))))
 }
 
-#[get("/api/job/{platform}/resume/{year}")]
-pub async fn resume_job(
+/// 修改费用上限，提高上限后可恢复已达到上限的任务
+#[post("/api/job/{id}/max_cost")]
+pub async fn update_max_cost(
     state: web::Data<AppState>,
-    path: web::Path<(Platform, i32)>,
+    path: web::Path<i32>,
+    request: web::Json<UpdateMaxCostRequest>,
 ) -> Result<Json<Resp<()>>> {
-    let (platform, year) = path.into_inner();
+    let id = path.into_inner();
     {
-        let job_runner = state.job_runner.lock().unwrap();
-        job_runner.resume_job(platform, year).await?;
+        let job_runner = state.job_runner.lock().await;
+        job_runner.set_max_cost(id, request.max_cost).await?;
     }
     Ok(Json(Resp::ok(Some(()))))
 }
 
-#[get("/api/job/{platform}/remove/{year}")]
+#[get("/api/job/{id}/remove")]
 pub async fn remove_job(
     state: web::Data<AppState>,
-    path: web::Path<(Platform, i32)>,
+    path: web::Path<i32>,
 ) -> Result<Json<Resp<()>>> {
-    let (platform, year) = path.into_inner();
+    let id = path.into_inner();
     {
-        let mut job_runner = state.job_runner.lock().unwrap();
-        job_runner.remove_job(platform, year).await?;
+        let mut job_runner = state.job_runner.lock().await;
+        job_runner.remove_job(id).await?;
     }
     Ok(Json(Resp::ok(Some(()))))
 }
+
+/// 通过 WebSocket 推送任务事件，每条消息为一个 JSON 格式的 `JobEvent`
+#[get("/api/job/events")]
+pub async fn job_events(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Payload,
+) -> Result<HttpResponse> {
+    let (response, mut session, mut msg_stream) = actix_ws::handle(&req, body)?;
+    let mut events = state.job_runner.lock().await.subscribe();
+
+    actix_web::rt::spawn(async move {
+        loop {
+            tokio::select! {
+                event = events.recv() => {
+                    let event = match event {
+                        Ok(event) => event,
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("任务事件推送过慢，丢弃了 {} 条事件", skipped);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    };
+                    let text = match serde_json::to_string(&event) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            warn!("序列化任务事件失败: {}", e);
+                            continue;
+                        }
+                    };
+                    if session.text(text).await.is_err() {
+                        return;
+                    }
+                }
+                msg = msg_stream.recv() => {
+                    match msg {
+                        Some(Ok(Message::Ping(bytes))) => {
+                            if session.pong(&bytes).await.is_err() {
+                                return;
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                        Some(Ok(_)) => {}
+                    }
+                }
+            }
+        }
+        let _ = session.close(None).await;
+    });
+
+    Ok(response)
+}
+
+#[get("/api/job/prompts")]
+pub async fn list_prompts(state: web::Data<AppState>) -> Result<Json<Resp<PromptVersions>>> {
+    Ok(Json(Resp::ok(Some(PromptVersions {
+        default_version: state.prompts.default_version().to_string(),
+        versions: state
+            .prompts
+            .versions()
+            .into_iter()
+            .map(|version| version.to_string())
+            .collect(),
+    }))))
+}
@@ -1,5 +1,7 @@
 pub mod animes;
+pub mod cache;
 pub mod export;
 pub mod job;
 pub mod review;
+pub mod schedule;
 pub mod types;
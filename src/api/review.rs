@@ -1,5 +1,6 @@
 use crate::api::types::Resp;
 use crate::errors::Result;
+use crate::models::candidates::Model as Candidate;
 use crate::{
     models::enums::{Platform, ReviewStatus},
     server::AppState,
@@ -18,3 +19,26 @@ pub async fn review_anime(
     state.db.review(anilist_id, platform, status).await?;
     Ok(Json(Resp::ok(Some(()))))
 }
+
+#[get("/api/anime/{anilist_id}/candidates/{platform}")]
+pub async fn anime_candidates(
+    state: web::Data<AppState>,
+    path: web::Path<(i32, Platform)>,
+) -> Result<Json<Resp<Vec<Candidate>>>> {
+    let (anilist_id, platform) = path.into_inner();
+    let candidates = state.db.get_candidates(anilist_id, platform).await?;
+    Ok(Json(Resp::ok(Some(candidates))))
+}
+
+#[get("/api/anime/{anilist_id}/review/{platform}/candidate/{rank}")]
+pub async fn accept_candidate(
+    state: web::Data<AppState>,
+    path: web::Path<(i32, Platform, i32)>,
+) -> Result<Json<Resp<Candidate>>> {
+    let (anilist_id, platform, rank) = path.into_inner();
+    let candidate = state
+        .db
+        .accept_candidate(anilist_id, platform, rank)
+        .await?;
+    Ok(Json(Resp::ok(Some(candidate))))
+}
@@ -0,0 +1,70 @@
+use crate::api::types::{Resp, ScheduleRequest};
+use crate::errors::Result;
+use crate::job::scheduler::{ScheduleDetails, ScheduleRunDetails};
+use crate::server::AppState;
+use actix_web::{
+    get, post,
+    web::{self, Json},
+};
+
+#[post("/api/schedule/create")]
+pub async fn create_schedule(
+    state: web::Data<AppState>,
+    request: web::Json<ScheduleRequest>,
+) -> Result<Json<Resp<i32>>> {
+    let id = state
+        .scheduler
+        .create_schedule(request.into_inner())
+        .await?;
+    Ok(Json(Resp::ok(Some(id))))
+}
+
+#[get("/api/schedule/list")]
+pub async fn list_schedules(
+    state: web::Data<AppState>,
+) -> Result<Json<Resp<Vec<ScheduleDetails>>>> {
+    let schedules = state.scheduler.list_schedules().await?;
+    Ok(Json(Resp::ok(Some(schedules))))
+}
+
+#[post("/api/schedule/{id}/update")]
+pub async fn update_schedule(
+    state: web::Data<AppState>,
+    path: web::Path<i32>,
+    request: web::Json<ScheduleRequest>,
+) -> Result<Json<Resp<()>>> {
+    state
+        .scheduler
+        .update_schedule(path.into_inner(), request.into_inner())
+        .await?;
+    Ok(Json(Resp::ok(Some(()))))
+}
+
+/// 立即执行一次计划，计划正在执行时返回 false
+#[get("/api/schedule/{id}/run")]
+pub async fn run_schedule(
+    state: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> Result<Json<Resp<bool>>> {
+    let started = state.scheduler.run_now(path.into_inner()).await?;
+    Ok(Json(Resp::ok(Some(started))))
+}
+
+#[get("/api/schedule/{id}/remove")]
+pub async fn remove_schedule(
+    state: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> Result<Json<Resp<()>>> {
+    state.scheduler.remove_schedule(path.into_inner()).await?;
+    Ok(Json(Resp::ok(Some(()))))
+}
+
+/// 计划最近的执行记录，按时间倒序
+#[get("/api/schedule/{id}/runs")]
+pub async fn schedule_runs(
+    state: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> Result<Json<Resp<Vec<ScheduleRunDetails>>>> {
+    let runs = state.scheduler.list_runs(path.into_inner()).await?;
+    Ok(Json(Resp::ok(Some(runs))))
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
-use crate::models::enums::{Platform, ReviewStatus};
+use crate::agent::consensus::ModelRef;
+use crate::models::enums::{MediaType, Platform, ReviewStatus};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Resp<T> {
@@ -69,6 +70,17 @@ pub struct Mapping {
 pub struct CompactMapping {
     pub id: Option<String>,
     pub platform: Platform,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub episodes: Vec<CompactEpisode>,
+}
+
+/// AniList 的第 episode 集对应平台的第 season 季第 platform_episode 集
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompactEpisode {
+    pub episode: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season: Option<i32>,
+    pub platform_episode: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -81,6 +93,117 @@ pub struct CompactAnime {
     pub mappings: Vec<CompactMapping>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateJobRequest {
+    pub platform: Platform,
+    pub provider: String,
+    /// 不指定时使用 provider 的默认模型
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub filter: JobFilter,
+    #[serde(flatten)]
+    pub options: JobOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateConsensusJobRequest {
+    pub platform: Platform,
+    pub models: Vec<ModelRef>,
+    #[serde(default)]
+    pub filter: JobFilter,
+    #[serde(flatten)]
+    pub options: JobOptions,
+}
+
+/// 任务选择动画的条件，所有条件需同时满足
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobFilter {
+    /// 起始年份（含）
+    pub year_from: Option<i32>,
+    /// 结束年份（含）
+    pub year_to: Option<i32>,
+    /// AniList 季度，如 WINTER、SPRING、SUMMER、FALL
+    pub season: Option<String>,
+    pub media_type: Option<MediaType>,
+    /// 映射在目标平台上的审核状态，为空时不限制
+    pub review_status: Vec<ReviewStatus>,
+    /// 只选择评分低于该值的映射
+    pub score_below: Option<u8>,
+    /// 只选择指定的动画，为空时不限制
+    pub anilist_ids: Vec<i32>,
+}
+
+impl Default for JobFilter {
+    /// 默认选择所有未匹配的动画
+    fn default() -> Self {
+        Self {
+            year_from: None,
+            year_to: None,
+            season: None,
+            media_type: None,
+            review_status: vec![ReviewStatus::UnMatched],
+            score_below: None,
+            anilist_ids: vec![],
+        }
+    }
+}
+
+/// 创建任务时的可选参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobOptions {
+    /// 提示词版本，不指定时使用默认版本
+    pub prompt_version: Option<String>,
+    /// 费用上限（美元），累计费用达到上限后任务自动停止
+    pub max_cost: Option<f64>,
+    /// 同时处理的动画数量，默认为1，实际请求还受 provider 的限流配置约束
+    pub concurrency: Option<usize>,
+    /// 重新匹配审核时被拒绝的动画，忽略筛选条件中的审核状态
+    #[serde(default)]
+    pub rematch_rejected: bool,
+}
+
+/// 修改任务的费用上限
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateMaxCostRequest {
+    /// 新的费用上限（美元），为空时取消上限
+    #[serde(default)]
+    pub max_cost: Option<f64>,
+}
+
+/// 创建或更新定时计划
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduleRequest {
+    pub name: String,
+    /// cron 表达式（UTC），如 `0 3 * * *`，也支持带秒的6段格式
+    pub cron: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// 需要创建匹配任务的平台，为空时包含所有平台
+    #[serde(default)]
+    pub platforms: Vec<Platform>,
+    pub provider: String,
+    /// 不指定时使用 provider 的默认模型
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 执行后重新生成导出文件
+    #[serde(default)]
+    pub export: bool,
+    #[serde(flatten)]
+    pub options: JobOptions,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PromptVersions {
+    pub default_version: String,
+    pub versions: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ManualMappingRequest {
     pub anilist_id: i32,
@@ -113,6 +236,61 @@ pub struct YearStatistic {
     pub bgmtv_dropped: usize,
 }
 
+/// 单个模型的用量统计
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageStatistic {
+    pub provider: String,
+    pub model: String,
+    /// 调用模型的匹配尝试次数
+    pub num_attempts: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// 费用（美元），价格未知时为空
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageStatistics {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+    pub models: Vec<UsageStatistic>,
+}
+
+/// 响应缓存的筛选条件，用于清除或刷新缓存
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheFilter {
+    /// 接口名称或前缀，如 bgm_tv 或 bgm_tv/search
+    pub endpoint: Option<String>,
+    /// 请求参数中包含的关键词
+    pub keyword: Option<String>,
+    /// 只处理已过期的条目
+    pub expired_only: bool,
+}
+
+/// 单个接口的缓存统计，命中次数只统计当前进程
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CacheStatistic {
+    pub endpoint: String,
+    pub entries: u64,
+    pub expired: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// 请求失败时使用过期缓存的次数
+    pub stale_hits: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheStatistics {
+    pub enabled: bool,
+    pub entries: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub stale_hits: u64,
+    pub endpoints: Vec<CacheStatistic>,
+}
+
 /// 所有年份统计数据的集合
 #[derive(Debug, Serialize, Deserialize)]
 pub struct YearStatistics {
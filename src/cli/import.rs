@@ -7,6 +7,7 @@ use tracing::info;
 
 use crate::models::db::DB;
 use crate::models::enums::{Platform, ReviewStatus};
+use crate::models::external_ids::Model as ExternalId;
 use crate::models::mappings::Model as AnimeMapping;
 use crate::models::{anime::Model as Anime, enums::MediaType};
 use anyhow::{Context, Result};
@@ -27,6 +28,26 @@ pub struct AnimeJsonMapping {
     themoviedb_id: Option<String>,
 }
 
+impl AnimeJsonMapping {
+    /// 需要保存的其他站点id，themoviedb_id 作为 TMDB 映射单独保存
+    fn external_ids(&self) -> Vec<(&'static str, String)> {
+        [
+            ("mal", self.mal_id.map(|id| id.to_string())),
+            ("anidb", self.anidb_id.map(|id| id.to_string())),
+            ("thetvdb", self.thetvdb_id.map(|id| id.to_string())),
+            ("imdb", self.imdb_id.clone()),
+            ("kitsu", self.kitsu_id.map(|id| id.to_string())),
+            ("anisearch", self.anisearch_id.map(|id| id.to_string())),
+            ("livechart", self.livechart_id.map(|id| id.to_string())),
+            ("animeplanet", self.animeplanet_id.clone()),
+            ("notifymoe", self.notifymoe_id.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(source, id)| id.map(|id| (source, id)))
+        .collect()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct AnimeObject {
@@ -77,7 +98,7 @@ pub async fn import_animes(dir: PathBuf) -> Result<()> {
     Ok(())
 }
 
-fn map_media_type(media_type: &str) -> MediaType {
+pub(crate) fn map_media_type(media_type: &str) -> MediaType {
     match media_type.to_lowercase().as_str() {
         "tv" => MediaType::TV,
         "movie" => MediaType::Movie,
@@ -93,6 +114,7 @@ pub async fn import_anime(animes: Vec<AnimeObject>) -> Result<()> {
 
     let mut anime_models = Vec::new();
     let mut mapping_models = Vec::new();
+    let mut external_id_models = Vec::new();
     for anime in animes.iter() {
         let media_type = if let Some(ref media_type) = anime.media_type {
             map_media_type(media_type)
@@ -115,6 +137,15 @@ pub async fn import_anime(animes: Vec<AnimeObject>) -> Result<()> {
         };
         anime_models.push(anime_model);
 
+        external_id_models.extend(anime.mappings.external_ids().into_iter().map(
+            |(source, external_id)| ExternalId {
+                anilist_id: anime.anilist_id,
+                source: source.to_string(),
+                external_id,
+                created_at: Utc::now(),
+            },
+        ));
+
         let bgm_review_status = if anime.bgm_id.is_some() {
             ReviewStatus::Ready
         } else {
@@ -154,6 +185,7 @@ pub async fn import_anime(animes: Vec<AnimeObject>) -> Result<()> {
     }
 
     db.batch_add_animes((anime_models, mapping_models)).await?;
+    db.save_external_ids(external_id_models).await?;
 
     Ok(())
 }
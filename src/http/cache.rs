@@ -0,0 +1,348 @@
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use super::cassette::CassetteMode;
+use crate::api::types::{CacheStatistic, CacheStatistics};
+use crate::models::db::DB;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// 没有匹配到任何配置时使用的有效期
+const DEFAULT_TTL: u64 = DAY;
+
+/// 各接口默认的有效期（秒），按最长前缀匹配接口名称。
+/// 搜索结果会随新条目上线而变化，详情类接口相对稳定
+const DEFAULT_TTLS: &[(&str, u64)] = &[
+    ("bgm_tv", WEEK),
+    ("bgm_tv/search", DAY),
+    ("tmdb", WEEK),
+    ("tmdb/search", DAY),
+    ("anilist", DAY),
+    ("anilist/season", HOUR),
+];
+
+/// 全局响应缓存，未安装时所有请求直接访问外部服务
+static RESPONSE_CACHE: OnceLock<ResponseCache> = OnceLock::new();
+
+/// 单个接口的命中统计
+#[derive(Debug, Clone, Copy, Default)]
+struct CacheCounter {
+    hits: u64,
+    misses: u64,
+    /// 请求失败时使用了过期缓存的次数
+    stale_hits: u64,
+}
+
+/// 各接口的缓存有效期
+#[derive(Debug, Clone)]
+pub struct CacheTtls {
+    ttls: HashMap<String, u64>,
+}
+
+impl CacheTtls {
+    /// 在默认配置上应用 `RESPONSE_CACHE_TTL`，格式为 `bgm_tv/search=3600,tmdb=86400`
+    pub fn from_env() -> Result<Self> {
+        let mut ttls = Self::default();
+        if let Ok(config) = std::env::var("RESPONSE_CACHE_TTL") {
+            ttls.apply(&config)?;
+        }
+        Ok(ttls)
+    }
+
+    fn apply(&mut self, config: &str) -> Result<()> {
+        for item in config.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (endpoint, seconds) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("无效的缓存有效期配置: {}", item))?;
+            let seconds = seconds
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("无效的缓存有效期: {}", item))?;
+            self.ttls.insert(endpoint.trim().to_string(), seconds);
+        }
+        Ok(())
+    }
+
+    /// 接口的有效期（秒），`bgm_tv` 同时匹配 `bgm_tv/search` 等子接口
+    pub fn get(&self, endpoint: &str) -> u64 {
+        self.ttls
+            .iter()
+            .filter(|(prefix, _)| {
+                endpoint == prefix.as_str()
+                    || endpoint
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, ttl)| *ttl)
+            .unwrap_or(DEFAULT_TTL)
+    }
+}
+
+impl Default for CacheTtls {
+    fn default() -> Self {
+        Self {
+            ttls: DEFAULT_TTLS
+                .iter()
+                .map(|(endpoint, ttl)| (endpoint.to_string(), *ttl))
+                .collect(),
+        }
+    }
+}
+
+/// 规范化请求参数作为缓存键：对象按键排序并去掉空值，字符串合并多余空白
+pub fn normalize_params(params: &Value) -> String {
+    fn normalize(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .filter(|(_, value)| !value.is_null())
+                    .map(|(key, value)| (key.clone(), normalize(value)))
+                    .collect::<BTreeMap<_, _>>()
+                    .into_iter()
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(normalize).collect()),
+            Value::String(s) => Value::String(s.split_whitespace().collect::<Vec<_>>().join(" ")),
+            other => other.clone(),
+        }
+    }
+    normalize(params).to_string()
+}
+
+/// 基于数据库的外部接口响应缓存
+pub struct ResponseCache {
+    db: DB,
+    ttls: CacheTtls,
+    counters: Mutex<HashMap<String, CacheCounter>>,
+}
+
+impl ResponseCache {
+    pub fn new(db: DB, ttls: CacheTtls) -> Self {
+        Self {
+            db,
+            ttls,
+            counters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 根据环境变量安装全局缓存，`RESPONSE_CACHE_ENABLED=false` 或开启录制/回放时不启用
+    pub fn install_from_env(db: &DB) -> Result<bool> {
+        let enabled = std::env::var("RESPONSE_CACHE_ENABLED")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+        if !enabled || CassetteMode::from_env().is_some() {
+            return Ok(false);
+        }
+
+        let cache = Self::new(db.clone(), CacheTtls::from_env()?);
+        let installed = RESPONSE_CACHE.set(cache).is_ok();
+        if installed {
+            info!("已启用外部接口响应缓存");
+        }
+        Ok(installed)
+    }
+
+    pub fn global() -> Option<&'static ResponseCache> {
+        RESPONSE_CACHE.get()
+    }
+
+    fn count(&self, endpoint: &str, update: impl FnOnce(&mut CacheCounter)) {
+        let mut counters = self.counters.lock().unwrap();
+        update(counters.entry(endpoint.to_string()).or_default());
+    }
+
+    /// 读取缓存，`allow_stale` 为 true 时也返回已过期的条目
+    async fn lookup(&self, endpoint: &str, params: &str, allow_stale: bool) -> Option<String> {
+        match self.db.get_response_cache(endpoint, params).await {
+            Ok(Some(entry)) if allow_stale || entry.expires_at > Utc::now() => Some(entry.body),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("读取响应缓存失败: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn store(&self, endpoint: &str, params: &str, body: &str) {
+        let expires_at = Utc::now() + Duration::seconds(self.ttls.get(endpoint) as i64);
+        if let Err(e) = self
+            .db
+            .put_response_cache(endpoint, params, body, expires_at)
+            .await
+        {
+            warn!("写入响应缓存失败: {}", e);
+        }
+    }
+
+    /// 有效缓存命中时直接返回，否则请求并写入缓存；请求失败时退回到过期的缓存
+    async fn get_or_fetch<E, F, Fut>(
+        &self,
+        endpoint: &str,
+        params: &Value,
+        fetch: F,
+    ) -> Result<Option<String>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<String>, E>>,
+        E: std::fmt::Display,
+    {
+        let params = normalize_params(params);
+        if let Some(body) = self.lookup(endpoint, &params, false).await {
+            self.count(endpoint, |c| c.hits += 1);
+            return Ok(Some(body));
+        }
+        self.count(endpoint, |c| c.misses += 1);
+
+        match fetch().await {
+            Ok(Some(body)) => {
+                self.store(endpoint, &params, &body).await;
+                Ok(Some(body))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => match self.lookup(endpoint, &params, true).await {
+                Some(body) => {
+                    warn!("请求失败，使用过期缓存: {} {}", endpoint, e);
+                    self.count(endpoint, |c| c.stale_hits += 1);
+                    Ok(Some(body))
+                }
+                None => Err(e),
+            },
+        }
+    }
+}
+
+/// 通过全局缓存获取原始响应，`fetch` 返回 None 表示资源不存在，不会被缓存
+pub async fn cached_text<E, F, Fut>(
+    endpoint: &str,
+    params: &Value,
+    fetch: F,
+) -> Result<Option<String>, E>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Option<String>, E>>,
+    E: std::fmt::Display,
+{
+    match ResponseCache::global() {
+        Some(cache) => cache.get_or_fetch(endpoint, params, fetch).await,
+        None => fetch().await,
+    }
+}
+
+/// 合并数据库中的缓存条目数和本进程内的命中统计
+pub async fn cache_statistics(db: &DB) -> Result<CacheStatistics> {
+    let mut endpoints: BTreeMap<String, CacheStatistic> = BTreeMap::new();
+    for (endpoint, entries, expired) in db.get_response_cache_counts().await? {
+        endpoints.insert(
+            endpoint.clone(),
+            CacheStatistic {
+                endpoint,
+                entries,
+                expired,
+                ..Default::default()
+            },
+        );
+    }
+
+    let cache = ResponseCache::global();
+    if let Some(cache) = cache {
+        for (endpoint, counter) in cache.counters.lock().unwrap().iter() {
+            let statistic = endpoints
+                .entry(endpoint.clone())
+                .or_insert_with(|| CacheStatistic {
+                    endpoint: endpoint.clone(),
+                    ..Default::default()
+                });
+            statistic.hits = counter.hits;
+            statistic.misses = counter.misses;
+            statistic.stale_hits = counter.stale_hits;
+        }
+    }
+
+    let endpoints: Vec<CacheStatistic> = endpoints.into_values().collect();
+    Ok(CacheStatistics {
+        enabled: cache.is_some(),
+        entries: endpoints.iter().map(|s| s.entries).sum(),
+        hits: endpoints.iter().map(|s| s.hits).sum(),
+        misses: endpoints.iter().map(|s| s.misses).sum(),
+        stale_hits: endpoints.iter().map(|s| s.stale_hits).sum(),
+        endpoints,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_params() {
+        let a = normalize_params(
+            &json!({"keyword": "  葬送的 芙莉莲 ", "filter": {"type": [2]}, "tag": null}),
+        );
+        let b = normalize_params(&json!({"filter": {"type": [2]}, "keyword": "葬送的 芙莉莲"}));
+        assert_eq!(a, b);
+        assert_ne!(a, normalize_params(&json!({"keyword": "葬送的芙莉莲"})));
+    }
+
+    #[test]
+    fn test_cache_ttls() {
+        let mut ttls = CacheTtls::default();
+        assert_eq!(ttls.get("bgm_tv/search"), DAY);
+        assert_eq!(ttls.get("bgm_tv/subject"), WEEK);
+        assert_eq!(ttls.get("bgm_tvx"), DEFAULT_TTL);
+        ttls.apply("bgm_tv=60, tmdb/search/tv=120").unwrap();
+        assert_eq!(ttls.get("bgm_tv/subject"), 60);
+        assert_eq!(ttls.get("bgm_tv/search"), DAY);
+        assert_eq!(ttls.get("tmdb/search/tv"), 120);
+        assert!(ttls.apply("tmdb").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_response_cache() {
+        let db = DB::new_for_test().await.unwrap();
+        let cache = ResponseCache::new(db.clone(), CacheTtls::default());
+        let params = json!({"id": 1});
+
+        let body = cache
+            .get_or_fetch("bgm_tv/subject", &params, || async {
+                Ok::<_, String>(Some("{\"id\":1}".to_string()))
+            })
+            .await
+            .unwrap();
+        assert_eq!(body.as_deref(), Some("{\"id\":1}"));
+
+        // 命中后不再请求
+        let body = cache
+            .get_or_fetch("bgm_tv/subject", &params, || async {
+                Err::<Option<String>, _>("不应请求".to_string())
+            })
+            .await
+            .unwrap();
+        assert_eq!(body.as_deref(), Some("{\"id\":1}"));
+
+        // 过期后请求失败时使用过期缓存
+        db.expire_response_cache(&Default::default()).await.unwrap();
+        let body = cache
+            .get_or_fetch("bgm_tv/subject", &params, || async {
+                Err::<Option<String>, _>("请求失败".to_string())
+            })
+            .await
+            .unwrap();
+        assert_eq!(body.as_deref(), Some("{\"id\":1}"));
+
+        let counter = cache.counters.lock().unwrap()["bgm_tv/subject"];
+        assert_eq!(
+            (counter.hits, counter.misses, counter.stale_hits),
+            (1, 2, 1)
+        );
+    }
+}
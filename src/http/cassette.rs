@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+#[cfg(test)]
+use std::sync::OnceLock;
+
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, dev::ServerHandle, web};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::http::{Service, set_base_url_override};
+
+const DEFAULT_CASSETTE_DIR: &str = "cassettes";
+
+/// 不参与请求匹配、也不会写入磁带的敏感查询参数
+const SECRET_QUERY_PARAMS: [&str; 2] = ["api_key", "access_token"];
+
+/// 转发给真实服务时保留的请求头
+const FORWARDED_HEADERS: [&str; 4] = ["content-type", "accept", "user-agent", "authorization"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// 转发到真实服务并记录请求/响应
+    Record,
+    /// 只从磁带中读取响应，不访问网络
+    Replay,
+}
+
+impl CassetteMode {
+    /// 通过 `HTTP_CASSETTE_MODE=record|replay` 开启
+    pub fn from_env() -> Option<Self> {
+        match std::env::var("HTTP_CASSETTE_MODE")
+            .ok()?
+            .to_lowercase()
+            .as_str()
+        {
+            "record" => Some(CassetteMode::Record),
+            "replay" => Some(CassetteMode::Replay),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+impl RecordedRequest {
+    fn new(method: &str, path: &str, query: &str, body: &[u8]) -> Self {
+        Self {
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            query: normalize_query(query),
+            body: normalize_body(body),
+        }
+    }
+}
+
+impl PartialEq for RecordedRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.method == other.method
+            && self.path == other.path
+            && self.query == other.query
+            && body_eq(&self.body, &other.body)
+    }
+}
+
+/// JSON 请求体按值比较，忽略字段顺序
+fn body_eq(a: &str, b: &str) -> bool {
+    match (
+        serde_json::from_str::<serde_json::Value>(a),
+        serde_json::from_str::<serde_json::Value>(b),
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// 去除敏感参数并排序，保证同一请求得到相同的查询串
+fn normalize_query(query: &str) -> String {
+    let mut pairs: Vec<&str> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| {
+            let key = pair.split('=').next().unwrap_or_default();
+            !SECRET_QUERY_PARAMS.contains(&key)
+        })
+        .collect();
+    pairs.sort();
+    pairs.join("&")
+}
+
+/// JSON 请求体重新序列化以去除空白差异
+fn normalize_body(body: &[u8]) -> String {
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(value) => value.to_string(),
+        Err(_) => String::from_utf8_lossy(body).to_string(),
+    }
+}
+
+/// 按服务分文件保存的磁带
+struct Cassettes {
+    dir: PathBuf,
+    interactions: HashMap<Service, Vec<Interaction>>,
+}
+
+impl Cassettes {
+    fn load(dir: &Path) -> Result<Self> {
+        let mut interactions = HashMap::new();
+        for service in Service::ALL {
+            let path = Self::path(dir, service);
+            if path.exists() {
+                let content = std::fs::read_to_string(&path)?;
+                interactions.insert(service, serde_json::from_str(&content)?);
+            }
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            interactions,
+        })
+    }
+
+    fn path(dir: &Path, service: Service) -> PathBuf {
+        dir.join(format!("{}.json", service.name()))
+    }
+
+    fn find(&self, service: Service, request: &RecordedRequest) -> Option<&RecordedResponse> {
+        self.interactions
+            .get(&service)?
+            .iter()
+            .find(|interaction| &interaction.request == request)
+            .map(|interaction| &interaction.response)
+    }
+
+    fn record(&mut self, service: Service, interaction: Interaction) -> Result<()> {
+        let interactions = self.interactions.entry(service).or_default();
+        interactions.retain(|item| item.request != interaction.request);
+        interactions.push(interaction);
+
+        std::fs::create_dir_all(&self.dir)?;
+        let file = std::fs::File::create(Self::path(&self.dir, service))?;
+        serde_json::to_writer_pretty(file, interactions)?;
+        Ok(())
+    }
+}
+
+struct CassetteState {
+    mode: CassetteMode,
+    cassettes: Mutex<Cassettes>,
+    client: reqwest::Client,
+}
+
+/// 在本地端口上替代 BgmTV/TMDB/AniList 的服务，路径格式为 `/{service}/{原始路径}`
+pub struct CassetteServer {
+    addr: SocketAddr,
+    handle: ServerHandle,
+}
+
+impl CassetteServer {
+    pub async fn start(mode: CassetteMode, dir: impl AsRef<Path>) -> Result<Self> {
+        let state = web::Data::new(CassetteState {
+            mode,
+            cassettes: Mutex::new(Cassettes::load(dir.as_ref())?),
+            client: reqwest::Client::new(),
+        });
+
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(state.clone())
+                .app_data(web::PayloadConfig::new(16 * 1024 * 1024))
+                .default_service(web::to(handle_request))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))?;
+
+        let addr = *server
+            .addrs()
+            .first()
+            .ok_or_else(|| anyhow!("磁带服务绑定端口失败"))?;
+        let server = server.run();
+        let handle = server.handle();
+        tokio::spawn(server);
+
+        info!("HTTP磁带服务已启动({:?}): http://{}", mode, addr);
+        Ok(Self { addr, handle })
+    }
+
+    /// 根据环境变量启动磁带服务，并将所有客户端指向它
+    pub async fn install_from_env() -> Result<Option<Self>> {
+        let Some(mode) = CassetteMode::from_env() else {
+            return Ok(None);
+        };
+        let dir =
+            std::env::var("HTTP_CASSETTE_DIR").unwrap_or_else(|_| DEFAULT_CASSETTE_DIR.to_string());
+        let server = Self::start(mode, dir).await?;
+        if !set_base_url_override(server.root_url(), mode) {
+            return Err(anyhow!("磁带服务已经安装"));
+        }
+        Ok(Some(server))
+    }
+
+    pub fn root_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// 指定服务在替身上的地址
+    pub fn base_url(&self, service: Service) -> String {
+        format!("{}/{}", self.root_url(), service.name())
+    }
+
+    pub async fn stop(self) {
+        self.handle.stop(true).await;
+    }
+}
+
+/// 测试用：在独立线程上启动回放仓库中磁带的服务，并将所有客户端指向它。
+/// 地址只能设置一次，整个测试进程共用同一个服务
+#[cfg(test)]
+pub(crate) fn install_test_replay() {
+    static INSTALLED: OnceLock<()> = OnceLock::new();
+    INSTALLED.get_or_init(|| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async move {
+                let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(DEFAULT_CASSETTE_DIR);
+                let server = CassetteServer::start(CassetteMode::Replay, dir)
+                    .await
+                    .unwrap();
+                tx.send(server.root_url()).unwrap();
+                std::future::pending::<()>().await;
+            });
+        });
+        let root = rx.recv().unwrap();
+        assert!(set_base_url_override(root, CassetteMode::Replay));
+    });
+}
+
+async fn handle_request(
+    req: HttpRequest,
+    body: web::Bytes,
+    state: web::Data<CassetteState>,
+) -> HttpResponse {
+    let path = req.path().trim_start_matches('/');
+    let (service_name, rest) = path.split_once('/').unwrap_or((path, ""));
+    let Some(service) = Service::from_name(service_name) else {
+        return HttpResponse::NotFound().body(format!("未知的服务: {}", service_name));
+    };
+    let rest = if rest.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", rest)
+    };
+
+    let recorded = RecordedRequest::new(req.method().as_str(), &rest, req.query_string(), &body);
+
+    let replayed = state
+        .cassettes
+        .lock()
+        .unwrap()
+        .find(service, &recorded)
+        .cloned();
+    if let Some(response) = replayed {
+        return to_http_response(&response);
+    }
+
+    if state.mode == CassetteMode::Replay {
+        warn!("磁带中没有对应的记录: {:?} {:?}", service, recorded);
+        return HttpResponse::NotImplemented().body(format!(
+            "磁带中没有对应的记录: {} {}{}?{}",
+            recorded.method,
+            service.name(),
+            recorded.path,
+            recorded.query
+        ));
+    }
+
+    match forward(&state.client, service, &req, &rest, body).await {
+        Ok(response) => {
+            let interaction = Interaction {
+                request: recorded,
+                response: response.clone(),
+            };
+            if let Err(e) = state.cassettes.lock().unwrap().record(service, interaction) {
+                warn!("写入磁带失败: {}", e);
+            }
+            to_http_response(&response)
+        }
+        Err(e) => HttpResponse::BadGateway().body(format!("转发请求失败: {}", e)),
+    }
+}
+
+async fn forward(
+    client: &reqwest::Client,
+    service: Service,
+    req: &HttpRequest,
+    path: &str,
+    body: web::Bytes,
+) -> Result<RecordedResponse> {
+    let mut url = format!("{}{}", service.upstream_url(), path);
+    if !req.query_string().is_empty() {
+        url = format!("{}?{}", url, req.query_string());
+    }
+
+    let method = reqwest::Method::from_bytes(req.method().as_str().as_bytes())?;
+    let mut builder = client.request(method, url).body(body.to_vec());
+    for name in FORWARDED_HEADERS {
+        if let Some(value) = req.headers().get(name).and_then(|v| v.to_str().ok()) {
+            builder = builder.header(name, value);
+        }
+    }
+
+    let response = builder.send().await?;
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+    let body = response.text().await?;
+
+    Ok(RecordedResponse {
+        status,
+        content_type,
+        body,
+    })
+}
+
+fn to_http_response(response: &RecordedResponse) -> HttpResponse {
+    let status = actix_web::http::StatusCode::from_u16(response.status)
+        .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+    let mut builder = HttpResponse::build(status);
+    if let Some(ref content_type) = response.content_type {
+        builder.content_type(content_type.as_str());
+    }
+    builder.body(response.body.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_request() {
+        let a = RecordedRequest::new("get", "/search/tv", "query=a&api_key=secret&page=1", b"");
+        let b = RecordedRequest::new("GET", "/search/tv", "page=1&query=a", b"");
+        assert_eq!(a, b);
+        assert!(!a.query.contains("secret"));
+
+        let a = RecordedRequest::new("POST", "/", "", br#"{"b": 1, "a": 2}"#);
+        let b = RecordedRequest::new("POST", "/", "", br#"{"a":2,"b":1}"#);
+        assert_eq!(a, b);
+    }
+
+    #[tokio::test]
+    async fn test_replay() {
+        let dir = std::env::temp_dir().join(format!("cassette-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let interactions = vec![Interaction {
+            request: RecordedRequest::new("GET", "/v0/subjects/1", "", b""),
+            response: RecordedResponse {
+                status: 200,
+                content_type: Some("application/json".to_string()),
+                body: r#"{"id":1}"#.to_string(),
+            },
+        }];
+        std::fs::write(
+            dir.join("bgm_tv.json"),
+            serde_json::to_string(&interactions).unwrap(),
+        )
+        .unwrap();
+
+        let server = CassetteServer::start(CassetteMode::Replay, &dir)
+            .await
+            .unwrap();
+        let base_url = server.base_url(Service::BgmTv);
+
+        let resp = reqwest::get(format!("{}/v0/subjects/1", base_url))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.text().await.unwrap(), r#"{"id":1}"#);
+
+        let resp = reqwest::get(format!("{}/v0/subjects/2", base_url))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 501);
+
+        server.stop().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use reqwest::header::{HeaderMap, RETRY_AFTER, USER_AGENT};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use tokio::time::sleep;
+use tokio_retry::strategy::jitter;
+use tracing::warn;
+
+use super::{Service, is_replaying};
+
+const DEFAULT_USER_AGENT: &str = "lyqingye/anime-matcher-agent";
+
+/// 所有服务共用的出站客户端，首次使用时根据环境变量创建
+static CLIENTS: OnceLock<HashMap<Service, HttpClient>> = OnceLock::new();
+
+/// 单个服务的限流和重试策略
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HttpPolicy {
+    /// 每分钟最多发出的请求数
+    pub requests_per_minute: u32,
+    /// 失败后最多重试的次数
+    pub max_retries: u32,
+    /// 第一次重试前的等待时间，之后每次翻倍
+    pub base_delay: Duration,
+    /// 单次等待时间的上限，服务端返回的 `Retry-After` 不受此限制
+    pub max_delay: Duration,
+}
+
+impl HttpPolicy {
+    /// 各服务的默认策略，参考各自公开的限流规则
+    pub fn default_for(service: Service) -> Self {
+        let requests_per_minute = match service {
+            Service::BgmTv => 120,
+            Service::Tmdb => 240,
+            Service::AniList => 20,
+        };
+        Self {
+            requests_per_minute,
+            max_retries: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    /// 在默认策略上应用环境变量，如 `TMDB_RATE_LIMIT`、`TMDB_MAX_RETRIES`、
+    /// `TMDB_RETRY_BASE_MS`、`TMDB_RETRY_MAX_MS`
+    pub fn from_env(service: Service) -> Result<Self> {
+        fn var<T: std::str::FromStr>(key: String) -> Result<Option<T>> {
+            match std::env::var(&key) {
+                Ok(value) => value
+                    .trim()
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| anyhow!("无效的配置 {}: {}", key, value)),
+                Err(_) => Ok(None),
+            }
+        }
+
+        let prefix = service.env_prefix();
+        let mut policy = Self::default_for(service);
+        if let Some(limit) = var(format!("{}_RATE_LIMIT", prefix))? {
+            policy.requests_per_minute = limit;
+        }
+        if let Some(retries) = var(format!("{}_MAX_RETRIES", prefix))? {
+            policy.max_retries = retries;
+        }
+        if let Some(ms) = var(format!("{}_RETRY_BASE_MS", prefix))? {
+            policy.base_delay = Duration::from_millis(ms);
+        }
+        if let Some(ms) = var(format!("{}_RETRY_MAX_MS", prefix))? {
+            policy.max_delay = Duration::from_millis(ms);
+        }
+        if policy.requests_per_minute == 0 {
+            return Err(anyhow!("{}_RATE_LIMIT 必须大于0", prefix));
+        }
+        Ok(policy)
+    }
+
+    /// 第 `attempt` 次重试前的等待时间：指数退避，一半固定一半随机抖动
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        delay / 2 + jitter(delay / 2)
+    }
+}
+
+/// 可以重试的状态码：限流、超时和暂时性的服务端错误，501、505 等重试也不会成功
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// 可以重试的请求错误：连接失败、超时等网络问题
+fn is_retryable_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect()
+}
+
+/// 解析 `Retry-After`，支持秒数和 HTTP 日期两种格式
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// 带限流和重试的出站 HTTP 客户端，每个外部服务一个实例
+pub struct HttpClient {
+    service: Service,
+    client: reqwest::Client,
+    limiter: DefaultDirectRateLimiter,
+    policy: HttpPolicy,
+}
+
+impl HttpClient {
+    pub fn new(service: Service, policy: HttpPolicy) -> Self {
+        let mut builder = reqwest::Client::builder();
+        if let Ok(http_proxy) = std::env::var("HTTP_PROXY") {
+            builder = builder.proxy(reqwest::Proxy::http(http_proxy).unwrap());
+        }
+        if let Ok(https_proxy) = std::env::var("HTTPS_PROXY") {
+            builder = builder.proxy(reqwest::Proxy::https(https_proxy).unwrap());
+        }
+
+        let quota = Quota::per_minute(
+            NonZeroU32::new(policy.requests_per_minute).unwrap_or(NonZeroU32::MIN),
+        );
+        Self {
+            service,
+            client: builder.build().unwrap(),
+            limiter: RateLimiter::direct(quota),
+            policy,
+        }
+    }
+
+    /// 服务共用的客户端，同一服务的所有请求共享限流额度
+    pub fn shared(service: Service) -> &'static HttpClient {
+        let clients = CLIENTS.get_or_init(|| {
+            Service::ALL
+                .into_iter()
+                .map(|service| {
+                    let policy = HttpPolicy::from_env(service).unwrap_or_else(|e| {
+                        warn!("{}，使用默认策略", e);
+                        HttpPolicy::default_for(service)
+                    });
+                    (service, HttpClient::new(service, policy))
+                })
+                .collect()
+        });
+        &clients[&service]
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url).header(USER_AGENT, DEFAULT_USER_AGENT)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url).header(USER_AGENT, DEFAULT_USER_AGENT)
+    }
+
+    /// 发送请求，遇到网络错误、限流或服务端错误时按策略重试。
+    /// 其他状态码（包括 404 等客户端错误）直接返回，由调用方处理
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        // 回放时磁带中缺少的请求重试也不会命中
+        let max_retries = if is_replaying() {
+            0
+        } else {
+            self.policy.max_retries
+        };
+        let mut attempt = 0;
+        loop {
+            // 请求体无法复制时只发送一次
+            let Some(current) = request.try_clone() else {
+                self.limiter.until_ready().await;
+                return request.send().await;
+            };
+            self.limiter.until_ready().await;
+
+            let (delay, reason) = match current.send().await {
+                Ok(response) if is_retryable_status(response.status()) => {
+                    if attempt >= max_retries {
+                        return Ok(response);
+                    }
+                    let delay = retry_after(response.headers())
+                        .unwrap_or_else(|| self.policy.backoff(attempt));
+                    (delay, response.status().to_string())
+                }
+                Ok(response) => return Ok(response),
+                Err(e) if is_retryable_error(&e) && attempt < max_retries => {
+                    (self.policy.backoff(attempt), e.to_string())
+                }
+                Err(e) => return Err(e),
+            };
+
+            attempt += 1;
+            warn!(
+                "{} 请求失败（{}），{}毫秒后第{}次重试",
+                self.service.name(),
+                reason,
+                delay.as_millis(),
+                attempt
+            );
+            sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_backoff() {
+        let policy = HttpPolicy {
+            requests_per_minute: 60,
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+        };
+        for attempt in 0..8 {
+            let delay = policy.backoff(attempt);
+            let full = Duration::from_millis(100 * 2u64.pow(attempt)).min(policy.max_delay);
+            assert!(delay >= full / 2 && delay <= full, "{:?}", delay);
+        }
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("30"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(30)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable_status(StatusCode::GATEWAY_TIMEOUT));
+        assert!(is_retryable_status(StatusCode::REQUEST_TIMEOUT));
+        assert!(!is_retryable_status(StatusCode::NOT_IMPLEMENTED));
+        assert!(!is_retryable_status(StatusCode::HTTP_VERSION_NOT_SUPPORTED));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+    }
+}
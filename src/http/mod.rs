@@ -0,0 +1,83 @@
+use std::sync::OnceLock;
+
+pub mod cache;
+pub mod cassette;
+pub mod client;
+
+/// 所有出站 HTTP 请求所访问的外部服务
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+    BgmTv,
+    Tmdb,
+    AniList,
+}
+
+/// 录制/回放时替代真实服务的本地地址及磁带模式
+static BASE_URL_OVERRIDE: OnceLock<(String, cassette::CassetteMode)> = OnceLock::new();
+
+impl Service {
+    pub const ALL: [Service; 3] = [Service::BgmTv, Service::Tmdb, Service::AniList];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Service::BgmTv => "bgm_tv",
+            Service::Tmdb => "tmdb",
+            Service::AniList => "anilist",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|service| service.name() == name)
+    }
+
+    /// 服务相关环境变量的前缀，如 `TMDB_RATE_LIMIT`
+    fn env_prefix(&self) -> &'static str {
+        match self {
+            Service::BgmTv => "BGM",
+            Service::Tmdb => "TMDB",
+            Service::AniList => "ANILIST",
+        }
+    }
+
+    fn env_key(&self) -> &'static str {
+        match self {
+            Service::BgmTv => "BGM_API_URL",
+            Service::Tmdb => "TMDB_API_URL",
+            Service::AniList => "ANILIST_API_URL",
+        }
+    }
+
+    fn default_url(&self) -> &'static str {
+        match self {
+            Service::BgmTv => "https://api.bgm.tv",
+            Service::Tmdb => "https://api.themoviedb.org/3",
+            Service::AniList => "https://graphql.anilist.co",
+        }
+    }
+
+    /// 真实服务地址，可通过环境变量覆盖
+    pub fn upstream_url(&self) -> String {
+        std::env::var(self.env_key()).unwrap_or_else(|_| self.default_url().to_string())
+    }
+
+    /// 客户端实际应访问的地址，启用录制/回放时指向本地替身服务
+    pub fn base_url(&self) -> String {
+        match BASE_URL_OVERRIDE.get() {
+            Some((root, _)) => format!("{}/{}", root, self.name()),
+            None => self.upstream_url(),
+        }
+    }
+}
+
+/// 将所有服务的地址指向本地替身服务，只能设置一次
+pub(crate) fn set_base_url_override(root: String, mode: cassette::CassetteMode) -> bool {
+    BASE_URL_OVERRIDE.set((root, mode)).is_ok()
+}
+
+/// 当前是否处于回放模式，回放时不需要真实的 API key
+pub fn is_replaying() -> bool {
+    matches!(
+        BASE_URL_OVERRIDE.get(),
+        Some((_, cassette::CassetteMode::Replay))
+    )
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::models::enums::Platform;
+
+/// 事件通道容量，订阅者处理过慢时会丢弃最早的事件
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// 任务执行过程中产生的事件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum JobEvent {
+    Started {
+        platform: Platform,
+        job_id: i32,
+    },
+    ItemMatched {
+        platform: Platform,
+        job_id: i32,
+        anilist_id: i32,
+        platform_id: String,
+        score: u8,
+    },
+    ItemFailed {
+        platform: Platform,
+        job_id: i32,
+        anilist_id: i32,
+        error: String,
+    },
+    Paused {
+        platform: Platform,
+        job_id: i32,
+    },
+    Completed {
+        platform: Platform,
+        job_id: i32,
+    },
+    /// 任务因错误或达到费用上限而停止
+    Stopped {
+        platform: Platform,
+        job_id: i32,
+        reason: String,
+    },
+}
+
+/// 任务事件的广播通道，可同时有多个订阅者
+#[derive(Debug, Clone)]
+pub struct JobEvents {
+    sender: broadcast::Sender<JobEvent>,
+}
+
+impl JobEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// 发送事件，没有订阅者时直接丢弃
+    pub fn send(&self, event: JobEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for JobEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_job_events() {
+        let events = JobEvents::new();
+        // 没有订阅者时发送不会出错
+        events.send(JobEvent::Started {
+            platform: Platform::BgmTv,
+            job_id: 1,
+        });
+
+        let mut receiver = events.subscribe();
+        events.send(JobEvent::ItemMatched {
+            platform: Platform::BgmTv,
+            job_id: 1,
+            anilist_id: 1,
+            platform_id: "100".to_string(),
+            score: 90,
+        });
+        let event = receiver.recv().await.unwrap();
+        assert!(matches!(event, JobEvent::ItemMatched { anilist_id: 1, .. }));
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "ItemMatched");
+        assert_eq!(json["platform_id"], "100");
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde_json::json;
+use tracing::info;
+
+use crate::anilist::{AniListClient, AniListSeason, AniListSeasonMedia};
+use crate::cli::import::map_media_type;
+use crate::models::anime::Model as Anime;
+use crate::models::db::DB;
+use crate::models::enums::{MediaType, Platform, ReviewStatus};
+use crate::models::mappings::Model as AnimeMapping;
+
+/// 单个季度的导入结果
+#[derive(Debug, Default)]
+pub struct IngestResult {
+    /// 从 AniList 获取到的动画数量
+    pub num_fetched: usize,
+    /// 新导入的动画id
+    pub added: Vec<i32>,
+}
+
+/// 导入 AniList 指定季度的动画，已存在的动画保持不变
+pub async fn ingest_season(
+    db: &DB,
+    anilist: &AniListClient,
+    season: AniListSeason,
+    year: i32,
+) -> Result<IngestResult> {
+    let media = anilist.get_season_media(season, year).await?;
+    let ids: Vec<i32> = media.iter().map(|media| media.id).collect();
+    let existing = db.get_existing_anilist_ids(&ids).await?;
+
+    let mut anime_models = Vec::new();
+    let mut mapping_models = Vec::new();
+    let mut added = Vec::new();
+    for media in media.iter().filter(|media| !existing.contains(&media.id)) {
+        // AniList 分页结果可能重复
+        if added.contains(&media.id) {
+            continue;
+        }
+        let (anime, mappings) = to_models(media, season, year);
+        anime_models.push(anime);
+        mapping_models.extend(mappings);
+        added.push(media.id);
+    }
+
+    info!(
+        "导入 {} {}: 获取 {} 部，新增 {} 部",
+        year,
+        season.as_str(),
+        media.len(),
+        added.len()
+    );
+    if !added.is_empty() {
+        db.batch_add_animes((anime_models, mapping_models)).await?;
+    }
+
+    Ok(IngestResult {
+        num_fetched: media.len(),
+        added,
+    })
+}
+
+/// 转换为动画及各平台未匹配的映射
+fn to_models(
+    media: &AniListSeasonMedia,
+    season: AniListSeason,
+    year: i32,
+) -> (Anime, Vec<AnimeMapping>) {
+    let mut titles: Vec<String> = Vec::new();
+    for title in [
+        media.title.native.as_ref(),
+        media.title.english.as_ref(),
+        media.title.romaji.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .chain(media.synonyms.iter())
+    {
+        if !title.is_empty() && !titles.contains(title) {
+            titles.push(title.clone());
+        }
+    }
+
+    let start_date = match (
+        media.start_date.year,
+        media.start_date.month,
+        media.start_date.day,
+    ) {
+        (Some(year), Some(month), Some(day)) => {
+            Some(format!("{:04}-{:02}-{:02}", year, month, day))
+        }
+        _ => None,
+    };
+
+    let anime = Anime {
+        anilist_id: media.id,
+        media_type: media
+            .format
+            .as_deref()
+            .map(map_media_type)
+            .unwrap_or(MediaType::Unknown),
+        titles: json!(titles).to_string(),
+        year: media.season_year.or(media.start_date.year).unwrap_or(year),
+        season: Some(
+            media
+                .season
+                .clone()
+                .unwrap_or_else(|| season.as_str().to_string()),
+        ),
+        start_date,
+        episode_count: media.episodes,
+        season_number: None,
+        episode_number: None,
+        absolute_episode_number: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    let mappings = [Platform::BgmTv, Platform::Tmdb]
+        .into_iter()
+        .map(|platform| AnimeMapping {
+            anilist_id: media.id,
+            platform,
+            platform_id: None,
+            review_status: ReviewStatus::UnMatched,
+            score: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .collect();
+
+    (anime, mappings)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::anilist::{AniListDate, AniListTitle};
+    use crate::http::Service;
+    use crate::http::cassette::{CassetteMode, CassetteServer};
+
+    #[test]
+    fn test_to_models() {
+        let media = AniListSeasonMedia {
+            id: 1,
+            format: Some("MOVIE".to_string()),
+            season: None,
+            season_year: None,
+            episodes: Some(1),
+            title: AniListTitle {
+                english: Some("Title".to_string()),
+                native: Some("タイトル".to_string()),
+                romaji: Some("Title".to_string()),
+            },
+            synonyms: vec!["别名".to_string()],
+            start_date: AniListDate {
+                year: Some(2025),
+                month: Some(4),
+                day: Some(5),
+            },
+        };
+
+        let (anime, mappings) = to_models(&media, AniListSeason::Spring, 2024);
+        assert_eq!(anime.media_type, MediaType::Movie);
+        assert_eq!(anime.titles, r#"["タイトル","Title","别名"]"#);
+        assert_eq!(anime.year, 2025);
+        assert_eq!(anime.season.as_deref(), Some("SPRING"));
+        assert_eq!(anime.start_date.as_deref(), Some("2025-04-05"));
+        assert_eq!(mappings.len(), 2);
+        assert!(
+            mappings
+                .iter()
+                .all(|mapping| mapping.review_status == ReviewStatus::UnMatched)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingest_season() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("cassettes");
+        let server = CassetteServer::start(CassetteMode::Replay, dir)
+            .await
+            .unwrap();
+        let anilist = AniListClient::with_base_url(
+            server.base_url(Service::AniList),
+            Duration::from_secs(10),
+        );
+
+        let db = DB::new_for_test().await.unwrap();
+        // 已存在的动画及其映射保持不变
+        db.batch_add_animes((
+            vec![Anime::test(400002)],
+            vec![AnimeMapping {
+                review_status: ReviewStatus::Accepted,
+                ..AnimeMapping::test(400002, Platform::BgmTv)
+            }],
+        ))
+        .await
+        .unwrap();
+
+        let result = ingest_season(&db, &anilist, AniListSeason::Spring, 2025)
+            .await
+            .unwrap();
+        assert_eq!(result.num_fetched, 2);
+        assert_eq!(result.added, vec![400001]);
+
+        let (anime, mappings) = db.get_anime(400001).await.unwrap();
+        let anime = anime.unwrap();
+        assert_eq!(anime.media_type, MediaType::TV);
+        assert!(anime.titles.contains("平野と鍵浦"));
+        assert_eq!(mappings.len(), 2);
+        assert!(
+            mappings
+                .iter()
+                .all(|mapping| mapping.review_status == ReviewStatus::UnMatched)
+        );
+
+        let (anime, mappings) = db.get_anime(400002).await.unwrap();
+        assert_eq!(anime.unwrap().media_type, MediaType::TV);
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].review_status, ReviewStatus::Accepted);
+
+        // 再次导入时没有新增
+        let result = ingest_season(&db, &anilist, AniListSeason::Spring, 2025)
+            .await
+            .unwrap();
+        assert!(result.added.is_empty());
+
+        server.stop().await;
+    }
+}
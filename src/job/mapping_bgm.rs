@@ -1,120 +1,457 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic::AssertUnwindSafe;
 use std::sync::{Arc, RwLock};
-use std::usize;
+use std::time::Instant;
 
-use crate::agent::runner::{run_mapping_bgm_tv_agent, run_mapping_tmdb_agent};
+use crate::agent::agent::{MatchResult, budget_exceeded};
+use crate::agent::budget::{MatchBudget, TokenUsage};
+use crate::agent::consensus::{MatchOutcome, ModelRef, decide_consensus};
+use crate::agent::prematch::prematch;
+use crate::agent::pricing::PriceTable;
+use crate::agent::prompt::{PromptRegistry, PromptTemplate};
+use crate::agent::provider::ProviderRegistry;
+use crate::agent::runner::{MatchOptions, MatchQuery, run_mapping_agent};
+use crate::agent::transcript::MatchTranscript;
+use crate::agent::verifier::{Verdict, verify_match};
+use crate::job::events::{JobEvent, JobEvents};
 use crate::models::anime::Model as Anime;
-use anyhow::Result;
+use crate::models::enums::JobItemOutcome;
+use crate::models::job_items::Model as JobItem;
+use crate::models::jobs::Model as Job;
+use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tracing::error;
+use tokio::sync::{Mutex, broadcast};
+use tracing::{error, info, warn};
 
 use crate::{
-    api::types::{PageQuery, QueryAnimes},
+    api::types::{JobFilter, JobOptions},
     models::{
         db::DB,
         enums::{Platform, ReviewStatus},
     },
 };
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum JobStatus {
-    Created,
-    Running,
-    Paused,
-    Completed,
-    Failed,
-}
+pub use crate::models::enums::JobStatus;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobDetails {
-    pub year: i32,
+    pub id: i32,
+    /// 任务选择动画的条件
+    pub filter: JobFilter,
     pub num_animes_to_match: usize,
     pub num_processed: usize,
     pub num_matched: usize,
+    /// 通过确定性预匹配完成、未调用模型的数量
+    pub num_prematched: usize,
     pub num_failed: usize,
+    /// 因超出预算（工具调用次数、token、超时）而中止的数量
+    pub num_budget_exceeded: usize,
+    /// 多模型结果不一致、等待人工裁决的数量
+    pub num_conflicts: usize,
+    /// 提交的条目未通过校验的数量
+    pub num_rejected: usize,
+    /// 累计的 token 用量
+    pub usage: TokenUsage,
+    /// 累计费用（美元），价格未知的模型不计入
+    pub cost: f64,
+    /// 费用上限（美元）
+    pub max_cost: Option<f64>,
     pub job_start_time: DateTime<Utc>,
     pub provider: String,
     pub model: String,
+    /// 共识模式下额外参与匹配的模型，为空时只使用 provider/model
+    pub consensus_models: Vec<ModelRef>,
+    /// 使用的提示词版本
+    pub prompt_version: String,
     pub platform: Platform,
     pub status: JobStatus,
-    pub current_index: usize,
+    /// 同时处理的动画数量
+    pub concurrency: usize,
 
+    /// 尚未开始处理的动画及其在队列中的位置
+    #[serde(skip)]
+    pending: VecDeque<(usize, Anime)>,
+    /// 正在运行的 worker 数量
     #[serde(skip)]
-    pub animes: Vec<Anime>,
+    workers: usize,
+}
+
+impl JobDetails {
+    /// 参与匹配的所有模型
+    pub fn models(&self) -> Vec<ModelRef> {
+        let mut models = vec![ModelRef::new(&self.provider, &self.model)];
+        models.extend(self.consensus_models.iter().cloned());
+        models
+    }
+
+    /// 累计费用是否已达到上限
+    pub fn cost_cap_reached(&self) -> bool {
+        self.max_cost.is_some_and(|max_cost| self.cost >= max_cost)
+    }
+
+    /// 任务正在运行，或暂停后仍有 worker 在处理当前的动画
+    fn is_busy(&self) -> bool {
+        self.status == JobStatus::Running || self.workers > 0
+    }
+
+    /// 暂停的任务，以及提高费用上限后达到上限的任务可以恢复
+    fn can_resume(&self) -> bool {
+        match self.status {
+            JobStatus::Paused => true,
+            JobStatus::CostCapReached => !self.cost_cap_reached(),
+            _ => false,
+        }
+    }
+
+    /// 计入单个动画的处理结果
+    fn count_outcome(&mut self, outcome: &JobItemOutcome) {
+        match outcome {
+            JobItemOutcome::Matched => self.num_matched += 1,
+            JobItemOutcome::Prematched => {
+                self.num_matched += 1;
+                self.num_prematched += 1;
+            }
+            JobItemOutcome::NoMatch | JobItemOutcome::Failed => self.num_failed += 1,
+            JobItemOutcome::Conflict => self.num_conflicts += 1,
+            JobItemOutcome::Rejected => self.num_rejected += 1,
+            JobItemOutcome::BudgetExceeded => self.num_budget_exceeded += 1,
+        }
+        self.num_processed += 1;
+    }
+
+    /// 根据已保存的条目恢复待处理队列，并按处理结果重新统计进度
+    fn restore_queue(&mut self, items: Vec<(JobItem, Anime)>) {
+        self.num_animes_to_match = items.len();
+        self.num_processed = 0;
+        self.num_matched = 0;
+        self.num_prematched = 0;
+        self.num_failed = 0;
+        self.num_budget_exceeded = 0;
+        self.num_conflicts = 0;
+        self.num_rejected = 0;
+        self.pending.clear();
+        for (item, anime) in items {
+            match item.outcome {
+                Some(outcome) => self.count_outcome(&outcome),
+                None => self.pending.push_back((item.position as usize, anime)),
+            }
+        }
+    }
+
+    fn from_model(job: Job) -> Self {
+        // 筛选条件只用于展示，待处理队列已单独保存，解析失败时不影响恢复任务
+        let filter = match serde_json::from_str(&job.filter) {
+            Ok(filter) => filter,
+            Err(e) => {
+                warn!("解析任务筛选条件失败: job_id={}, {}", job.id, e);
+                JobFilter::default()
+            }
+        };
+        Self {
+            id: job.id,
+            filter,
+            num_animes_to_match: job.num_animes_to_match as usize,
+            num_processed: job.num_processed as usize,
+            num_matched: job.num_matched as usize,
+            num_prematched: job.num_prematched as usize,
+            num_failed: job.num_failed as usize,
+            num_budget_exceeded: job.num_budget_exceeded as usize,
+            num_conflicts: job.num_conflicts as usize,
+            num_rejected: job.num_rejected as usize,
+            usage: TokenUsage {
+                prompt_tokens: job.prompt_tokens as u64,
+                completion_tokens: job.completion_tokens as u64,
+            },
+            cost: job.cost,
+            max_cost: job.max_cost,
+            job_start_time: job.created_at,
+            provider: job.provider,
+            model: job.model,
+            consensus_models: serde_json::from_str(&job.consensus_models).unwrap_or_default(),
+            prompt_version: job.prompt_version,
+            platform: job.platform,
+            status: job.status,
+            concurrency: job.concurrency.max(1) as usize,
+            pending: VecDeque::new(),
+            workers: 0,
+        }
+    }
+
+    fn to_model(&self) -> Job {
+        Job {
+            id: self.id,
+            platform: self.platform.clone(),
+            filter: json!(self.filter).to_string(),
+            status: self.status.clone(),
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            consensus_models: json!(self.consensus_models).to_string(),
+            prompt_version: self.prompt_version.clone(),
+            max_cost: self.max_cost,
+            concurrency: self.concurrency as i32,
+            num_animes_to_match: self.num_animes_to_match as i32,
+            num_processed: self.num_processed as i32,
+            num_matched: self.num_matched as i32,
+            num_prematched: self.num_prematched as i32,
+            num_failed: self.num_failed as i32,
+            num_budget_exceeded: self.num_budget_exceeded as i32,
+            num_conflicts: self.num_conflicts as i32,
+            num_rejected: self.num_rejected as i32,
+            prompt_tokens: self.usage.prompt_tokens as i64,
+            completion_tokens: self.usage.completion_tokens as i64,
+            cost: self.cost,
+            created_at: self.job_start_time,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// worker 异常退出（panic 或被取消）时归还计数，避免恢复任务时无法启动新的 worker
+struct WorkerGuard<'a> {
+    job_details: &'a Arc<RwLock<JobDetails>>,
+    /// 已在正常退出时扣减计数
+    exited: bool,
+}
+
+impl Drop for WorkerGuard<'_> {
+    fn drop(&mut self) {
+        if self.exited {
+            return;
+        }
+        let mut guard = self
+            .job_details
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        guard.workers = guard.workers.saturating_sub(1);
+    }
+}
+
+/// panic 时携带的信息，通常为 `&str` 或 `String`
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "未知错误".to_string())
 }
 
 #[derive(Clone)]
 pub struct MappingBgmJobRunner {
     db: DB,
+    providers: Arc<ProviderRegistry>,
+    prompts: Arc<PromptRegistry>,
+    prices: Arc<PriceTable>,
     jobs: Vec<Arc<RwLock<JobDetails>>>,
+    events: JobEvents,
+    /// 保证多个 worker 保存任务时按顺序写入
+    save_lock: Arc<Mutex<()>>,
+    prematch: bool,
+    verify: bool,
+    options: MatchOptions,
 }
 
 impl MappingBgmJobRunner {
-    pub async fn new() -> Result<Self> {
+    pub async fn new(
+        providers: Arc<ProviderRegistry>,
+        prompts: Arc<PromptRegistry>,
+        prices: Arc<PriceTable>,
+    ) -> Result<Self> {
         let db = DB::new_from_env().await?;
-        let jobs = Vec::new();
-        Ok(Self { db, jobs })
+
+        // 服务重启前仍在运行的任务恢复为暂停状态
+        let interrupted = db.pause_interrupted_jobs().await?;
+        let mut jobs = Vec::new();
+        for job in db.get_jobs().await? {
+            let mut job_details = JobDetails::from_model(job);
+            // 已完成的任务不会再执行，不需要加载待处理队列
+            if job_details.status != JobStatus::Completed {
+                job_details.restore_queue(db.get_job_queue(job_details.id).await?);
+            }
+            jobs.push(Arc::new(RwLock::new(job_details)));
+        }
+        if !interrupted.is_empty() {
+            info!("{}个任务在服务重启时被中断，已暂停", interrupted.len());
+        }
+
+        let auto_resume = std::env::var("JOB_AUTO_RESUME")
+            .map(|value| value == "true" || value == "1")
+            .unwrap_or(false);
+        let prematch = std::env::var("PREMATCH_ENABLED")
+            .map(|value| value != "false" && value != "0")
+            .unwrap_or(true);
+        let verify = std::env::var("VERIFY_ENABLED")
+            .map(|value| value != "false" && value != "0")
+            .unwrap_or(true);
+        let runner = Self {
+            db,
+            providers,
+            prompts,
+            prices,
+            jobs,
+            events: JobEvents::new(),
+            save_lock: Arc::new(Mutex::new(())),
+            prematch,
+            verify,
+            options: MatchOptions::new(MatchBudget::from_env(), 3, 10),
+        };
+
+        if auto_resume {
+            for job_details in runner.jobs.iter() {
+                let (id, platform) = {
+                    let guard = job_details.read().unwrap();
+                    (guard.id, guard.platform.clone())
+                };
+                if interrupted.contains(&id) {
+                    info!("自动恢复任务: {} {:?}", id, platform);
+                    runner.resume_job(id).await?;
+                }
+            }
+        }
+
+        Ok(runner)
+    }
+
+    fn find_job(&self, id: i32) -> Option<&Arc<RwLock<JobDetails>>> {
+        self.jobs.iter().find(|job| job.read().unwrap().id == id)
     }
 
+    /// 保存任务的状态和进度，失败时只记录日志
+    async fn save_job(&self, job_details: &Arc<RwLock<JobDetails>>) {
+        // 持有锁期间读取最新状态，避免较旧的快照覆盖较新的
+        let _guard = self.save_lock.lock().await;
+        let job = job_details.read().unwrap().to_model();
+        if let Err(e) = self.db.update_job(job).await {
+            warn!("保存任务失败: {}", e);
+        }
+    }
+
+    /// 保存单个动画的处理结果
+    async fn save_item(&self, item: JobItem) {
+        if let Err(e) = self.db.save_job_item(item).await {
+            warn!("保存任务条目失败: {}", e);
+        }
+    }
+
+    /// 创建匹配任务，返回任务id
     pub async fn create_job(
         &mut self,
         platform: Platform,
-        year: i32,
+        filter: JobFilter,
         provider: String,
         model: String,
-    ) -> Result<()> {
-        let query_result = self
-            .db
-            .query_animes(&QueryAnimes {
-                year: Some(year),
-                query: PageQuery {
-                    page: 1,
-                    page_size: usize::MAX,
-                },
-                status: Some(ReviewStatus::UnMatched),
-            })
-            .await?;
+        options: JobOptions,
+    ) -> Result<i32> {
+        self.create_job_with_models(
+            platform,
+            filter,
+            vec![ModelRef::new(provider, model)],
+            options,
+        )
+        .await
+    }
 
-        let animes = query_result
-            .data
-            .iter()
-            .filter(|(_anime, mappings)| {
-                mappings
-                    .iter()
-                    .any(|m| m.platform == platform && m.review_status == ReviewStatus::UnMatched)
+    /// 创建多模型共识匹配任务，所有模型一致时才视为匹配成功
+    pub async fn create_consensus_job(
+        &mut self,
+        platform: Platform,
+        filter: JobFilter,
+        models: Vec<ModelRef>,
+        options: JobOptions,
+    ) -> Result<i32> {
+        if models.len() < 2 {
+            return Err(anyhow!("共识匹配至少需要两个模型"));
+        }
+        self.create_job_with_models(platform, filter, models, options)
+            .await
+    }
+
+    async fn create_job_with_models(
+        &mut self,
+        platform: Platform,
+        filter: JobFilter,
+        models: Vec<ModelRef>,
+        options: JobOptions,
+    ) -> Result<i32> {
+        let prompt_version = self
+            .prompts
+            .get(options.prompt_version.as_deref())?
+            .version
+            .clone();
+
+        // 提前校验provider，避免在任务执行过程中才失败
+        let mut models = models
+            .into_iter()
+            .map(|model| -> Result<ModelRef> {
+                let resolved = self
+                    .providers
+                    .resolve(&model.provider)?
+                    .resolve_model(Some(&model.model));
+                Ok(ModelRef::new(model.provider, resolved))
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>>>()?;
+        let primary = models.remove(0);
+
+        let mut filter = filter;
+        if options.rematch_rejected {
+            filter.review_status = vec![ReviewStatus::Rejected];
+        }
+        let animes = self.db.select_job_animes(platform.clone(), &filter).await?;
 
-        let job_details = Arc::new(RwLock::new(JobDetails {
+        let mut job_details = JobDetails {
+            id: 0,
             platform,
-            year,
+            filter,
             num_animes_to_match: animes.len(),
             num_processed: 0,
             num_matched: 0,
+            num_prematched: 0,
             num_failed: 0,
+            num_budget_exceeded: 0,
+            num_conflicts: 0,
+            num_rejected: 0,
+            usage: TokenUsage::default(),
+            cost: 0.0,
+            max_cost: options.max_cost,
             job_start_time: Utc::now(),
-            animes: animes.iter().map(|(anime, _)| anime.clone()).collect(),
-            provider,
-            model,
+            provider: primary.provider,
+            model: primary.model,
+            consensus_models: models,
+            prompt_version,
             status: JobStatus::Created,
-            current_index: 0,
-        }));
+            concurrency: options.concurrency.unwrap_or(1).max(1),
+            pending: VecDeque::new(),
+            workers: 0,
+        };
+        let anilist_ids: Vec<i32> = animes.iter().map(|anime| anime.anilist_id).collect();
+        job_details.pending = animes.into_iter().enumerate().collect();
+        let id = self
+            .db
+            .create_job(job_details.to_model(), &anilist_ids)
+            .await?;
+        job_details.id = id;
 
-        self.jobs.push(job_details.clone());
+        self.jobs.push(Arc::new(RwLock::new(job_details)));
 
-        Ok(())
+        Ok(id)
     }
 
-    pub async fn run(&self, platform: Platform, year: i32) {
-        let job_details = self.jobs.iter().find(|job| {
-            job.read().unwrap().platform == platform && job.read().unwrap().year == year
-        });
-        if let Some(job_details) = job_details {
-            {
+    pub async fn run(&self, id: i32) {
+        if let Some(job_details) = self.find_job(id) {
+            let platform = {
                 let mut guard = job_details.write().unwrap();
                 guard.status = JobStatus::Running;
-            }
+                guard.platform.clone()
+            };
+            self.save_job(job_details).await;
+            self.events.send(JobEvent::Started {
+                platform,
+                job_id: id,
+            });
 
             let cloned = self.clone();
             let job_details = job_details.clone();
@@ -124,15 +461,22 @@ impl MappingBgmJobRunner {
         }
     }
 
-    pub async fn pause_job(&self, platform: Platform, year: i32) -> Result<bool> {
-        let job_details = self.jobs.iter().find(|job| {
-            job.read().unwrap().platform == platform && job.read().unwrap().year == year
-        });
-
-        if let Some(job_details) = job_details {
-            let mut guard = job_details.write().unwrap();
-            if guard.status == JobStatus::Running {
-                guard.status = JobStatus::Paused;
+    pub async fn pause_job(&self, id: i32) -> Result<bool> {
+        if let Some(job_details) = self.find_job(id) {
+            let (paused, platform) = {
+                let mut guard = job_details.write().unwrap();
+                let paused = guard.status == JobStatus::Running;
+                if paused {
+                    guard.status = JobStatus::Paused;
+                }
+                (paused, guard.platform.clone())
+            };
+            if paused {
+                self.save_job(job_details).await;
+                self.events.send(JobEvent::Paused {
+                    platform,
+                    job_id: id,
+                });
                 return Ok(true);
             }
         }
@@ -140,16 +484,18 @@ impl MappingBgmJobRunner {
         Ok(false)
     }
 
-    pub async fn resume_job(&self, platform: Platform, year: i32) -> Result<bool> {
-        let job_details = self.jobs.iter().find(|job| {
-            job.read().unwrap().platform == platform && job.read().unwrap().year == year
-        });
-
-        if let Some(job_details) = job_details {
+    pub async fn resume_job(&self, id: i32) -> Result<bool> {
+        if let Some(job_details) = self.find_job(id) {
             let mut guard = job_details.write().unwrap();
-            if guard.status == JobStatus::Paused {
+            if guard.can_resume() {
                 guard.status = JobStatus::Running;
+                let platform = guard.platform.clone();
                 drop(guard); // 释放锁，避免死锁
+                self.save_job(job_details).await;
+                self.events.send(JobEvent::Started {
+                    platform,
+                    job_id: id,
+                });
 
                 let cloned = self.clone();
                 let job_details_clone = job_details.clone();
@@ -164,16 +510,36 @@ impl MappingBgmJobRunner {
         Ok(false)
     }
 
-    pub async fn remove_job(&mut self, platform: Platform, year: i32) -> Result<bool> {
-        // 查找任务索引
-        let index = self.jobs.iter().position(|job| {
-            let guard = job.read().unwrap();
-            guard.platform == platform && guard.year == year
-        });
+    /// 修改任务的费用上限，为空时取消上限
+    pub async fn set_max_cost(&self, id: i32, max_cost: Option<f64>) -> Result<bool> {
+        if max_cost.is_some_and(|max_cost| !max_cost.is_finite() || max_cost < 0.0) {
+            return Err(anyhow!("费用上限无效: {:?}", max_cost));
+        }
+        let Some(job_details) = self.find_job(id) else {
+            return Ok(false);
+        };
+        job_details.write().unwrap().max_cost = max_cost;
+        self.save_job(job_details).await;
+        Ok(true)
+    }
 
-        // 如果找到任务，直接从数组中移除
+    pub async fn remove_job(&mut self, id: i32) -> Result<bool> {
+        let index = self
+            .jobs
+            .iter()
+            .position(|job| job.read().unwrap().id == id);
+
+        // 如果找到任务，从数组和数据库中移除
         if let Some(index) = index {
+            // 运行中的任务需要先暂停，并等待正在处理的动画完成
+            if self.jobs[index].read().unwrap().is_busy() {
+                return Err(anyhow!(
+                    "任务正在运行，请先暂停并等待当前动画处理完成: {}",
+                    id
+                ));
+            }
             self.jobs.remove(index);
+            self.db.delete_job(id).await?;
             return Ok(true);
         }
 
@@ -181,126 +547,626 @@ impl MappingBgmJobRunner {
     }
 
     async fn run_job(&self, job_details: Arc<RwLock<JobDetails>>) {
-        // 提前获取需要处理的动画列表和起始索引
-        let (start_index, animes) = {
+        let (job_id, prompt_version) = {
             let guard = job_details.read().unwrap();
-            (guard.current_index, guard.animes.clone())
+            (guard.id, guard.prompt_version.clone())
         };
 
-        // 确保只处理尚未处理的动画
-        for i in start_index..animes.len() {
-            // 每次循环开始时检查任务状态
-            {
-                let guard = job_details.read().unwrap();
-                if guard.status == JobStatus::Paused {
-                    // 如果任务已暂停，直接返回，不做进一步处理
-                    return;
-                }
+        let prompt = match self.prompts.get(Some(&prompt_version)) {
+            Ok(prompt) => prompt.clone(),
+            Err(e) => {
+                error!("加载提示词失败: {}", e);
+                let platform = {
+                    let mut guard = job_details.write().unwrap();
+                    guard.status = JobStatus::Failed;
+                    guard.platform.clone()
+                };
+                self.save_job(&job_details).await;
+                self.events.send(JobEvent::Stopped {
+                    platform,
+                    job_id,
+                    reason: format!("加载提示词失败: {}", e),
+                });
+                return;
             }
+        };
 
-            let anime = &animes[i];
-            let keywords = json!({
-                "titles": anime.titles,
-                "year": anime.year,
-                // "media_type": anime.media_type,
-                "start_date": anime.start_date,
-                "episode_number": anime.episode_number,
+        // 暂停后立即恢复时，上一轮的 worker 可能仍在处理，只补足差额
+        let workers = {
+            let mut guard = job_details.write().unwrap();
+            let workers = guard.concurrency.saturating_sub(guard.workers);
+            guard.workers += workers;
+            workers
+        };
+        let results = join_all(
+            (0..workers)
+                .map(|_| AssertUnwindSafe(self.run_worker(&job_details, &prompt)).catch_unwind()),
+        )
+        .await;
+        if !results.iter().any(Result::is_err) {
+            return;
+        }
+
+        // 所有 worker 都异常退出时任务无人处理，暂停以便恢复
+        let stopped = {
+            let mut guard = job_details
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let stopped = guard.status == JobStatus::Running && guard.workers == 0;
+            if stopped {
+                guard.status = JobStatus::Paused;
+            }
+            stopped.then(|| guard.platform.clone())
+        };
+        if let Some(platform) = stopped {
+            error!("任务的 worker 全部异常退出，已暂停: {}", job_id);
+            self.save_job(&job_details).await;
+            self.events.send(JobEvent::Stopped {
+                platform,
+                job_id,
+                reason: "worker 异常退出".to_string(),
             });
+        }
+    }
 
-            let platform = {
-                let guard = job_details.read().unwrap();
-                guard.platform.clone()
+    /// 不断从队列中取出动画处理，直到队列为空或任务不再运行
+    async fn run_worker(&self, job_details: &Arc<RwLock<JobDetails>>, prompt: &PromptTemplate) {
+        let mut worker = WorkerGuard {
+            job_details,
+            exited: false,
+        };
+        loop {
+            let (next, completed) = {
+                let mut guard = job_details.write().unwrap();
+                let next = if guard.status == JobStatus::Running {
+                    guard.pending.pop_front()
+                } else {
+                    None
+                };
+                // 最后一个退出的 worker 负责将任务标记为完成
+                let mut completed = false;
+                if next.is_none() {
+                    guard.workers -= 1;
+                    worker.exited = true;
+                    completed = guard.workers == 0
+                        && guard.pending.is_empty()
+                        && guard.status == JobStatus::Running;
+                    if completed {
+                        guard.status = JobStatus::Completed;
+                        self.events.send(JobEvent::Completed {
+                            platform: guard.platform.clone(),
+                            job_id: guard.id,
+                        });
+                    }
+                }
+                (next, completed)
             };
 
-            let result = match platform {
-                Platform::BgmTv => {
-                    let provider = job_details.read().unwrap().provider.clone();
-                    let model = job_details.read().unwrap().model.clone();
-                    run_mapping_bgm_tv_agent(
-                        keywords.to_string().as_str(),
-                        &provider,
-                        &model,
-                        3,
-                        10,
-                    )
+            let Some((position, anime)) = next else {
+                if completed {
+                    self.save_job(job_details).await;
+                }
+                return;
+            };
+
+            // 单个动画处理时 panic 不影响其他 worker，该动画记为失败
+            let item =
+                match AssertUnwindSafe(self.process_item(job_details, prompt, position, &anime))
+                    .catch_unwind()
                     .await
+                {
+                    Ok(item) => item,
+                    Err(panic) => self.panicked_item(job_details, position, &anime, panic),
+                };
+
+            {
+                let mut guard = job_details.write().unwrap();
+                if let Some(outcome) = &item.outcome {
+                    guard.count_outcome(outcome);
                 }
-                Platform::Tmdb => {
-                    let provider = job_details.read().unwrap().provider.clone();
-                    let model = job_details.read().unwrap().model.clone();
-                    run_mapping_tmdb_agent(keywords.to_string().as_str(), &provider, &model, 3, 10)
-                        .await
+                if guard.status == JobStatus::Running && guard.cost_cap_reached() {
+                    warn!("任务费用已达上限: {:.4}", guard.cost);
+                    guard.status = JobStatus::CostCapReached;
+                    self.events.send(JobEvent::Stopped {
+                        platform: guard.platform.clone(),
+                        job_id: guard.id,
+                        reason: format!("费用已达上限: {:.4}", guard.cost),
+                    });
                 }
-            };
+            }
+
+            self.save_item(item).await;
+            self.save_job(job_details).await;
+        }
+    }
+
+    /// 匹配单个动画并写入结果，返回该条目的处理结果
+    async fn process_item(
+        &self,
+        job_details: &Arc<RwLock<JobDetails>>,
+        prompt: &PromptTemplate,
+        position: usize,
+        anime: &Anime,
+    ) -> JobItem {
+        let (job_id, platform, models) = {
+            let guard = job_details.read().unwrap();
+            (guard.id, guard.platform.clone(), guard.models())
+        };
+
+        // 其他平台已确认的映射和导入时携带的id，供模型交叉参考
+        let known_ids = match self.db.get_known_ids(anime.anilist_id).await {
+            Ok(known_ids) => known_ids.for_platform(&platform),
+            Err(e) => {
+                warn!("获取已知id失败: {}", e);
+                Default::default()
+            }
+        };
+
+        // 审核时被拒绝过的条目，重新匹配时排除
+        let rejected_ids = match self
+            .db
+            .get_rejected_ids(anime.anilist_id, platform.clone())
+            .await
+        {
+            Ok(rejected_ids) => rejected_ids,
+            Err(e) => {
+                warn!("获取已拒绝的条目失败: {}", e);
+                Vec::new()
+            }
+        };
 
-            let result = match result {
+        let mut keywords = json!({
+            "titles": anime.titles,
+            "year": anime.year,
+            // "media_type": anime.media_type,
+            "start_date": anime.start_date,
+            "episode_number": anime.episode_number,
+        });
+        if !known_ids.is_empty() {
+            keywords["known_ids"] = json!(known_ids);
+        }
+        if !rejected_ids.is_empty() {
+            keywords["rejected_ids"] = json!(rejected_ids);
+        }
+        let query = MatchQuery::new(keywords.to_string())
+            .with_known_ids(known_ids)
+            .with_rejected_ids(rejected_ids.clone());
+        let is_rejected = |result: &MatchResult| {
+            result
+                .id
+                .is_some_and(|id| rejected_ids.contains(&id.to_string()))
+        };
+
+        // 先尝试确定性预匹配，只有结果不明确时才调用模型
+        let started = Instant::now();
+        let prematched = if self.prematch {
+            match prematch(&platform, anime).await {
                 Ok(result) => result,
                 Err(e) => {
-                    error!("匹配Bgm失败: {}", e);
-                    {
-                        let mut guard = job_details.write().unwrap();
-                        guard.num_failed += 1;
-                        guard.num_processed += 1;
-                        guard.current_index = i + 1; // 更新索引以便下次从这里继续
-                    }
-                    continue;
+                    warn!("预匹配失败: {}", e);
+                    None
                 }
-            };
+            }
+        } else {
+            None
+        };
+        // 预匹配可能再次命中被拒绝的条目，此时交给模型重新匹配
+        let prematched = prematched.filter(|result| !is_rejected(result));
+        let is_prematched = prematched.is_some();
+
+        let mut transcripts = Vec::new();
+        let outcome = match prematched {
+            Some(result) => {
+                transcripts.push(MatchTranscript::prematch(
+                    platform.clone(),
+                    &query.keywords,
+                    &result,
+                    started.elapsed().as_millis() as u64,
+                ));
+                Ok(MatchOutcome::Matched(result))
+            }
+            None if models.len() > 1 => {
+                self.run_consensus(&platform, &query, &models, prompt, &mut transcripts)
+                    .await
+            }
+            None => self
+                .run_model(&platform, &query, &models[0], prompt, &mut transcripts)
+                .await
+                .map(MatchOutcome::from_result),
+        };
+
+        // 按价格表计算每次尝试的费用，并计入任务总用量
+        let mut usage = TokenUsage::default();
+        let mut cost = 0.0;
+        for transcript in transcripts.iter_mut() {
+            transcript.cost =
+                self.prices
+                    .cost(&transcript.provider, &transcript.model, &transcript.usage);
+            usage.add(&transcript.usage);
+            cost += transcript.cost.unwrap_or_default();
+        }
+        {
+            let mut guard = job_details.write().unwrap();
+            guard.usage.add(&usage);
+            guard.cost += cost;
+        }
+
+        // 保存每次尝试的对话记录，便于审核时追溯
+        if let Err(e) = self
+            .db
+            .save_transcripts(anime.anilist_id, &transcripts)
+            .await
+        {
+            warn!("保存匹配记录失败: {}", e);
+        }
+
+        let mut item = JobItem {
+            job_id,
+            position: position as i32,
+            anilist_id: anime.anilist_id,
+            outcome: None,
+            platform_id: None,
+            score: None,
+            error: None,
+            processed_at: Some(Utc::now()),
+        };
+
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                error!("匹配Bgm失败: {}", e);
+                item.outcome = Some(if budget_exceeded(&e).is_some() {
+                    JobItemOutcome::BudgetExceeded
+                } else {
+                    JobItemOutcome::Failed
+                });
+                item.error = Some(e.to_string());
+                self.send_item_failed(&platform, job_id, anime, &e.to_string());
+                return item;
+            }
+        };
+
+        // 模型仍然提交了被拒绝的条目
+        let outcome = match outcome {
+            MatchOutcome::Matched(result) if is_rejected(&result) => {
+                MatchOutcome::rejected(result, "该条目已在审核时被拒绝")
+            }
+            outcome => outcome,
+        };
 
-            let mut success_count = 0;
-            let mut failed_count = 0;
-            if let Some(id) = result.id {
-                success_count += 1;
-                self.db
+        // 写入映射前重新获取条目校验，预匹配的结果本身来自远程数据，无需校验
+        let outcome = match outcome {
+            MatchOutcome::Matched(result) if self.verify && !is_prematched => {
+                self.verify_result(&platform, anime, result).await
+            }
+            outcome => outcome,
+        };
+
+        // 保存候选，审核时可直接选用其他候选
+        if let Err(e) = self
+            .db
+            .save_candidates(anime.anilist_id, platform.clone(), outcome.candidates())
+            .await
+        {
+            warn!("保存候选失败: {}", e);
+        }
+
+        match outcome {
+            MatchOutcome::Matched(result) => {
+                let platform_id = result.id.unwrap_or_default().to_string();
+                let score = result.confidence_score.unwrap_or_default() as u8;
+                item.outcome = Some(if is_prematched {
+                    JobItemOutcome::Prematched
+                } else {
+                    JobItemOutcome::Matched
+                });
+                item.platform_id = Some(platform_id.clone());
+                item.score = Some(score as i32);
+                if let Err(e) = self
+                    .db
                     .update_anime_mapping(
                         anime.anilist_id,
                         platform.clone(),
-                        id.to_string(),
-                        result.confidence_score.unwrap_or_default() as u8,
+                        platform_id.clone(),
+                        score,
                     )
                     .await
-                    .unwrap();
+                {
+                    warn!("写入映射失败: anilist_id={}, {}", anime.anilist_id, e);
+                    item.outcome = Some(JobItemOutcome::Failed);
+                    item.error = Some(format!("写入映射失败: {}", e));
+                    self.send_item_failed(
+                        &platform,
+                        job_id,
+                        anime,
+                        &format!("写入映射失败: {}", e),
+                    );
+                    return item;
+                }
+                self.events.send(JobEvent::ItemMatched {
+                    platform: platform.clone(),
+                    job_id,
+                    anilist_id: anime.anilist_id,
+                    platform_id,
+                    score,
+                });
 
                 if let Some(season) = result.season {
                     if season > 0 {
-                        self.db
-                            .update_season_number(anime.anilist_id, season)
-                            .await
-                            .unwrap();
+                        // 映射已写入，季度写入失败不影响匹配结果
+                        if let Err(e) = self.db.update_season_number(anime.anilist_id, season).await
+                        {
+                            warn!("写入季度失败: anilist_id={}, {}", anime.anilist_id, e);
+                        }
                     }
                 }
-            } else {
-                failed_count += 1;
             }
-
-            {
-                let mut guard = job_details.write().unwrap();
-                guard.num_matched += success_count;
-                guard.num_failed += failed_count;
-                guard.num_processed += 1;
-                guard.current_index = i + 1; // 重要：更新当前索引为下一条记录
-
-                // 检查是否所有动画都已处理完毕
-                if guard.current_index >= animes.len() {
-                    guard.status = JobStatus::Completed;
+            MatchOutcome::NoMatch(_) => {
+                item.outcome = Some(JobItemOutcome::NoMatch);
+                self.send_item_failed(&platform, job_id, anime, "未找到匹配的条目");
+            }
+            MatchOutcome::Conflict(_) => {
+                item.outcome = Some(JobItemOutcome::Conflict);
+                if let Err(e) = self
+                    .db
+                    .mark_conflict(anime.anilist_id, platform.clone())
+                    .await
+                {
+                    warn!("标记冲突失败: anilist_id={}, {}", anime.anilist_id, e);
+                    item.outcome = Some(JobItemOutcome::Failed);
+                    item.error = Some(format!("标记冲突失败: {}", e));
                 }
+                self.send_item_failed(&platform, job_id, anime, "多个模型的结果不一致");
+            }
+            MatchOutcome::Rejected { result, reason } => {
+                item.outcome = Some(JobItemOutcome::Rejected);
+                item.platform_id = result.id.map(|id| id.to_string());
+                item.error = Some(reason.clone());
+                warn!(
+                    "匹配结果未通过校验: anilist_id={}, id={:?}, {}",
+                    anime.anilist_id, result.id, reason
+                );
+                self.send_item_failed(&platform, job_id, anime, &reason);
             }
+        }
 
-            // 再次检查是否应该继续执行
-            {
-                let guard = job_details.read().unwrap();
-                if guard.status != JobStatus::Running {
-                    return;
+        item
+    }
+
+    fn panicked_item(
+        &self,
+        job_details: &Arc<RwLock<JobDetails>>,
+        position: usize,
+        anime: &Anime,
+        panic: Box<dyn Any + Send>,
+    ) -> JobItem {
+        let message = panic_message(panic.as_ref());
+        error!(
+            "处理动画时发生panic: anilist_id={}, {}",
+            anime.anilist_id, message
+        );
+
+        let (job_id, platform) = {
+            let guard = job_details
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            (guard.id, guard.platform.clone())
+        };
+        let error = format!("处理异常: {}", message);
+        self.send_item_failed(&platform, job_id, anime, &error);
+        JobItem {
+            job_id,
+            position: position as i32,
+            anilist_id: anime.anilist_id,
+            outcome: Some(JobItemOutcome::Failed),
+            platform_id: None,
+            score: None,
+            error: Some(error),
+            processed_at: Some(Utc::now()),
+        }
+    }
+
+    fn send_item_failed(&self, platform: &Platform, job_id: i32, anime: &Anime, error: &str) {
+        self.events.send(JobEvent::ItemFailed {
+            platform: platform.clone(),
+            job_id,
+            anilist_id: anime.anilist_id,
+            error: error.to_string(),
+        });
+    }
+
+    async fn verify_result(
+        &self,
+        platform: &Platform,
+        anime: &Anime,
+        mut result: MatchResult,
+    ) -> MatchOutcome {
+        match verify_match(platform, anime, &result).await {
+            Ok(Verdict::Accepted { score }) => {
+                result.confidence_score = Some(score as i32);
+                MatchOutcome::Matched(result)
+            }
+            Ok(Verdict::Rejected { reason }) => {
+                MatchOutcome::rejected(result, format!("校验未通过: {}", reason))
+            }
+            Err(e) => {
+                // 校验本身失败时保留原结果，不因网络问题丢弃匹配
+                warn!("校验匹配结果失败: {}", e);
+                MatchOutcome::Matched(result)
+            }
+        }
+    }
+
+    async fn run_model(
+        &self,
+        platform: &Platform,
+        query: &MatchQuery,
+        model: &ModelRef,
+        prompt: &PromptTemplate,
+        transcripts: &mut Vec<MatchTranscript>,
+    ) -> Result<MatchResult> {
+        let provider = self.providers.resolve(&model.provider)?;
+        run_mapping_agent(
+            platform.clone(),
+            query,
+            provider,
+            &model.model,
+            prompt,
+            &self.options,
+            transcripts,
+        )
+        .await
+    }
+
+    /// 同一动画并发交给多个模型匹配，再根据结果是否一致得出结论
+    async fn run_consensus(
+        &self,
+        platform: &Platform,
+        query: &MatchQuery,
+        models: &[ModelRef],
+        prompt: &PromptTemplate,
+        transcripts: &mut Vec<MatchTranscript>,
+    ) -> Result<MatchOutcome> {
+        let runs = join_all(models.iter().map(|model| async move {
+            let mut transcripts = Vec::new();
+            let result = self
+                .run_model(platform, query, model, prompt, &mut transcripts)
+                .await;
+            (result, transcripts)
+        }))
+        .await;
+
+        let mut proposals = Vec::with_capacity(models.len());
+        let mut first_error = None;
+        for (model, (result, model_transcripts)) in models.iter().zip(runs) {
+            transcripts.extend(model_transcripts);
+            match result {
+                Ok(result) => proposals.push((model.clone(), Some(result))),
+                Err(e) => {
+                    warn!("模型 {} 匹配失败: {}", model.label(), e);
+                    proposals.push((model.clone(), None));
+                    first_error.get_or_insert(e);
                 }
             }
         }
+
+        // 所有模型都失败时按失败处理
+        if proposals.iter().all(|(_, result)| result.is_none()) {
+            if let Some(e) = first_error {
+                return Err(e);
+            }
+        }
+
+        Ok(decide_consensus(&proposals))
+    }
+
+    /// 订阅任务事件
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.events.subscribe()
     }
 
     pub async fn list_jobs(&self) -> Result<Vec<JobDetails>> {
-        Ok(self
-            .jobs
+        let jobs = self.db.get_jobs().await?;
+        Ok(jobs.into_iter().map(JobDetails::from_model).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(position: i32, outcome: Option<JobItemOutcome>) -> (JobItem, Anime) {
+        let item = JobItem {
+            job_id: 1,
+            position,
+            anilist_id: position + 100,
+            outcome,
+            platform_id: None,
+            score: None,
+            error: None,
+            processed_at: None,
+        };
+        (item, Anime::test(position + 100))
+    }
+
+    #[test]
+    fn test_can_resume() {
+        let mut job_details = JobDetails::from_model(Job {
+            status: JobStatus::CostCapReached,
+            max_cost: Some(1.0),
+            cost: 1.2,
+            ..Job::test(Platform::BgmTv)
+        });
+        assert!(!job_details.can_resume());
+
+        // 提高或取消上限后可以恢复
+        job_details.max_cost = Some(2.0);
+        assert!(job_details.can_resume());
+        job_details.max_cost = None;
+        assert!(job_details.can_resume());
+
+        job_details.status = JobStatus::Completed;
+        assert!(!job_details.can_resume());
+        job_details.status = JobStatus::Paused;
+        assert!(job_details.can_resume());
+    }
+
+    #[test]
+    fn test_panic_message() {
+        assert_eq!(panic_message(&"写入失败"), "写入失败");
+        assert_eq!(panic_message(&format!("写入失败: {}", 1)), "写入失败: 1");
+        assert_eq!(panic_message(&1), "未知错误");
+    }
+
+    #[test]
+    fn test_is_busy() {
+        let mut job_details = JobDetails::from_model(Job {
+            status: JobStatus::Running,
+            ..Job::test(Platform::BgmTv)
+        });
+        assert!(job_details.is_busy());
+
+        // 暂停后仍需等待 worker 退出
+        job_details.status = JobStatus::Paused;
+        job_details.workers = 1;
+        assert!(job_details.is_busy());
+        job_details.workers = 0;
+        assert!(!job_details.is_busy());
+    }
+
+    #[test]
+    fn test_restore_queue() {
+        let mut job_details = JobDetails::from_model(Job {
+            id: 1,
+            status: JobStatus::Paused,
+            concurrency: 4,
+            // 保存的计数可能落后于条目的处理结果
+            num_animes_to_match: 5,
+            num_processed: 1,
+            num_matched: 1,
+            ..Job::test(Platform::BgmTv)
+        });
+
+        // 并发处理时条目可能乱序完成
+        job_details.restore_queue(vec![
+            item(0, Some(JobItemOutcome::Matched)),
+            item(1, None),
+            item(2, Some(JobItemOutcome::Prematched)),
+            item(3, Some(JobItemOutcome::NoMatch)),
+            item(4, None),
+        ]);
+
+        assert_eq!(job_details.concurrency, 4);
+        assert_eq!(job_details.num_animes_to_match, 5);
+        assert_eq!(job_details.num_processed, 3);
+        assert_eq!(job_details.num_matched, 2);
+        assert_eq!(job_details.num_prematched, 1);
+        assert_eq!(job_details.num_failed, 1);
+        let pending: Vec<usize> = job_details
+            .pending
             .iter()
-            .map(|job| job.read().unwrap().clone())
-            .collect())
+            .map(|(position, _)| *position)
+            .collect();
+        assert_eq!(pending, vec![1, 4]);
     }
 }
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+pub mod events;
+pub mod ingest;
 pub mod mapping_bgm;
+pub mod scheduler;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum JobType {
@@ -0,0 +1,435 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::agent::provider::ProviderRegistry;
+use crate::anilist::{AniListClient, AniListSeason};
+use crate::api::export::{compact_exports, write_export};
+use crate::api::types::{JobFilter, JobOptions, ScheduleRequest};
+use crate::job::ingest::ingest_season;
+use crate::job::mapping_bgm::{JobStatus, MappingBgmJobRunner};
+use crate::models::db::DB;
+use crate::models::enums::{Platform, ScheduleRunStatus};
+use crate::models::schedule_runs::Model as ScheduleRun;
+use crate::models::schedules::Model as Schedule;
+
+/// 检查到期计划的间隔
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+/// 等待任务结束时查询任务状态的间隔
+const JOB_POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// 等待任务结束的最长时间，超时后放弃导出
+const JOB_WAIT_TIMEOUT: Duration = Duration::from_secs(12 * 60 * 60);
+/// 查询执行记录时返回的最大条数
+const MAX_RUNS: u64 = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleDetails {
+    pub id: i32,
+    pub name: String,
+    pub cron: String,
+    pub enabled: bool,
+    pub platforms: Vec<Platform>,
+    pub provider: String,
+    pub model: String,
+    pub options: JobOptions,
+    pub export: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ScheduleDetails {
+    fn from_model(schedule: Schedule) -> Self {
+        Self {
+            id: schedule.id,
+            name: schedule.name,
+            cron: schedule.cron,
+            enabled: schedule.enabled,
+            platforms: serde_json::from_str(&schedule.platforms).unwrap_or_default(),
+            provider: schedule.provider,
+            model: schedule.model,
+            options: serde_json::from_str(&schedule.options).unwrap_or_default(),
+            export: schedule.export,
+            last_run_at: schedule.last_run_at,
+            next_run_at: schedule.next_run_at,
+            created_at: schedule.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRunDetails {
+    pub id: i32,
+    pub schedule_id: i32,
+    pub status: ScheduleRunStatus,
+    pub num_fetched: i32,
+    pub num_added: i32,
+    pub job_ids: Vec<i32>,
+    pub exported: bool,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl ScheduleRunDetails {
+    fn from_model(run: ScheduleRun) -> Self {
+        Self {
+            id: run.id,
+            schedule_id: run.schedule_id,
+            status: run.status,
+            num_fetched: run.num_fetched,
+            num_added: run.num_added,
+            job_ids: serde_json::from_str(&run.job_ids).unwrap_or_default(),
+            exported: run.exported,
+            error: run.error,
+            started_at: run.started_at,
+            finished_at: run.finished_at,
+        }
+    }
+}
+
+/// 解析 cron 表达式，5段格式（分 时 日 月 周）自动补充秒
+pub fn parse_cron(expression: &str) -> Result<cron::Schedule> {
+    let expression = expression.trim();
+    let expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    cron::Schedule::from_str(&expression).map_err(|e| anyhow!("cron 表达式无效: {}", e))
+}
+
+/// 指定时间之后的下一次执行时间
+pub fn next_run_after(expression: &str, after: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+    Ok(parse_cron(expression)?.after(&after).next())
+}
+
+/// 需要导入的季度：当前季度和下一个季度
+pub fn ingest_seasons(now: DateTime<Utc>) -> [(AniListSeason, i32); 2] {
+    let season = AniListSeason::from_month(now.month());
+    [(season, now.year()), season.next(now.year())]
+}
+
+/// 按计划定期导入新番并创建匹配任务
+#[derive(Clone)]
+pub struct Scheduler {
+    db: DB,
+    anilist: Arc<AniListClient>,
+    providers: Arc<ProviderRegistry>,
+    job_runner: Arc<Mutex<MappingBgmJobRunner>>,
+    /// 正在执行的计划，同一计划不会重叠执行
+    running: Arc<std::sync::Mutex<HashSet<i32>>>,
+}
+
+impl Scheduler {
+    pub async fn new(
+        db: DB,
+        anilist: Arc<AniListClient>,
+        providers: Arc<ProviderRegistry>,
+        job_runner: Arc<Mutex<MappingBgmJobRunner>>,
+    ) -> Result<Self> {
+        let interrupted = db.fail_interrupted_schedule_runs().await?;
+        if interrupted > 0 {
+            info!("{}次计划执行在服务重启时被中断", interrupted);
+        }
+
+        Ok(Self {
+            db,
+            anilist,
+            providers,
+            job_runner,
+            running: Arc::new(std::sync::Mutex::new(HashSet::new())),
+        })
+    }
+
+    /// 在后台定期检查并执行到期的计划
+    pub fn start(&self) {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TICK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = scheduler.run_due().await {
+                    warn!("检查定时计划失败: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn run_due(&self) -> Result<()> {
+        let now = Utc::now();
+        for schedule in self.db.get_schedules().await? {
+            if !schedule.enabled || !schedule.next_run_at.is_some_and(|at| at <= now) {
+                continue;
+            }
+            // 服务停止期间错过的多次执行只补执行一次
+            let next_run_at = next_run_after(&schedule.cron, now)?;
+            self.db
+                .set_schedule_next_run(schedule.id, next_run_at)
+                .await?;
+            if !self.start_run(schedule.clone()).await? {
+                warn!("定时计划 {} 上次执行尚未结束，跳过本次执行", schedule.id);
+            }
+        }
+        Ok(())
+    }
+
+    /// 立即执行计划，不影响下一次的执行时间
+    pub async fn run_now(&self, id: i32) -> Result<bool> {
+        let schedule = self
+            .db
+            .get_schedule(id)
+            .await?
+            .ok_or_else(|| anyhow!("计划不存在: {}", id))?;
+        self.start_run(schedule).await
+    }
+
+    /// 在后台开始执行计划，计划正在执行时返回 false
+    async fn start_run(&self, schedule: Schedule) -> Result<bool> {
+        if !self.running.lock().unwrap().insert(schedule.id) {
+            return Ok(false);
+        }
+
+        if let Err(e) = self.db.set_schedule_last_run(schedule.id, Utc::now()).await {
+            self.running.lock().unwrap().remove(&schedule.id);
+            return Err(e);
+        }
+
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            scheduler.execute(&schedule).await;
+            scheduler.running.lock().unwrap().remove(&schedule.id);
+        });
+        Ok(true)
+    }
+
+    /// 执行计划并保存执行记录
+    async fn execute(&self, schedule: &Schedule) {
+        let mut run = match self.db.create_schedule_run(schedule.id).await {
+            Ok(run) => run,
+            Err(e) => {
+                error!("创建计划执行记录失败: {}", e);
+                return;
+            }
+        };
+
+        info!("开始执行定时计划: {} {}", schedule.id, schedule.name);
+        match self.execute_run(schedule, &mut run).await {
+            Ok(()) => {
+                info!(
+                    "定时计划执行完成: {}，新增 {} 部动画",
+                    schedule.id, run.num_added
+                );
+                run.status = ScheduleRunStatus::Succeeded;
+            }
+            Err(e) => {
+                error!("定时计划执行失败: {} {}", schedule.id, e);
+                run.status = ScheduleRunStatus::Failed;
+                run.error = Some(e.to_string());
+            }
+        }
+        run.finished_at = Some(Utc::now());
+        if let Err(e) = self.db.update_schedule_run(run).await {
+            warn!("保存计划执行记录失败: {}", e);
+        }
+    }
+
+    async fn execute_run(&self, schedule: &Schedule, run: &mut ScheduleRun) -> Result<()> {
+        let details = ScheduleDetails::from_model(schedule.clone());
+
+        // 导入新番
+        let seasons = ingest_seasons(Utc::now());
+        let mut added = Vec::new();
+        for (season, year) in seasons {
+            let result = ingest_season(&self.db, &self.anilist, season, year).await?;
+            run.num_fetched += result.num_fetched as i32;
+            added.extend(result.added);
+        }
+        run.num_added = added.len() as i32;
+        self.db.update_schedule_run(run.clone()).await?;
+
+        // 为新导入的动画在每个平台上创建匹配任务
+        let mut job_ids = Vec::new();
+        if !added.is_empty() {
+            let mut job_runner = self.job_runner.lock().await;
+            for platform in details.platforms {
+                let filter = JobFilter {
+                    anilist_ids: added.clone(),
+                    ..Default::default()
+                };
+                let id = job_runner
+                    .create_job(
+                        platform,
+                        filter,
+                        details.provider.clone(),
+                        details.model.clone(),
+                        details.options.clone(),
+                    )
+                    .await?;
+                job_runner.run(id).await;
+                job_ids.push(id);
+            }
+        }
+        run.job_ids = json!(job_ids).to_string();
+        self.db.update_schedule_run(run.clone()).await?;
+
+        if details.export {
+            // 等待任务结束，导出文件包含本次的匹配结果
+            self.wait_for_jobs(&job_ids).await?;
+            let mut years: Vec<i32> = seasons.iter().map(|(_, year)| *year).collect();
+            years.dedup();
+            for year in years {
+                write_export(&self.db, year).await?;
+            }
+            compact_exports()?;
+            run.exported = true;
+        }
+
+        Ok(())
+    }
+
+    /// 等待任务不再处于运行状态，暂停或达到费用上限的任务也视为结束，超时时返回错误
+    async fn wait_for_jobs(&self, job_ids: &[i32]) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + JOB_WAIT_TIMEOUT;
+        loop {
+            let running = self
+                .db
+                .get_jobs()
+                .await?
+                .iter()
+                .any(|job| job_ids.contains(&job.id) && job.status == JobStatus::Running);
+            if !running {
+                return Ok(());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow!(
+                    "等待任务结束超时（{}小时），未导出: {:?}",
+                    JOB_WAIT_TIMEOUT.as_secs() / 3600,
+                    job_ids
+                ));
+            }
+            tokio::time::sleep(JOB_POLL_INTERVAL).await;
+        }
+    }
+
+    pub async fn list_schedules(&self) -> Result<Vec<ScheduleDetails>> {
+        let schedules = self.db.get_schedules().await?;
+        Ok(schedules
+            .into_iter()
+            .map(ScheduleDetails::from_model)
+            .collect())
+    }
+
+    /// 创建定时计划，返回计划id
+    pub async fn create_schedule(&self, request: ScheduleRequest) -> Result<i32> {
+        let now = Utc::now();
+        let schedule = self.to_model(
+            Schedule {
+                id: 0,
+                name: String::new(),
+                cron: String::new(),
+                enabled: true,
+                platforms: String::new(),
+                provider: String::new(),
+                model: String::new(),
+                options: String::new(),
+                export: false,
+                last_run_at: None,
+                next_run_at: None,
+                created_at: now,
+                updated_at: now,
+            },
+            request,
+        )?;
+        self.db.create_schedule(schedule).await
+    }
+
+    pub async fn update_schedule(&self, id: i32, request: ScheduleRequest) -> Result<()> {
+        let schedule = self
+            .db
+            .get_schedule(id)
+            .await?
+            .ok_or_else(|| anyhow!("计划不存在: {}", id))?;
+        let schedule = self.to_model(schedule, request)?;
+        self.db.update_schedule(schedule).await
+    }
+
+    pub async fn remove_schedule(&self, id: i32) -> Result<bool> {
+        self.db.delete_schedule(id).await
+    }
+
+    pub async fn list_runs(&self, id: i32) -> Result<Vec<ScheduleRunDetails>> {
+        let runs = self.db.get_schedule_runs(id, MAX_RUNS).await?;
+        Ok(runs
+            .into_iter()
+            .map(ScheduleRunDetails::from_model)
+            .collect())
+    }
+
+    /// 校验请求并写入计划，重新计算下一次执行时间
+    fn to_model(&self, mut schedule: Schedule, request: ScheduleRequest) -> Result<Schedule> {
+        // 提前校验provider，避免在计划执行时才失败
+        let model = self
+            .providers
+            .resolve(&request.provider)?
+            .resolve_model(request.model.as_deref());
+        let platforms = if request.platforms.is_empty() {
+            vec![Platform::BgmTv, Platform::Tmdb]
+        } else {
+            request.platforms
+        };
+
+        schedule.next_run_at = if request.enabled {
+            next_run_after(&request.cron, Utc::now())?
+        } else {
+            parse_cron(&request.cron)?;
+            None
+        };
+        schedule.name = request.name;
+        schedule.cron = request.cron;
+        schedule.enabled = request.enabled;
+        schedule.platforms = json!(platforms).to_string();
+        schedule.provider = request.provider;
+        schedule.model = model;
+        schedule.options = json!(request.options).to_string();
+        schedule.export = request.export;
+        Ok(schedule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_next_run_after() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 31, 12, 0, 0).unwrap();
+        assert_eq!(
+            next_run_after("0 3 * * *", now).unwrap(),
+            Some(Utc.with_ymd_and_hms(2025, 4, 1, 3, 0, 0).unwrap())
+        );
+        assert_eq!(
+            next_run_after("30 0 3 1 * *", now).unwrap(),
+            Some(Utc.with_ymd_and_hms(2025, 4, 1, 3, 0, 30).unwrap())
+        );
+        assert!(parse_cron("every day").is_err());
+    }
+
+    #[test]
+    fn test_ingest_seasons() {
+        let now = Utc.with_ymd_and_hms(2025, 11, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            ingest_seasons(now),
+            [(AniListSeason::Fall, 2025), (AniListSeason::Winter, 2026)]
+        );
+    }
+}
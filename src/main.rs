@@ -1,16 +1,24 @@
 use std::path::PathBuf;
 
-use agent::runner::{run_mapping_bgm_tv_agent, run_mapping_tmdb_agent};
+use agent::budget::MatchBudget;
+use agent::prompt::PromptRegistry;
+use agent::provider::ProviderRegistry;
+use agent::runner::{MatchOptions, run_mapping_bgm_tv_agent, run_mapping_tmdb_agent};
 use anyhow::Result;
+use api::types::CacheFilter;
 use clap::{Parser, Subcommand};
 use cli::import::import_animes;
 use dotenv::dotenv;
+use http::cache::{ResponseCache, cache_statistics};
+use http::cassette::CassetteServer;
+use models::db::DB;
 
 pub mod agent;
 pub mod anilist;
 pub mod api;
 pub mod cli;
 pub mod errors;
+pub mod http;
 pub mod job;
 pub mod migration;
 pub mod models;
@@ -31,10 +39,15 @@ enum Commands {
         /// 搜索关键词
         #[arg(short, long)]
         query: String,
-        #[arg(short, long, default_value = "bgm_tv")]
+        /// providers.json 中配置的 provider 名称
+        #[arg(short, long, default_value = "openai")]
         provider: String,
-        #[arg(short, long, default_value = "gpt-4o")]
-        model: String,
+        /// 不指定时使用 provider 的默认模型
+        #[arg(short, long)]
+        model: Option<String>,
+        /// 提示词版本，不指定时使用默认版本
+        #[arg(long)]
+        prompt_version: Option<String>,
     },
     /// 匹配动漫信息
     #[command(name = "match-tmdb")]
@@ -42,14 +55,45 @@ enum Commands {
         /// 搜索关键词
         #[arg(short, long)]
         query: String,
-        #[arg(short, long, default_value = "tmdb")]
+        /// providers.json 中配置的 provider 名称
+        #[arg(short, long, default_value = "openai")]
         provider: String,
-        #[arg(short, long, default_value = "gpt-4o")]
-        model: String,
+        /// 不指定时使用 provider 的默认模型
+        #[arg(short, long)]
+        model: Option<String>,
+        /// 提示词版本，不指定时使用默认版本
+        #[arg(long)]
+        prompt_version: Option<String>,
     },
     /// 启动服务器
     #[command(name = "server")]
     Server,
+    /// 查看外部接口响应缓存的统计
+    #[command(name = "cache-stats")]
+    CacheStats,
+    /// 删除外部接口响应缓存
+    #[command(name = "cache-purge")]
+    CachePurge {
+        /// 接口名称或前缀，如 bgm_tv 或 bgm_tv/search，不指定时处理所有接口
+        #[arg(short, long)]
+        endpoint: Option<String>,
+        /// 只处理请求参数中包含该关键词的条目
+        #[arg(short, long)]
+        keyword: Option<String>,
+        /// 只删除已过期的条目
+        #[arg(long)]
+        expired_only: bool,
+    },
+    /// 将外部接口响应缓存标记为过期，下次请求时重新获取
+    #[command(name = "cache-refresh")]
+    CacheRefresh {
+        /// 接口名称或前缀，如 bgm_tv 或 bgm_tv/search，不指定时处理所有接口
+        #[arg(short, long)]
+        endpoint: Option<String>,
+        /// 只处理请求参数中包含该关键词的条目
+        #[arg(short, long)]
+        keyword: Option<String>,
+    },
     /// 导入动漫信息
     #[command(name = "import")]
     Import {
@@ -59,6 +103,15 @@ enum Commands {
     },
 }
 
+/// 配置了数据库时为单次匹配启用响应缓存
+async fn install_response_cache() -> Result<()> {
+    if std::env::var("DATABASE_URL").is_ok() {
+        let db = DB::new_from_env().await?;
+        ResponseCache::install_from_env(&db)?;
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // 加载环境变量
@@ -72,27 +125,87 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
+    // 开启录制/回放时，所有外部请求都经过本地磁带服务
+    let _cassette = CassetteServer::install_from_env().await?;
+
     match cli.command {
         Commands::MatchBgm {
             query,
             provider,
             model,
+            prompt_version,
         } => {
-            let result = run_mapping_bgm_tv_agent(&query, &provider, &model, 1, 5).await?;
+            let registry = ProviderRegistry::from_env()?;
+            let provider = registry.resolve(&provider)?;
+            let model = provider.resolve_model(model.as_deref());
+            let prompts = PromptRegistry::from_env()?;
+            let prompt = prompts.get(prompt_version.as_deref())?;
+            install_response_cache().await?;
+            let result = run_mapping_bgm_tv_agent(
+                &query,
+                provider,
+                &model,
+                prompt,
+                &MatchOptions::new(MatchBudget::from_env(), 1, 5),
+            )
+            .await?;
             println!("{}", serde_json::to_string(&result).unwrap());
         }
         Commands::MatchTmdb {
             query,
             provider,
             model,
+            prompt_version,
         } => {
-            let result = run_mapping_tmdb_agent(&query, &provider, &model, 1, 5).await?;
+            let registry = ProviderRegistry::from_env()?;
+            let provider = registry.resolve(&provider)?;
+            let model = provider.resolve_model(model.as_deref());
+            let prompts = PromptRegistry::from_env()?;
+            let prompt = prompts.get(prompt_version.as_deref())?;
+            install_response_cache().await?;
+            let result = run_mapping_tmdb_agent(
+                &query,
+                provider,
+                &model,
+                prompt,
+                &MatchOptions::new(MatchBudget::from_env(), 1, 5),
+            )
+            .await?;
             println!("{}", serde_json::to_string(&result).unwrap());
         }
         Commands::Server => {
             let server = server::Server::new().await?;
             server.serve().await?;
         }
+        Commands::CacheStats => {
+            let db = DB::new_from_env().await?;
+            let statistics = cache_statistics(&db).await?;
+            println!("{}", serde_json::to_string(&statistics).unwrap());
+        }
+        Commands::CachePurge {
+            endpoint,
+            keyword,
+            expired_only,
+        } => {
+            let db = DB::new_from_env().await?;
+            let filter = CacheFilter {
+                endpoint,
+                keyword,
+                expired_only,
+            };
+            let count = db.purge_response_cache(&filter).await?;
+            println!("已删除 {} 条缓存", count);
+        }
+        Commands::CacheRefresh { endpoint, keyword } => {
+            let db = DB::new_from_env().await?;
+            let filter = CacheFilter {
+                endpoint,
+                keyword,
+                expired_only: false,
+            };
+            let count = db.expire_response_cache(&filter).await?;
+            println!("已标记 {} 条缓存为过期", count);
+        }
         Commands::Import { path } => {
             let result = import_animes(PathBuf::from(path)).await?;
             println!("{}", serde_json::to_string(&result).unwrap());
@@ -0,0 +1,127 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 match_transcripts 表
+        manager
+            .create_table(
+                Table::create()
+                    .table(MatchTranscripts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MatchTranscripts::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MatchTranscripts::AnilistId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MatchTranscripts::Platform)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MatchTranscripts::Provider)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MatchTranscripts::Model).string().not_null())
+                    .col(ColumnDef::new(MatchTranscripts::Prompt).text().not_null())
+                    .col(
+                        ColumnDef::new(MatchTranscripts::ToolCalls)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MatchTranscripts::Output).text())
+                    .col(ColumnDef::new(MatchTranscripts::Result).text())
+                    .col(ColumnDef::new(MatchTranscripts::Error).text())
+                    .col(
+                        ColumnDef::new(MatchTranscripts::PromptTokens)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(MatchTranscripts::CompletionTokens)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(MatchTranscripts::DurationMs)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(MatchTranscripts::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MatchTranscripts::Table, MatchTranscripts::AnilistId)
+                            .to(Animes::Table, Animes::AnilistId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_match_transcripts_anime_platform")
+                    .table(MatchTranscripts::Table)
+                    .col(MatchTranscripts::AnilistId)
+                    .col(MatchTranscripts::Platform)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MatchTranscripts::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Animes {
+    Table,
+    AnilistId,
+}
+
+#[derive(Iden)]
+enum MatchTranscripts {
+    Table,
+    Id,
+    AnilistId,
+    Platform,
+    Provider,
+    Model,
+    Prompt,
+    ToolCalls,
+    Output,
+    Result,
+    Error,
+    PromptTokens,
+    CompletionTokens,
+    DurationMs,
+    CreatedAt,
+}
@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 mapping_candidates 表
+        manager
+            .create_table(
+                Table::create()
+                    .table(MappingCandidates::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MappingCandidates::AnilistId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MappingCandidates::Platform)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MappingCandidates::Rank).integer().not_null())
+                    .col(
+                        ColumnDef::new(MappingCandidates::PlatformId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MappingCandidates::Name).string())
+                    .col(ColumnDef::new(MappingCandidates::Season).integer())
+                    .col(
+                        ColumnDef::new(MappingCandidates::Score)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MappingCandidates::Reason).text())
+                    .col(
+                        ColumnDef::new(MappingCandidates::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(MappingCandidates::AnilistId)
+                            .col(MappingCandidates::Platform)
+                            .col(MappingCandidates::Rank),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MappingCandidates::Table, MappingCandidates::AnilistId)
+                            .to(Animes::Table, Animes::AnilistId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MappingCandidates::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Animes {
+    Table,
+    AnilistId,
+}
+
+#[derive(Iden)]
+enum MappingCandidates {
+    Table,
+    AnilistId,
+    Platform,
+    Rank,
+    PlatformId,
+    Name,
+    Season,
+    Score,
+    Reason,
+    CreatedAt,
+}
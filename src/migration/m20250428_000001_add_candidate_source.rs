@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 记录候选由哪个模型提出，多模型共识匹配时用于裁决
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MappingCandidates::Table)
+                    .add_column(ColumnDef::new(MappingCandidates::Source).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MappingCandidates::Table)
+                    .drop_column(MappingCandidates::Source)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum MappingCandidates {
+    Table,
+    Source,
+}
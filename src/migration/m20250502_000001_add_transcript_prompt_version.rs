@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 记录每次匹配使用的提示词版本，旧记录使用的是内置的 v1
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MatchTranscripts::Table)
+                    .add_column(
+                        ColumnDef::new(MatchTranscripts::PromptVersion)
+                            .string()
+                            .not_null()
+                            .default("v1"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MatchTranscripts::Table)
+                    .drop_column(MatchTranscripts::PromptVersion)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum MatchTranscripts {
+    Table,
+    PromptVersion,
+}
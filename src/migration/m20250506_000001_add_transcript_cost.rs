@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 记录每次匹配的费用，价格未知时为空
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MatchTranscripts::Table)
+                    .add_column(ColumnDef::new(MatchTranscripts::Cost).double())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MatchTranscripts::Table)
+                    .drop_column(MatchTranscripts::Cost)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum MatchTranscripts {
+    Table,
+    Cost,
+}
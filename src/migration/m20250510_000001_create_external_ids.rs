@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 external_ids 表
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExternalIds::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ExternalIds::AnilistId).integer().not_null())
+                    .col(ColumnDef::new(ExternalIds::Source).string().not_null())
+                    .col(ColumnDef::new(ExternalIds::ExternalId).string().not_null())
+                    .col(
+                        ColumnDef::new(ExternalIds::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ExternalIds::AnilistId)
+                            .col(ExternalIds::Source),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ExternalIds::Table, ExternalIds::AnilistId)
+                            .to(Animes::Table, Animes::AnilistId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ExternalIds::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Animes {
+    Table,
+    AnilistId,
+}
+
+#[derive(Iden)]
+enum ExternalIds {
+    Table,
+    AnilistId,
+    Source,
+    ExternalId,
+    CreatedAt,
+}
@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 episode_mappings 表
+        manager
+            .create_table(
+                Table::create()
+                    .table(EpisodeMappings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EpisodeMappings::AnilistId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(EpisodeMappings::Platform)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(EpisodeMappings::Episode)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(EpisodeMappings::Season).integer())
+                    .col(
+                        ColumnDef::new(EpisodeMappings::PlatformEpisode)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(EpisodeMappings::PlatformEpisodeId).string())
+                    .col(ColumnDef::new(EpisodeMappings::AirDate).string())
+                    .col(
+                        ColumnDef::new(EpisodeMappings::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(EpisodeMappings::AnilistId)
+                            .col(EpisodeMappings::Platform)
+                            .col(EpisodeMappings::Episode),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(EpisodeMappings::Table, EpisodeMappings::AnilistId)
+                            .to(Animes::Table, Animes::AnilistId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EpisodeMappings::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Animes {
+    Table,
+    AnilistId,
+}
+
+#[derive(Iden)]
+enum EpisodeMappings {
+    Table,
+    AnilistId,
+    Platform,
+    Episode,
+    Season,
+    PlatformEpisode,
+    PlatformEpisodeId,
+    AirDate,
+    CreatedAt,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 response_cache 表
+        manager
+            .create_table(
+                Table::create()
+                    .table(ResponseCache::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ResponseCache::Endpoint).string().not_null())
+                    .col(ColumnDef::new(ResponseCache::Params).text().not_null())
+                    .col(ColumnDef::new(ResponseCache::Body).text().not_null())
+                    .col(
+                        ColumnDef::new(ResponseCache::ExpiresAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ResponseCache::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ResponseCache::Endpoint)
+                            .col(ResponseCache::Params),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_response_cache_expires_at")
+                    .table(ResponseCache::Table)
+                    .col(ResponseCache::ExpiresAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ResponseCache::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum ResponseCache {
+    Table,
+    Endpoint,
+    Params,
+    Body,
+    ExpiresAt,
+    CreatedAt,
+}
@@ -0,0 +1,173 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 jobs 表
+        manager
+            .create_table(
+                Table::create()
+                    .table(Jobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Jobs::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Jobs::Platform).string().not_null())
+                    .col(ColumnDef::new(Jobs::Year).integer().not_null())
+                    .col(ColumnDef::new(Jobs::Status).string().not_null())
+                    .col(ColumnDef::new(Jobs::Provider).string().not_null())
+                    .col(ColumnDef::new(Jobs::Model).string().not_null())
+                    .col(
+                        ColumnDef::new(Jobs::ConsensusModels)
+                            .text()
+                            .not_null()
+                            .default("[]"),
+                    )
+                    .col(ColumnDef::new(Jobs::PromptVersion).string().not_null())
+                    .col(ColumnDef::new(Jobs::MaxCost).double())
+                    .col(counter(Jobs::NumAnimesToMatch))
+                    .col(counter(Jobs::NumProcessed))
+                    .col(counter(Jobs::NumMatched))
+                    .col(counter(Jobs::NumPrematched))
+                    .col(counter(Jobs::NumFailed))
+                    .col(counter(Jobs::NumBudgetExceeded))
+                    .col(counter(Jobs::NumConflicts))
+                    .col(counter(Jobs::NumRejected))
+                    .col(
+                        ColumnDef::new(Jobs::PromptTokens)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Jobs::CompletionTokens)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Jobs::Cost).double().not_null().default(0.0))
+                    .col(counter(Jobs::CurrentIndex))
+                    .col(
+                        ColumnDef::new(Jobs::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Jobs::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 创建 job_items 表
+        manager
+            .create_table(
+                Table::create()
+                    .table(JobItems::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(JobItems::JobId).integer().not_null())
+                    .col(ColumnDef::new(JobItems::Position).integer().not_null())
+                    .col(ColumnDef::new(JobItems::AnilistId).integer().not_null())
+                    .col(ColumnDef::new(JobItems::Outcome).string())
+                    .col(ColumnDef::new(JobItems::PlatformId).string())
+                    .col(ColumnDef::new(JobItems::Score).integer())
+                    .col(ColumnDef::new(JobItems::Error).text())
+                    .col(ColumnDef::new(JobItems::ProcessedAt).timestamp())
+                    .primary_key(Index::create().col(JobItems::JobId).col(JobItems::Position))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(JobItems::Table, JobItems::JobId)
+                            .to(Jobs::Table, Jobs::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(JobItems::Table, JobItems::AnilistId)
+                            .to(Animes::Table, Animes::AnilistId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(JobItems::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Jobs::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// 默认为0的计数列
+fn counter(column: Jobs) -> ColumnDef {
+    ColumnDef::new(column)
+        .integer()
+        .not_null()
+        .default(0)
+        .to_owned()
+}
+
+#[derive(Iden)]
+enum Animes {
+    Table,
+    AnilistId,
+}
+
+#[derive(Iden)]
+enum Jobs {
+    Table,
+    Id,
+    Platform,
+    Year,
+    Status,
+    Provider,
+    Model,
+    ConsensusModels,
+    PromptVersion,
+    MaxCost,
+    NumAnimesToMatch,
+    NumProcessed,
+    NumMatched,
+    NumPrematched,
+    NumFailed,
+    NumBudgetExceeded,
+    NumConflicts,
+    NumRejected,
+    PromptTokens,
+    CompletionTokens,
+    Cost,
+    CurrentIndex,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum JobItems {
+    Table,
+    JobId,
+    Position,
+    AnilistId,
+    Outcome,
+    PlatformId,
+    Score,
+    Error,
+    ProcessedAt,
+}
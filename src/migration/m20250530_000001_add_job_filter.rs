@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 任务按筛选条件选择动画，不再限定于单个年份
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Jobs::Table)
+                    .add_column(ColumnDef::new(Jobs::Filter).text().not_null().default("{}"))
+                    .to_owned(),
+            )
+            .await?;
+
+        // 已有任务选择的是该年份中未匹配的动画
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE jobs SET filter = json_object(
+                    'year_from', year,
+                    'year_to', year,
+                    'review_status', json_array('UnMatched')
+                )",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Jobs::Table)
+                    .drop_column(Jobs::Year)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Jobs::Table)
+                    .add_column(ColumnDef::new(Jobs::Year).integer().not_null().default(0))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE jobs SET year = COALESCE(json_extract(filter, '$.year_from'), 0)",
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Jobs::Table)
+                    .drop_column(Jobs::Filter)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Jobs {
+    Table,
+    Filter,
+    Year,
+}
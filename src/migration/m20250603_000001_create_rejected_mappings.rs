@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 rejected_mappings 表，记录审核时被拒绝的平台条目
+        manager
+            .create_table(
+                Table::create()
+                    .table(RejectedMappings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RejectedMappings::AnilistId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RejectedMappings::Platform)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RejectedMappings::PlatformId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RejectedMappings::RejectedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(RejectedMappings::AnilistId)
+                            .col(RejectedMappings::Platform)
+                            .col(RejectedMappings::PlatformId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RejectedMappings::Table, RejectedMappings::AnilistId)
+                            .to(Animes::Table, Animes::AnilistId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RejectedMappings::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Animes {
+    Table,
+    AnilistId,
+}
+
+#[derive(Iden)]
+enum RejectedMappings {
+    Table,
+    AnilistId,
+    Platform,
+    PlatformId,
+    RejectedAt,
+}
@@ -0,0 +1,185 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 schedules 表，定时导入新番并创建匹配任务
+        manager
+            .create_table(
+                Table::create()
+                    .table(Schedules::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Schedules::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Schedules::Name).string().not_null())
+                    .col(ColumnDef::new(Schedules::Cron).string().not_null())
+                    .col(
+                        ColumnDef::new(Schedules::Enabled)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(Schedules::Platforms)
+                            .text()
+                            .not_null()
+                            .default("[]"),
+                    )
+                    .col(ColumnDef::new(Schedules::Provider).string().not_null())
+                    .col(ColumnDef::new(Schedules::Model).string().not_null())
+                    .col(
+                        ColumnDef::new(Schedules::Options)
+                            .text()
+                            .not_null()
+                            .default("{}"),
+                    )
+                    .col(
+                        ColumnDef::new(Schedules::Export)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(Schedules::LastRunAt).timestamp())
+                    .col(ColumnDef::new(Schedules::NextRunAt).timestamp())
+                    .col(
+                        ColumnDef::new(Schedules::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Schedules::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 创建 schedule_runs 表，记录每次执行的结果
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduleRuns::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ScheduleRuns::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRuns::ScheduleId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ScheduleRuns::Status).string().not_null())
+                    .col(
+                        ColumnDef::new(ScheduleRuns::NumFetched)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRuns::NumAdded)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRuns::JobIds)
+                            .text()
+                            .not_null()
+                            .default("[]"),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRuns::Exported)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(ScheduleRuns::Error).text())
+                    .col(
+                        ColumnDef::new(ScheduleRuns::StartedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ScheduleRuns::FinishedAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ScheduleRuns::Table, ScheduleRuns::ScheduleId)
+                            .to(Schedules::Table, Schedules::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_schedule_runs_schedule_id")
+                    .table(ScheduleRuns::Table)
+                    .col(ScheduleRuns::ScheduleId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ScheduleRuns::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Schedules::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Schedules {
+    Table,
+    Id,
+    Name,
+    Cron,
+    Enabled,
+    Platforms,
+    Provider,
+    Model,
+    Options,
+    Export,
+    LastRunAt,
+    NextRunAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum ScheduleRuns {
+    Table,
+    Id,
+    ScheduleId,
+    Status,
+    NumFetched,
+    NumAdded,
+    JobIds,
+    Exported,
+    Error,
+    StartedAt,
+    FinishedAt,
+}
@@ -1,12 +1,40 @@
 use sea_orm_migration::prelude::*;
 
 mod m20240321_000001_create_initial_tables;
+mod m20250420_000001_create_match_transcripts;
+mod m20250425_000001_create_mapping_candidates;
+mod m20250428_000001_add_candidate_source;
+mod m20250502_000001_add_transcript_prompt_version;
+mod m20250506_000001_add_transcript_cost;
+mod m20250510_000001_create_external_ids;
+mod m20250514_000001_create_episode_mappings;
+mod m20250518_000001_create_response_cache;
+mod m20250522_000001_create_jobs;
+mod m20250526_000001_add_job_concurrency;
+mod m20250530_000001_add_job_filter;
+mod m20250603_000001_create_rejected_mappings;
+mod m20250607_000001_create_schedules;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20240321_000001_create_initial_tables::Migration)]
+        vec![
+            Box::new(m20240321_000001_create_initial_tables::Migration),
+            Box::new(m20250420_000001_create_match_transcripts::Migration),
+            Box::new(m20250425_000001_create_mapping_candidates::Migration),
+            Box::new(m20250428_000001_add_candidate_source::Migration),
+            Box::new(m20250502_000001_add_transcript_prompt_version::Migration),
+            Box::new(m20250506_000001_add_transcript_cost::Migration),
+            Box::new(m20250510_000001_create_external_ids::Migration),
+            Box::new(m20250514_000001_create_episode_mappings::Migration),
+            Box::new(m20250518_000001_create_response_cache::Migration),
+            Box::new(m20250522_000001_create_jobs::Migration),
+            Box::new(m20250526_000001_add_job_concurrency::Migration),
+            Box::new(m20250530_000001_add_job_filter::Migration),
+            Box::new(m20250603_000001_create_rejected_mappings::Migration),
+            Box::new(m20250607_000001_create_schedules::Migration),
+        ]
     }
 }
@@ -34,3 +34,24 @@ impl Related<super::mappings::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+impl Model {
+    /// 测试用的动画，除id外使用固定的默认值
+    pub fn test(anilist_id: i32) -> Self {
+        Self {
+            anilist_id,
+            media_type: MediaType::TV,
+            titles: "[]".to_string(),
+            year: 2024,
+            season: None,
+            start_date: None,
+            episode_count: None,
+            season_number: None,
+            episode_number: None,
+            absolute_episode_number: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::models::enums::Platform;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "mapping_candidates")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub anilist_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub platform: Platform,
+    /// 候选排名，从0开始
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub rank: i32,
+    pub platform_id: String,
+    pub name: Option<String>,
+    pub season: Option<i32>,
+    pub score: u8,
+    pub reason: Option<String>,
+    /// 提出该候选的模型，预匹配时为 prematch
+    pub source: Option<String>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::anime::Entity",
+        from = "Column::AnilistId",
+        to = "super::anime::Column::AnilistId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Anime,
+}
+
+impl Related<super::anime::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Anime.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
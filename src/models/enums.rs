@@ -14,6 +14,9 @@ pub enum ReviewStatus {
     Rejected,
     #[sea_orm(string_value = "Dropped")]
     Dropped,
+    /// 多个模型的匹配结果不一致，需要人工裁决
+    #[sea_orm(string_value = "Conflict")]
+    Conflict,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
@@ -41,3 +44,61 @@ pub enum MediaType {
     #[sea_orm(string_value = "Unknown")]
     Unknown,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "job_status")]
+pub enum JobStatus {
+    #[sea_orm(string_value = "Created")]
+    Created,
+    #[sea_orm(string_value = "Running")]
+    Running,
+    #[sea_orm(string_value = "Paused")]
+    Paused,
+    #[sea_orm(string_value = "Completed")]
+    Completed,
+    #[sea_orm(string_value = "Failed")]
+    Failed,
+    /// 累计费用达到上限后自动停止
+    #[sea_orm(string_value = "CostCapReached")]
+    CostCapReached,
+}
+
+/// 任务中单个动画的处理结果
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "job_item_outcome")]
+pub enum JobItemOutcome {
+    #[sea_orm(string_value = "Matched")]
+    Matched,
+    /// 通过确定性预匹配完成
+    #[sea_orm(string_value = "Prematched")]
+    Prematched,
+    #[sea_orm(string_value = "NoMatch")]
+    NoMatch,
+    /// 多个模型的结果不一致
+    #[sea_orm(string_value = "Conflict")]
+    Conflict,
+    /// 提交的条目未通过校验
+    #[sea_orm(string_value = "Rejected")]
+    Rejected,
+    #[sea_orm(string_value = "Failed")]
+    Failed,
+    /// 超出预算而中止
+    #[sea_orm(string_value = "BudgetExceeded")]
+    BudgetExceeded,
+}
+
+/// 定时计划单次执行的状态
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "schedule_run_status"
+)]
+pub enum ScheduleRunStatus {
+    #[sea_orm(string_value = "Running")]
+    Running,
+    #[sea_orm(string_value = "Succeeded")]
+    Succeeded,
+    #[sea_orm(string_value = "Failed")]
+    Failed,
+}
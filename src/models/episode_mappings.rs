@@ -0,0 +1,44 @@
+use crate::models::enums::Platform;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// AniList 每一集对应的平台章节
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "episode_mappings")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub anilist_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub platform: Platform,
+    /// AniList 中的集数，从1开始
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub episode: i32,
+    /// TMDB 的季数，BgmTV 为空
+    pub season: Option<i32>,
+    /// 平台上的集数（TMDB 为季内集数，BgmTV 为条目内集数）
+    pub platform_episode: i32,
+    /// 平台上的章节id
+    pub platform_episode_id: Option<String>,
+    pub air_date: Option<String>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::anime::Entity",
+        from = "Column::AnilistId",
+        to = "super::anime::Column::AnilistId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Anime,
+}
+
+impl Related<super::anime::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Anime.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,6 +2,9 @@ use super::db::DB;
 use crate::models::anime::Column as AnimeColumn;
 use crate::models::anime::Entity as AnimeEntity;
 use crate::models::anime::Model as Anime;
+use crate::models::episode_mappings::Column as EpisodeMappingColumn;
+use crate::models::episode_mappings::Entity as EpisodeMappingEntity;
+use crate::models::episode_mappings::Model as EpisodeMapping;
 use crate::models::mappings::Column as AnimeMappingColumn;
 use crate::models::mappings::Entity as AnimeMappingEntity;
 use crate::models::mappings::Model as AnimeMapping;
@@ -17,6 +20,9 @@ pub struct ExportAnime {
     #[serde(flatten)]
     pub anime: Anime,
     pub mappings: Vec<AnimeMapping>,
+    /// 分集映射，旧的导出文件中没有该字段
+    #[serde(default)]
+    pub episodes: Vec<EpisodeMapping>,
 }
 
 impl DB {
@@ -29,7 +35,12 @@ impl DB {
         let anilist_ids: Vec<i32> = animes.iter().map(|a| a.anilist_id).collect();
 
         let mappings = AnimeMappingEntity::find()
-            .filter(AnimeMappingColumn::AnilistId.is_in(anilist_ids))
+            .filter(AnimeMappingColumn::AnilistId.is_in(anilist_ids.clone()))
+            .all(self.conn())
+            .await?;
+
+        let episodes = EpisodeMappingEntity::find()
+            .filter(EpisodeMappingColumn::AnilistId.is_in(anilist_ids))
             .all(self.conn())
             .await?;
 
@@ -44,6 +55,16 @@ impl DB {
                 .push(mapping);
         }
 
+        let mut episode_groups: std::collections::HashMap<i32, Vec<EpisodeMapping>> =
+            std::collections::HashMap::new();
+
+        for episode in episodes {
+            episode_groups
+                .entry(episode.anilist_id)
+                .or_default()
+                .push(episode);
+        }
+
         Ok(animes
             .into_iter()
             .map(|anime| {
@@ -51,6 +72,7 @@ impl DB {
                 ExportAnime {
                     anime,
                     mappings: mapping_groups.get(&anilist_id).cloned().unwrap_or_default(),
+                    episodes: episode_groups.remove(&anilist_id).unwrap_or_default(),
                 }
             })
             .collect())
@@ -64,8 +86,10 @@ impl DB {
         // 1. 提取所有anime记录和mapping记录
         let anime_models: Vec<Anime> = animes.iter().map(|item| item.anime.clone()).collect();
         let mut all_mappings: Vec<AnimeMapping> = Vec::new();
+        let mut all_episodes: Vec<EpisodeMapping> = Vec::new();
         for export_anime in &animes {
             all_mappings.extend(export_anime.mappings.clone());
+            all_episodes.extend(export_anime.episodes.clone());
         }
 
         // 2. 批量upsert anime记录
@@ -134,6 +158,32 @@ impl DB {
                 .await?;
         }
 
+        // 4. 批量upsert分集映射，分批写入避免超出参数上限
+        for chunk in all_episodes.chunks(500) {
+            EpisodeMappingEntity::insert_many(
+                chunk
+                    .iter()
+                    .cloned()
+                    .map(IntoActiveModel::into_active_model),
+            )
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::columns([
+                    EpisodeMappingColumn::AnilistId,
+                    EpisodeMappingColumn::Platform,
+                    EpisodeMappingColumn::Episode,
+                ])
+                .update_columns([
+                    EpisodeMappingColumn::Season,
+                    EpisodeMappingColumn::PlatformEpisode,
+                    EpisodeMappingColumn::PlatformEpisodeId,
+                    EpisodeMappingColumn::AirDate,
+                ])
+                .to_owned(),
+            )
+            .exec(self.conn())
+            .await?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 导入数据中携带的其他站点id，如 mal、anidb、thetvdb、imdb
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "external_ids")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub anilist_id: i32,
+    /// 站点名称，如 mal、anidb
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub source: String,
+    pub external_id: String,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::anime::Entity",
+        from = "Column::AnilistId",
+        to = "super::anime::Column::AnilistId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Anime,
+}
+
+impl Related<super::anime::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Anime.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
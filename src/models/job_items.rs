@@ -0,0 +1,55 @@
+use crate::models::enums::JobItemOutcome;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 任务中待处理的动画及其处理结果
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "job_items")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub job_id: i32,
+    /// 在任务队列中的位置，从0开始
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub position: i32,
+    pub anilist_id: i32,
+    /// 尚未处理时为空
+    pub outcome: Option<JobItemOutcome>,
+    pub platform_id: Option<String>,
+    pub score: Option<i32>,
+    pub error: Option<String>,
+    pub processed_at: Option<DateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::jobs::Entity",
+        from = "Column::JobId",
+        to = "super::jobs::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Job,
+    #[sea_orm(
+        belongs_to = "super::anime::Entity",
+        from = "Column::AnilistId",
+        to = "super::anime::Column::AnilistId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Anime,
+}
+
+impl Related<super::jobs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Job.def()
+    }
+}
+
+impl Related<super::anime::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Anime.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
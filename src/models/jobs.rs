@@ -0,0 +1,82 @@
+use crate::models::enums::{JobStatus, Platform};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 匹配任务，服务重启后从这里恢复
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "jobs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub platform: Platform,
+    /// JSON 对象，任务选择动画的条件
+    pub filter: String,
+    pub status: JobStatus,
+    pub provider: String,
+    pub model: String,
+    /// JSON 数组，共识模式下额外参与匹配的模型
+    pub consensus_models: String,
+    pub prompt_version: String,
+    pub max_cost: Option<f64>,
+    /// 同时处理的动画数量
+    pub concurrency: i32,
+    pub num_animes_to_match: i32,
+    pub num_processed: i32,
+    pub num_matched: i32,
+    pub num_prematched: i32,
+    pub num_failed: i32,
+    pub num_budget_exceeded: i32,
+    pub num_conflicts: i32,
+    pub num_rejected: i32,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cost: f64,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::job_items::Entity")]
+    JobItems,
+}
+
+impl Related<super::job_items::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::JobItems.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+impl Model {
+    /// 测试用的新建任务，计数均为0
+    pub fn test(platform: Platform) -> Self {
+        Self {
+            id: 0,
+            platform,
+            filter: "{}".to_string(),
+            status: JobStatus::Created,
+            provider: "deepseek".to_string(),
+            model: "deepseek-chat".to_string(),
+            consensus_models: "[]".to_string(),
+            prompt_version: "v1".to_string(),
+            max_cost: None,
+            concurrency: 1,
+            num_animes_to_match: 0,
+            num_processed: 0,
+            num_matched: 0,
+            num_prematched: 0,
+            num_failed: 0,
+            num_budget_exceeded: 0,
+            num_conflicts: 0,
+            num_rejected: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            cost: 0.0,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+}
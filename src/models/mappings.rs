@@ -34,3 +34,19 @@ impl Related<super::anime::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+impl Model {
+    /// 测试用的未匹配映射
+    pub fn test(anilist_id: i32, platform: Platform) -> Self {
+        Self {
+            anilist_id,
+            platform,
+            platform_id: None,
+            review_status: ReviewStatus::UnMatched,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            score: 0,
+        }
+    }
+}
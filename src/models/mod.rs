@@ -1,7 +1,17 @@
 pub mod anime;
+pub mod candidates;
 pub mod db;
 pub mod enums;
+pub mod episode_mappings;
 pub mod export;
+pub mod external_ids;
+pub mod job_items;
+pub mod jobs;
 pub mod mappings;
 pub mod prelude;
 pub mod query;
+pub mod rejected_mappings;
+pub mod response_cache;
+pub mod schedule_runs;
+pub mod schedules;
+pub mod transcripts;
@@ -1,2 +1,12 @@
 pub use super::anime::Entity as Anime;
+pub use super::candidates::Entity as MappingCandidate;
+pub use super::episode_mappings::Entity as EpisodeMapping;
+pub use super::external_ids::Entity as ExternalId;
+pub use super::job_items::Entity as JobItem;
+pub use super::jobs::Entity as Job;
 pub use super::mappings::Entity as AnimeMapping;
+pub use super::rejected_mappings::Entity as RejectedMapping;
+pub use super::response_cache::Entity as ResponseCache;
+pub use super::schedule_runs::Entity as ScheduleRun;
+pub use super::schedules::Entity as Schedule;
+pub use super::transcripts::Entity as MatchTranscript;
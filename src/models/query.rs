@@ -1,28 +1,80 @@
 use super::db::DB;
+use super::enums::JobStatus;
+use super::enums::MediaType;
 use super::enums::Platform;
 use super::enums::ReviewStatus;
+use super::enums::ScheduleRunStatus;
+use crate::agent::agent::MatchCandidate;
+use crate::agent::episodes::EpisodeMapping;
+use crate::agent::known_ids::{KnownIds, KnownMapping};
+use crate::agent::transcript::{MatchTranscript, PREMATCH_PROVIDER};
+use crate::api::types::CacheFilter;
+use crate::api::types::JobFilter;
 use crate::api::types::Pagination;
 use crate::api::types::QueryAnimes;
 use crate::api::types::Summary;
+use crate::api::types::{UsageStatistic, UsageStatistics};
 use crate::models::anime::Column as AnimeColumn;
 use crate::models::anime::Entity as AnimeEntity;
 use crate::models::anime::Model as Anime;
+use crate::models::anime::Relation as AnimeRelation;
+use crate::models::candidates::ActiveModel as CandidateActiveModel;
+use crate::models::candidates::Column as CandidateColumn;
+use crate::models::candidates::Entity as CandidateEntity;
+use crate::models::candidates::Model as Candidate;
+use crate::models::episode_mappings::ActiveModel as EpisodeMappingActiveModel;
+use crate::models::episode_mappings::Column as EpisodeMappingColumn;
+use crate::models::episode_mappings::Entity as EpisodeMappingEntity;
+use crate::models::episode_mappings::Model as EpisodeMappingModel;
+use crate::models::external_ids::Column as ExternalIdColumn;
+use crate::models::external_ids::Entity as ExternalIdEntity;
+use crate::models::external_ids::Model as ExternalId;
+use crate::models::job_items::ActiveModel as JobItemActiveModel;
+use crate::models::job_items::Column as JobItemColumn;
+use crate::models::job_items::Entity as JobItemEntity;
+use crate::models::job_items::Model as JobItem;
+use crate::models::jobs::ActiveModel as JobActiveModel;
+use crate::models::jobs::Column as JobColumn;
+use crate::models::jobs::Entity as JobEntity;
+use crate::models::jobs::Model as Job;
 use crate::models::mappings::Column as AnimeMappingColumn;
 use crate::models::mappings::Entity as AnimeMappingEntity;
 use crate::models::mappings::Model as AnimeMapping;
-use anyhow::Result;
-use chrono::Utc;
+use crate::models::rejected_mappings::ActiveModel as RejectedMappingActiveModel;
+use crate::models::rejected_mappings::Column as RejectedMappingColumn;
+use crate::models::rejected_mappings::Entity as RejectedMappingEntity;
+use crate::models::response_cache::ActiveModel as ResponseCacheActiveModel;
+use crate::models::response_cache::Column as ResponseCacheColumn;
+use crate::models::response_cache::Entity as ResponseCacheEntity;
+use crate::models::response_cache::Model as ResponseCacheEntry;
+use crate::models::schedule_runs::ActiveModel as ScheduleRunActiveModel;
+use crate::models::schedule_runs::Column as ScheduleRunColumn;
+use crate::models::schedule_runs::Entity as ScheduleRunEntity;
+use crate::models::schedule_runs::Model as ScheduleRun;
+use crate::models::schedules::Column as ScheduleColumn;
+use crate::models::schedules::Entity as ScheduleEntity;
+use crate::models::schedules::Model as Schedule;
+use crate::models::transcripts::ActiveModel as TranscriptActiveModel;
+use crate::models::transcripts::Column as TranscriptColumn;
+use crate::models::transcripts::Entity as TranscriptEntity;
+use crate::models::transcripts::Model as Transcript;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
 use sea_orm::ColumnTrait;
+use sea_orm::Condition;
 use sea_orm::JoinType;
 use sea_orm::Order;
 use sea_orm::PaginatorTrait;
 use sea_orm::QueryFilter;
 use sea_orm::QueryOrder;
 use sea_orm::QuerySelect;
+use sea_orm::RelationTrait;
+use sea_orm::sea_query::{Expr, OnConflict};
 use sea_orm::{
-    ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Set, TransactionTrait,
+    ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, NotSet, Set,
+    TransactionTrait,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 impl DB {
     pub async fn batch_add_animes(&self, animes: (Vec<Anime>, Vec<AnimeMapping>)) -> Result<()> {
@@ -52,6 +104,55 @@ impl DB {
         Ok(())
     }
 
+    /// 保存其他站点的id，已存在时覆盖
+    pub async fn save_external_ids(&self, external_ids: Vec<ExternalId>) -> Result<()> {
+        // 分批写入，避免超出 SQLite 单条语句的参数上限
+        for chunk in external_ids.chunks(500) {
+            ExternalIdEntity::insert_many(
+                chunk
+                    .iter()
+                    .cloned()
+                    .map(IntoActiveModel::into_active_model),
+            )
+            .on_conflict(
+                OnConflict::columns([ExternalIdColumn::AnilistId, ExternalIdColumn::Source])
+                    .update_column(ExternalIdColumn::ExternalId)
+                    .to_owned(),
+            )
+            .exec(self.conn())
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// 获取动画已知的id：其他站点的id以及各平台已确认的映射
+    pub async fn get_known_ids(&self, anilist_id: i32) -> Result<KnownIds> {
+        let (anime, mappings) = self.get_anime(anilist_id).await?;
+        let external_ids = ExternalIdEntity::find()
+            .filter(ExternalIdColumn::AnilistId.eq(anilist_id))
+            .all(self.conn())
+            .await?;
+
+        Ok(KnownIds {
+            mappings: mappings
+                .into_iter()
+                // 未审核的匹配结果可能有误，不作为其他平台的参考
+                .filter(|mapping| mapping.review_status == ReviewStatus::Accepted)
+                .filter_map(|mapping| {
+                    mapping.platform_id.map(|id| KnownMapping {
+                        platform: mapping.platform,
+                        id,
+                    })
+                })
+                .collect(),
+            external_ids: external_ids
+                .into_iter()
+                .map(|external_id| (external_id.source, external_id.external_id))
+                .collect(),
+            is_movie: anime.is_some_and(|anime| anime.media_type == MediaType::Movie),
+        })
+    }
+
     pub fn conn(&self) -> &DatabaseConnection {
         &self.db
     }
@@ -70,16 +171,79 @@ impl DB {
         platform: Platform,
         status: ReviewStatus,
     ) -> Result<()> {
+        let txn = self.db.begin().await?;
+
+        // 被拒绝的条目作为反例保留，重新匹配时排除
+        let platform_id = AnimeMappingEntity::find()
+            .filter(AnimeMappingColumn::AnilistId.eq(anilist_id))
+            .filter(AnimeMappingColumn::Platform.eq(platform.clone()))
+            .one(&txn)
+            .await?
+            .and_then(|mapping| mapping.platform_id);
+        if let Some(platform_id) = platform_id {
+            match status {
+                ReviewStatus::Rejected => {
+                    RejectedMappingEntity::insert(RejectedMappingActiveModel {
+                        anilist_id: Set(anilist_id),
+                        platform: Set(platform.clone()),
+                        platform_id: Set(platform_id),
+                        rejected_at: Set(Utc::now()),
+                    })
+                    .on_conflict(
+                        OnConflict::columns([
+                            RejectedMappingColumn::AnilistId,
+                            RejectedMappingColumn::Platform,
+                            RejectedMappingColumn::PlatformId,
+                        ])
+                        .update_column(RejectedMappingColumn::RejectedAt)
+                        .to_owned(),
+                    )
+                    .exec(&txn)
+                    .await?;
+                }
+                // 改为接受时撤销之前的拒绝
+                ReviewStatus::Accepted => {
+                    RejectedMappingEntity::delete_many()
+                        .filter(RejectedMappingColumn::AnilistId.eq(anilist_id))
+                        .filter(RejectedMappingColumn::Platform.eq(platform.clone()))
+                        .filter(RejectedMappingColumn::PlatformId.eq(platform_id))
+                        .exec(&txn)
+                        .await?;
+                }
+                _ => {}
+            }
+        }
+
         AnimeMappingEntity::update_many()
             .filter(AnimeMappingColumn::AnilistId.eq(anilist_id))
             .filter(AnimeMappingColumn::Platform.eq(platform))
             .col_expr(AnimeMappingColumn::ReviewStatus, status.into())
             .col_expr(AnimeMappingColumn::UpdatedAt, Utc::now().into())
-            .exec(self.conn())
+            .exec(&txn)
             .await?;
+
+        txn.commit().await?;
         Ok(())
     }
 
+    /// 获取动画在指定平台上被拒绝过的条目id，按拒绝时间排序
+    pub async fn get_rejected_ids(
+        &self,
+        anilist_id: i32,
+        platform: Platform,
+    ) -> Result<Vec<String>> {
+        let ids = RejectedMappingEntity::find()
+            .select_only()
+            .column(RejectedMappingColumn::PlatformId)
+            .filter(RejectedMappingColumn::AnilistId.eq(anilist_id))
+            .filter(RejectedMappingColumn::Platform.eq(platform))
+            .order_by(RejectedMappingColumn::RejectedAt, Order::Asc)
+            .into_tuple()
+            .all(self.conn())
+            .await?;
+        Ok(ids)
+    }
+
     pub async fn update_anime_mapping(
         &self,
         anilist_id: i32,
@@ -99,6 +263,26 @@ impl DB {
         Ok(())
     }
 
+    /// 多模型结果不一致，清空匹配结果等待人工裁决
+    pub async fn mark_conflict(&self, anilist_id: i32, platform: Platform) -> Result<()> {
+        AnimeMappingEntity::update_many()
+            .filter(AnimeMappingColumn::AnilistId.eq(anilist_id))
+            .filter(AnimeMappingColumn::Platform.eq(platform))
+            .col_expr(
+                AnimeMappingColumn::ReviewStatus,
+                ReviewStatus::Conflict.into(),
+            )
+            .col_expr(AnimeMappingColumn::UpdatedAt, Utc::now().into())
+            .col_expr(
+                AnimeMappingColumn::PlatformId,
+                Option::<String>::None.into(),
+            )
+            .col_expr(AnimeMappingColumn::Score, 0u8.into())
+            .exec(self.conn())
+            .await?;
+        Ok(())
+    }
+
     pub async fn update_season_number(&self, anilist_id: i32, season_number: i32) -> Result<()> {
         AnimeEntity::update_many()
             .filter(AnimeColumn::AnilistId.eq(anilist_id))
@@ -229,13 +413,14 @@ impl DB {
         let total_animes = AnimeEntity::find().count(self.conn()).await? as usize;
 
         // 获取各平台不同状态的映射数量
-        // TMDB平台 - 已匹配（Ready + Accepted + Rejected）
+        // TMDB平台 - 已匹配（Ready + Accepted + Rejected + Conflict）
         let total_tmdb_matched = AnimeMappingEntity::find()
             .filter(AnimeMappingColumn::Platform.eq(Platform::Tmdb))
             .filter(AnimeMappingColumn::ReviewStatus.is_in([
                 ReviewStatus::Ready,
                 ReviewStatus::Accepted,
                 ReviewStatus::Rejected,
+                ReviewStatus::Conflict,
             ]))
             .count(self.conn())
             .await? as usize;
@@ -254,13 +439,14 @@ impl DB {
             .count(self.conn())
             .await? as usize;
 
-        // BgmTv平台 - 已匹配（Ready + Accepted + Rejected）
+        // BgmTv平台 - 已匹配（Ready + Accepted + Rejected + Conflict）
         let total_bgmtv_matched = AnimeMappingEntity::find()
             .filter(AnimeMappingColumn::Platform.eq(Platform::BgmTv))
             .filter(AnimeMappingColumn::ReviewStatus.is_in([
                 ReviewStatus::Ready,
                 ReviewStatus::Accepted,
                 ReviewStatus::Rejected,
+                ReviewStatus::Conflict,
             ]))
             .count(self.conn())
             .await? as usize;
@@ -374,9 +560,10 @@ impl DB {
             if let Some(stat) = year_stats.get_mut(&year) {
                 match status {
                     // 所有已匹配状态都计入matched字段
-                    ReviewStatus::Ready | ReviewStatus::Accepted | ReviewStatus::Rejected => {
-                        stat.tmdb_matched += count as usize
-                    }
+                    ReviewStatus::Ready
+                    | ReviewStatus::Accepted
+                    | ReviewStatus::Rejected
+                    | ReviewStatus::Conflict => stat.tmdb_matched += count as usize,
                     ReviewStatus::UnMatched => stat.tmdb_unmatched = count as usize,
                     ReviewStatus::Dropped => stat.tmdb_dropped = count as usize,
                 }
@@ -409,9 +596,10 @@ impl DB {
             if let Some(stat) = year_stats.get_mut(&year) {
                 match status {
                     // 所有已匹配状态都计入matched字段
-                    ReviewStatus::Ready | ReviewStatus::Accepted | ReviewStatus::Rejected => {
-                        stat.bgmtv_matched += count as usize
-                    }
+                    ReviewStatus::Ready
+                    | ReviewStatus::Accepted
+                    | ReviewStatus::Rejected
+                    | ReviewStatus::Conflict => stat.bgmtv_matched += count as usize,
                     ReviewStatus::UnMatched => stat.bgmtv_unmatched = count as usize,
                     ReviewStatus::Dropped => stat.bgmtv_dropped = count as usize,
                 }
@@ -424,6 +612,647 @@ impl DB {
 
         Ok(YearStatistics { statistics })
     }
+
+    /// 保存一次匹配任务中每次尝试的完整记录
+    pub async fn save_transcripts(
+        &self,
+        anilist_id: i32,
+        transcripts: &[MatchTranscript],
+    ) -> Result<()> {
+        if transcripts.is_empty() {
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let mut models = Vec::with_capacity(transcripts.len());
+        for transcript in transcripts {
+            models.push(TranscriptActiveModel {
+                anilist_id: Set(anilist_id),
+                platform: Set(transcript.platform.clone()),
+                provider: Set(transcript.provider.clone()),
+                model: Set(transcript.model.clone()),
+                prompt_version: Set(transcript.prompt_version.clone()),
+                prompt: Set(transcript.prompt.clone()),
+                tool_calls: Set(serde_json::to_string(&transcript.tool_calls)?),
+                output: Set(transcript.output.clone()),
+                result: Set(transcript
+                    .result
+                    .as_ref()
+                    .map(serde_json::to_string)
+                    .transpose()?),
+                error: Set(transcript.error.clone()),
+                prompt_tokens: Set(transcript.usage.prompt_tokens as i64),
+                completion_tokens: Set(transcript.usage.completion_tokens as i64),
+                cost: Set(transcript.cost),
+                duration_ms: Set(transcript.duration_ms as i64),
+                created_at: Set(now),
+                ..Default::default()
+            });
+        }
+
+        TranscriptEntity::insert_many(models)
+            .exec(self.conn())
+            .await?;
+
+        Ok(())
+    }
+
+    /// 查询动画在指定平台上的匹配记录，按时间倒序
+    pub async fn get_transcripts(
+        &self,
+        anilist_id: i32,
+        platform: Platform,
+    ) -> Result<Vec<Transcript>> {
+        let transcripts = TranscriptEntity::find()
+            .filter(TranscriptColumn::AnilistId.eq(anilist_id))
+            .filter(TranscriptColumn::Platform.eq(platform))
+            .order_by(TranscriptColumn::CreatedAt, Order::Desc)
+            .order_by(TranscriptColumn::Id, Order::Desc)
+            .all(self.conn())
+            .await?;
+        Ok(transcripts)
+    }
+
+    /// 按模型汇总所有匹配记录的 token 用量与费用
+    pub async fn get_usage_statistics(&self) -> Result<UsageStatistics> {
+        let rows: Vec<(String, String, i64, Option<i64>, Option<i64>, Option<f64>)> =
+            TranscriptEntity::find()
+                .filter(TranscriptColumn::Provider.ne(PREMATCH_PROVIDER))
+                .select_only()
+                .column(TranscriptColumn::Provider)
+                .column(TranscriptColumn::Model)
+                .column_as(TranscriptColumn::Id.count(), "num_attempts")
+                .column_as(TranscriptColumn::PromptTokens.sum(), "prompt_tokens")
+                .column_as(
+                    TranscriptColumn::CompletionTokens.sum(),
+                    "completion_tokens",
+                )
+                .column_as(TranscriptColumn::Cost.sum(), "cost")
+                .group_by(TranscriptColumn::Provider)
+                .group_by(TranscriptColumn::Model)
+                .into_tuple()
+                .all(self.conn())
+                .await?;
+
+        let mut models: Vec<UsageStatistic> = rows
+            .into_iter()
+            .map(
+                |(provider, model, num_attempts, prompt_tokens, completion_tokens, cost)| {
+                    UsageStatistic {
+                        provider,
+                        model,
+                        num_attempts: num_attempts as usize,
+                        prompt_tokens: prompt_tokens.unwrap_or_default() as u64,
+                        completion_tokens: completion_tokens.unwrap_or_default() as u64,
+                        cost,
+                    }
+                },
+            )
+            .collect();
+        // 费用高的排在前面
+        models.sort_by(|a, b| {
+            b.cost
+                .unwrap_or_default()
+                .total_cmp(&a.cost.unwrap_or_default())
+        });
+
+        Ok(UsageStatistics {
+            prompt_tokens: models.iter().map(|m| m.prompt_tokens).sum(),
+            completion_tokens: models.iter().map(|m| m.completion_tokens).sum(),
+            cost: models.iter().filter_map(|m| m.cost).sum(),
+            models,
+        })
+    }
+
+    /// 替换动画在指定平台上的分集映射
+    pub async fn save_episode_mappings(
+        &self,
+        anilist_id: i32,
+        platform: Platform,
+        episodes: &[EpisodeMapping],
+    ) -> Result<()> {
+        let now = Utc::now();
+        let txn = self.db.begin().await?;
+
+        EpisodeMappingEntity::delete_many()
+            .filter(EpisodeMappingColumn::AnilistId.eq(anilist_id))
+            .filter(EpisodeMappingColumn::Platform.eq(platform.clone()))
+            .exec(&txn)
+            .await?;
+
+        if !episodes.is_empty() {
+            let models = episodes.iter().map(|episode| EpisodeMappingActiveModel {
+                anilist_id: Set(anilist_id),
+                platform: Set(platform.clone()),
+                episode: Set(episode.episode),
+                season: Set(episode.season),
+                platform_episode: Set(episode.platform_episode),
+                platform_episode_id: Set(episode.platform_episode_id.clone()),
+                air_date: Set(episode.air_date.clone()),
+                created_at: Set(now),
+            });
+            EpisodeMappingEntity::insert_many(models).exec(&txn).await?;
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_episode_mappings(
+        &self,
+        anilist_id: i32,
+        platform: Platform,
+    ) -> Result<Vec<EpisodeMappingModel>> {
+        let episodes = EpisodeMappingEntity::find()
+            .filter(EpisodeMappingColumn::AnilistId.eq(anilist_id))
+            .filter(EpisodeMappingColumn::Platform.eq(platform))
+            .order_by(EpisodeMappingColumn::Episode, Order::Asc)
+            .all(self.conn())
+            .await?;
+        Ok(episodes)
+    }
+
+    /// 按筛选条件选择任务要处理的动画，只包含在目标平台上有映射的动画
+    pub async fn select_job_animes(
+        &self,
+        platform: Platform,
+        filter: &JobFilter,
+    ) -> Result<Vec<Anime>> {
+        let mut query = AnimeEntity::find()
+            .join(JoinType::InnerJoin, AnimeRelation::AnimeMapping.def())
+            .filter(AnimeMappingColumn::Platform.eq(platform));
+
+        if let Some(year_from) = filter.year_from {
+            query = query.filter(AnimeColumn::Year.gte(year_from));
+        }
+        if let Some(year_to) = filter.year_to {
+            query = query.filter(AnimeColumn::Year.lte(year_to));
+        }
+        if let Some(season) = &filter.season {
+            query = query.filter(AnimeColumn::Season.eq(season.to_uppercase()));
+        }
+        if let Some(media_type) = &filter.media_type {
+            query = query.filter(AnimeColumn::MediaType.eq(media_type.clone()));
+        }
+        if !filter.review_status.is_empty() {
+            query =
+                query.filter(AnimeMappingColumn::ReviewStatus.is_in(filter.review_status.clone()));
+        }
+        if let Some(score) = filter.score_below {
+            query = query.filter(AnimeMappingColumn::Score.lt(score));
+        }
+        if !filter.anilist_ids.is_empty() {
+            query = query.filter(AnimeColumn::AnilistId.is_in(filter.anilist_ids.clone()));
+        }
+
+        let animes = query
+            .order_by(AnimeColumn::AnilistId, Order::Asc)
+            .all(self.conn())
+            .await?;
+        Ok(animes)
+    }
+
+    /// 保存新任务及其待处理的动画，返回任务id
+    pub async fn create_job(&self, job: Job, anilist_ids: &[i32]) -> Result<i32> {
+        let txn = self.db.begin().await?;
+
+        let mut job = job.into_active_model().reset_all();
+        job.id = NotSet;
+        let job_id = JobEntity::insert(job).exec(&txn).await?.last_insert_id;
+
+        for (chunk_index, chunk) in anilist_ids.chunks(500).enumerate() {
+            let items = chunk
+                .iter()
+                .enumerate()
+                .map(|(index, anilist_id)| JobItemActiveModel {
+                    job_id: Set(job_id),
+                    position: Set((chunk_index * 500 + index) as i32),
+                    anilist_id: Set(*anilist_id),
+                    outcome: Set(None),
+                    platform_id: Set(None),
+                    score: Set(None),
+                    error: Set(None),
+                    processed_at: Set(None),
+                });
+            JobItemEntity::insert_many(items).exec(&txn).await?;
+        }
+
+        txn.commit().await?;
+        Ok(job_id)
+    }
+
+    /// 更新任务的状态、进度和计数
+    pub async fn update_job(&self, job: Job) -> Result<()> {
+        let mut job = job.into_active_model().reset_all();
+        job.created_at = NotSet;
+        job.updated_at = Set(Utc::now());
+        job.update(self.conn()).await?;
+        Ok(())
+    }
+
+    /// 将所有运行中的任务标记为暂停，返回被中断的任务id
+    pub async fn pause_interrupted_jobs(&self) -> Result<Vec<i32>> {
+        let ids: Vec<i32> = JobEntity::find()
+            .select_only()
+            .column(JobColumn::Id)
+            .filter(JobColumn::Status.eq(JobStatus::Running))
+            .into_tuple()
+            .all(self.conn())
+            .await?;
+        if !ids.is_empty() {
+            JobEntity::update_many()
+                .col_expr(JobColumn::Status, Expr::value(JobStatus::Paused))
+                .col_expr(JobColumn::UpdatedAt, Expr::value(Utc::now()))
+                .filter(JobColumn::Id.is_in(ids.clone()))
+                .exec(self.conn())
+                .await?;
+        }
+        Ok(ids)
+    }
+
+    pub async fn get_jobs(&self) -> Result<Vec<Job>> {
+        let jobs = JobEntity::find()
+            .order_by_asc(JobColumn::Id)
+            .all(self.conn())
+            .await?;
+        Ok(jobs)
+    }
+
+    /// 按队列顺序获取任务中的所有条目及对应的动画
+    pub async fn get_job_queue(&self, job_id: i32) -> Result<Vec<(JobItem, Anime)>> {
+        let items = JobItemEntity::find()
+            .filter(JobItemColumn::JobId.eq(job_id))
+            .order_by_asc(JobItemColumn::Position)
+            .find_also_related(AnimeEntity)
+            .all(self.conn())
+            .await?;
+        Ok(items
+            .into_iter()
+            .filter_map(|(item, anime)| anime.map(|anime| (item, anime)))
+            .collect())
+    }
+
+    /// 记录单个动画的处理结果
+    pub async fn save_job_item(&self, item: JobItem) -> Result<()> {
+        item.into_active_model()
+            .reset_all()
+            .update(self.conn())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_job(&self, job_id: i32) -> Result<bool> {
+        let result = JobEntity::delete_by_id(job_id).exec(self.conn()).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// 返回已存在的动画id
+    pub async fn get_existing_anilist_ids(&self, anilist_ids: &[i32]) -> Result<HashSet<i32>> {
+        let mut existing = HashSet::new();
+        for chunk in anilist_ids.chunks(500) {
+            let ids: Vec<i32> = AnimeEntity::find()
+                .select_only()
+                .column(AnimeColumn::AnilistId)
+                .filter(AnimeColumn::AnilistId.is_in(chunk.to_vec()))
+                .into_tuple()
+                .all(self.conn())
+                .await?;
+            existing.extend(ids);
+        }
+        Ok(existing)
+    }
+
+    /// 保存新的定时计划，返回计划id
+    pub async fn create_schedule(&self, schedule: Schedule) -> Result<i32> {
+        let mut schedule = schedule.into_active_model().reset_all();
+        schedule.id = NotSet;
+        let result = ScheduleEntity::insert(schedule).exec(self.conn()).await?;
+        Ok(result.last_insert_id)
+    }
+
+    pub async fn update_schedule(&self, schedule: Schedule) -> Result<()> {
+        let mut schedule = schedule.into_active_model().reset_all();
+        schedule.created_at = NotSet;
+        schedule.updated_at = Set(Utc::now());
+        schedule.update(self.conn()).await?;
+        Ok(())
+    }
+
+    /// 只更新下一次执行时间，不覆盖同时修改的计划配置
+    pub async fn set_schedule_next_run(
+        &self,
+        id: i32,
+        next_run_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        ScheduleEntity::update_many()
+            .col_expr(ScheduleColumn::NextRunAt, Expr::value(next_run_at))
+            .filter(ScheduleColumn::Id.eq(id))
+            .exec(self.conn())
+            .await?;
+        Ok(())
+    }
+
+    /// 只更新上一次执行时间，不覆盖同时修改的计划配置
+    pub async fn set_schedule_last_run(&self, id: i32, last_run_at: DateTime<Utc>) -> Result<()> {
+        ScheduleEntity::update_many()
+            .col_expr(ScheduleColumn::LastRunAt, Expr::value(last_run_at))
+            .filter(ScheduleColumn::Id.eq(id))
+            .exec(self.conn())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_schedules(&self) -> Result<Vec<Schedule>> {
+        let schedules = ScheduleEntity::find()
+            .order_by_asc(ScheduleColumn::Id)
+            .all(self.conn())
+            .await?;
+        Ok(schedules)
+    }
+
+    pub async fn get_schedule(&self, id: i32) -> Result<Option<Schedule>> {
+        let schedule = ScheduleEntity::find_by_id(id).one(self.conn()).await?;
+        Ok(schedule)
+    }
+
+    pub async fn delete_schedule(&self, id: i32) -> Result<bool> {
+        let result = ScheduleEntity::delete_by_id(id).exec(self.conn()).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// 记录计划开始执行
+    pub async fn create_schedule_run(&self, schedule_id: i32) -> Result<ScheduleRun> {
+        let run = ScheduleRunActiveModel {
+            id: NotSet,
+            schedule_id: Set(schedule_id),
+            status: Set(ScheduleRunStatus::Running),
+            num_fetched: Set(0),
+            num_added: Set(0),
+            job_ids: Set("[]".to_string()),
+            exported: Set(false),
+            error: Set(None),
+            started_at: Set(Utc::now()),
+            finished_at: Set(None),
+        };
+        Ok(run.insert(self.conn()).await?)
+    }
+
+    pub async fn update_schedule_run(&self, run: ScheduleRun) -> Result<()> {
+        run.into_active_model()
+            .reset_all()
+            .update(self.conn())
+            .await?;
+        Ok(())
+    }
+
+    /// 按时间倒序获取计划的执行记录
+    pub async fn get_schedule_runs(
+        &self,
+        schedule_id: i32,
+        limit: u64,
+    ) -> Result<Vec<ScheduleRun>> {
+        let runs = ScheduleRunEntity::find()
+            .filter(ScheduleRunColumn::ScheduleId.eq(schedule_id))
+            .order_by_desc(ScheduleRunColumn::Id)
+            .limit(limit)
+            .all(self.conn())
+            .await?;
+        Ok(runs)
+    }
+
+    /// 服务重启前未结束的执行记录标记为失败，返回记录数
+    pub async fn fail_interrupted_schedule_runs(&self) -> Result<u64> {
+        let result = ScheduleRunEntity::update_many()
+            .col_expr(
+                ScheduleRunColumn::Status,
+                Expr::value(ScheduleRunStatus::Failed),
+            )
+            .col_expr(
+                ScheduleRunColumn::Error,
+                Expr::value("服务重启，执行被中断"),
+            )
+            .col_expr(ScheduleRunColumn::FinishedAt, Expr::value(Utc::now()))
+            .filter(ScheduleRunColumn::Status.eq(ScheduleRunStatus::Running))
+            .exec(self.conn())
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    pub async fn get_response_cache(
+        &self,
+        endpoint: &str,
+        params: &str,
+    ) -> Result<Option<ResponseCacheEntry>> {
+        let entry = ResponseCacheEntity::find_by_id((endpoint.to_string(), params.to_string()))
+            .one(self.conn())
+            .await?;
+        Ok(entry)
+    }
+
+    pub async fn put_response_cache(
+        &self,
+        endpoint: &str,
+        params: &str,
+        body: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let entry = ResponseCacheActiveModel {
+            endpoint: Set(endpoint.to_string()),
+            params: Set(params.to_string()),
+            body: Set(body.to_string()),
+            expires_at: Set(expires_at),
+            created_at: Set(Utc::now()),
+        };
+        ResponseCacheEntity::insert(entry)
+            .on_conflict(
+                OnConflict::columns([ResponseCacheColumn::Endpoint, ResponseCacheColumn::Params])
+                    .update_columns([
+                        ResponseCacheColumn::Body,
+                        ResponseCacheColumn::ExpiresAt,
+                        ResponseCacheColumn::CreatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(self.conn())
+            .await?;
+        Ok(())
+    }
+
+    /// 每个接口的缓存条目数和过期条目数
+    pub async fn get_response_cache_counts(&self) -> Result<Vec<(String, u64, u64)>> {
+        let entries: Vec<(String, i64)> = ResponseCacheEntity::find()
+            .select_only()
+            .column(ResponseCacheColumn::Endpoint)
+            .column_as(ResponseCacheColumn::Endpoint.count(), "entries")
+            .group_by(ResponseCacheColumn::Endpoint)
+            .into_tuple()
+            .all(self.conn())
+            .await?;
+        let expired: HashMap<String, i64> = ResponseCacheEntity::find()
+            .select_only()
+            .column(ResponseCacheColumn::Endpoint)
+            .column_as(ResponseCacheColumn::Endpoint.count(), "expired")
+            .filter(ResponseCacheColumn::ExpiresAt.lte(Utc::now()))
+            .group_by(ResponseCacheColumn::Endpoint)
+            .into_tuple::<(String, i64)>()
+            .all(self.conn())
+            .await?
+            .into_iter()
+            .collect();
+
+        Ok(entries
+            .into_iter()
+            .map(|(endpoint, entries)| {
+                let expired = expired.get(&endpoint).copied().unwrap_or_default();
+                (endpoint, entries as u64, expired as u64)
+            })
+            .collect())
+    }
+
+    fn response_cache_condition(filter: &CacheFilter) -> Condition {
+        let mut condition = Condition::all();
+        if let Some(endpoint) = &filter.endpoint {
+            condition = condition.add(
+                Condition::any()
+                    .add(ResponseCacheColumn::Endpoint.eq(endpoint.as_str()))
+                    .add(ResponseCacheColumn::Endpoint.starts_with(format!("{}/", endpoint))),
+            );
+        }
+        if let Some(keyword) = &filter.keyword {
+            condition = condition.add(ResponseCacheColumn::Params.contains(keyword.as_str()));
+        }
+        if filter.expired_only {
+            condition = condition.add(ResponseCacheColumn::ExpiresAt.lte(Utc::now()));
+        }
+        condition
+    }
+
+    /// 删除符合条件的缓存条目，返回删除的条目数
+    pub async fn purge_response_cache(&self, filter: &CacheFilter) -> Result<u64> {
+        let result = ResponseCacheEntity::delete_many()
+            .filter(Self::response_cache_condition(filter))
+            .exec(self.conn())
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// 将符合条件的缓存条目标记为过期，下次请求时重新获取，获取失败时仍可使用旧数据
+    pub async fn expire_response_cache(&self, filter: &CacheFilter) -> Result<u64> {
+        let now = Utc::now();
+        let result = ResponseCacheEntity::update_many()
+            .col_expr(ResponseCacheColumn::ExpiresAt, Expr::value(now))
+            .filter(Self::response_cache_condition(filter))
+            .filter(ResponseCacheColumn::ExpiresAt.gt(now))
+            .exec(self.conn())
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// 替换动画在指定平台上的候选列表
+    pub async fn save_candidates(
+        &self,
+        anilist_id: i32,
+        platform: Platform,
+        candidates: &[MatchCandidate],
+    ) -> Result<()> {
+        let now = Utc::now();
+        let txn = self.db.begin().await?;
+
+        CandidateEntity::delete_many()
+            .filter(CandidateColumn::AnilistId.eq(anilist_id))
+            .filter(CandidateColumn::Platform.eq(platform.clone()))
+            .exec(&txn)
+            .await?;
+
+        if !candidates.is_empty() {
+            let models =
+                candidates
+                    .iter()
+                    .enumerate()
+                    .map(|(rank, candidate)| CandidateActiveModel {
+                        anilist_id: Set(anilist_id),
+                        platform: Set(platform.clone()),
+                        rank: Set(rank as i32),
+                        platform_id: Set(candidate.id.to_string()),
+                        name: Set(candidate.name.clone()),
+                        season: Set(candidate.season),
+                        score: Set(
+                            candidate.confidence_score.unwrap_or_default().clamp(0, 100) as u8
+                        ),
+                        reason: Set(candidate.reason.clone()),
+                        source: Set(candidate.source.clone()),
+                        created_at: Set(now),
+                    });
+            CandidateEntity::insert_many(models).exec(&txn).await?;
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_candidates(
+        &self,
+        anilist_id: i32,
+        platform: Platform,
+    ) -> Result<Vec<Candidate>> {
+        let candidates = CandidateEntity::find()
+            .filter(CandidateColumn::AnilistId.eq(anilist_id))
+            .filter(CandidateColumn::Platform.eq(platform))
+            .order_by(CandidateColumn::Rank, Order::Asc)
+            .all(self.conn())
+            .await?;
+        Ok(candidates)
+    }
+
+    /// 审核时直接采用某个候选，映射状态设为已接受
+    pub async fn accept_candidate(
+        &self,
+        anilist_id: i32,
+        platform: Platform,
+        rank: i32,
+    ) -> Result<Candidate> {
+        let candidate = CandidateEntity::find_by_id((anilist_id, platform.clone(), rank))
+            .one(self.conn())
+            .await?
+            .ok_or_else(|| anyhow!("候选不存在: anilist_id={}, rank={}", anilist_id, rank))?;
+
+        let txn = self.db.begin().await?;
+        AnimeMappingEntity::update_many()
+            .filter(AnimeMappingColumn::AnilistId.eq(anilist_id))
+            .filter(AnimeMappingColumn::Platform.eq(platform.clone()))
+            .col_expr(
+                AnimeMappingColumn::ReviewStatus,
+                ReviewStatus::Accepted.into(),
+            )
+            .col_expr(AnimeMappingColumn::UpdatedAt, Utc::now().into())
+            .col_expr(
+                AnimeMappingColumn::PlatformId,
+                candidate.platform_id.clone().into(),
+            )
+            .col_expr(AnimeMappingColumn::Score, candidate.score.into())
+            .exec(&txn)
+            .await?;
+
+        // 与审核时接受一致，撤销之前对该条目的拒绝
+        RejectedMappingEntity::delete_many()
+            .filter(RejectedMappingColumn::AnilistId.eq(anilist_id))
+            .filter(RejectedMappingColumn::Platform.eq(platform))
+            .filter(RejectedMappingColumn::PlatformId.eq(candidate.platform_id.clone()))
+            .exec(&txn)
+            .await?;
+
+        if let Some(season) = candidate.season.filter(|season| *season > 0) {
+            AnimeEntity::update_many()
+                .filter(AnimeColumn::AnilistId.eq(anilist_id))
+                .col_expr(AnimeColumn::SeasonNumber, season.into())
+                .col_expr(AnimeColumn::UpdatedAt, Utc::now().into())
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        Ok(candidate)
+    }
 }
 
 #[cfg(test)]
@@ -475,4 +1304,621 @@ mod tests {
         assert_eq!(mappings.len(), 1);
         assert_eq!(mappings[0].review_status, ReviewStatus::Ready);
     }
+
+    #[tokio::test]
+    async fn test_get_known_ids() {
+        use crate::models::external_ids::Model as ExternalId;
+
+        let db = DB::new_for_test().await.unwrap();
+        let anime = Anime {
+            media_type: MediaType::Movie,
+            ..Anime::test(1)
+        };
+        let mapping = |platform, platform_id: Option<&str>, review_status| AnimeMapping {
+            platform_id: platform_id.map(|id| id.to_string()),
+            review_status,
+            ..AnimeMapping::test(1, platform)
+        };
+        db.batch_add_animes((
+            vec![anime],
+            vec![
+                mapping(Platform::BgmTv, Some("1958"), ReviewStatus::Ready),
+                mapping(Platform::Tmdb, Some("9320"), ReviewStatus::Accepted),
+            ],
+        ))
+        .await
+        .unwrap();
+
+        let external_id = |source: &str, external_id: &str| ExternalId {
+            anilist_id: 1,
+            source: source.to_string(),
+            external_id: external_id.to_string(),
+            created_at: Utc::now(),
+        };
+        db.save_external_ids(vec![external_id("mal", "1"), external_id("anidb", "2")])
+            .await
+            .unwrap();
+        // 重复导入时覆盖原值
+        db.save_external_ids(vec![external_id("mal", "1395")])
+            .await
+            .unwrap();
+
+        let known_ids = db.get_known_ids(1).await.unwrap();
+        assert!(known_ids.is_movie);
+        assert_eq!(known_ids.mappings.len(), 1);
+        assert_eq!(known_ids.mapping(&Platform::Tmdb), Some("9320"));
+        assert_eq!(known_ids.mapping(&Platform::BgmTv), None);
+        assert_eq!(known_ids.external_ids.len(), 2);
+        assert_eq!(known_ids.external_ids["mal"], "1395");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_ids() {
+        let db = DB::new_for_test().await.unwrap();
+        let anime = Anime::test(1);
+        let mapping = AnimeMapping {
+            platform_id: Some("100".to_string()),
+            review_status: ReviewStatus::Ready,
+            score: 80,
+            ..AnimeMapping::test(1, Platform::BgmTv)
+        };
+        db.batch_add_animes((vec![anime], vec![mapping]))
+            .await
+            .unwrap();
+
+        db.review(1, Platform::BgmTv, ReviewStatus::Rejected)
+            .await
+            .unwrap();
+        // 重复拒绝同一条目只记录一次
+        db.review(1, Platform::BgmTv, ReviewStatus::Rejected)
+            .await
+            .unwrap();
+        db.update_anime_mapping(1, Platform::BgmTv, "200".to_string(), 70)
+            .await
+            .unwrap();
+        db.review(1, Platform::BgmTv, ReviewStatus::Rejected)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_rejected_ids(1, Platform::BgmTv).await.unwrap(),
+            vec!["100".to_string(), "200".to_string()]
+        );
+        assert!(
+            db.get_rejected_ids(1, Platform::Tmdb)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // 改为接受后不再排除
+        db.review(1, Platform::BgmTv, ReviewStatus::Accepted)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_rejected_ids(1, Platform::BgmTv).await.unwrap(),
+            vec!["100".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_episode_mappings() {
+        let db = DB::new_for_test().await.unwrap();
+        let anime = Anime {
+            episode_count: Some(2),
+            ..Anime::test(1)
+        };
+        db.batch_add_animes((vec![anime], vec![])).await.unwrap();
+
+        let episode = |episode: i32, platform_episode: i32| EpisodeMapping {
+            episode,
+            season: Some(2),
+            platform_episode,
+            platform_episode_id: None,
+            air_date: None,
+        };
+        db.save_episode_mappings(1, Platform::Tmdb, &[episode(1, 13), episode(2, 14)])
+            .await
+            .unwrap();
+        // 重新生成时替换原有的映射
+        db.save_episode_mappings(1, Platform::Tmdb, &[episode(2, 2), episode(1, 1)])
+            .await
+            .unwrap();
+
+        let episodes = db.get_episode_mappings(1, Platform::Tmdb).await.unwrap();
+        assert_eq!(episodes.len(), 2);
+        assert_eq!(episodes[0].episode, 1);
+        assert_eq!(episodes[0].platform_episode, 1);
+        assert_eq!(episodes[0].season, Some(2));
+        assert!(
+            db.get_episode_mappings(1, Platform::BgmTv)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_transcripts() {
+        use crate::agent::budget::TokenUsage;
+        use crate::agent::transcript::ToolCallRecord;
+
+        let db = DB::new_for_test().await.unwrap();
+        let anime = Anime::test(1);
+        db.batch_add_animes((vec![anime], vec![])).await.unwrap();
+
+        let transcript = MatchTranscript {
+            platform: Platform::BgmTv,
+            provider: "openai".to_string(),
+            model: "gpt-4o".to_string(),
+            prompt_version: "v1".to_string(),
+            prompt: "test".to_string(),
+            tool_calls: vec![ToolCallRecord {
+                name: "bgm_tv_search".to_string(),
+                arguments: serde_json::json!({"keyword": "test"}),
+                result: Some("[]".to_string()),
+                error: None,
+            }],
+            output: Some("没有找到".to_string()),
+            result: None,
+            error: Some("没有找到".to_string()),
+            usage: TokenUsage {
+                prompt_tokens: 100,
+                completion_tokens: 10,
+            },
+            cost: Some(0.01),
+            duration_ms: 1000,
+        };
+        db.save_transcripts(1, &[transcript]).await.unwrap();
+
+        let transcripts = db.get_transcripts(1, Platform::BgmTv).await.unwrap();
+        assert_eq!(transcripts.len(), 1);
+        assert_eq!(transcripts[0].prompt_tokens, 100);
+        assert!(transcripts[0].tool_calls.contains("bgm_tv_search"));
+        assert!(
+            db.get_transcripts(1, Platform::Tmdb)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_usage_statistics() {
+        use crate::agent::agent::MatchResult;
+        use crate::agent::budget::TokenUsage;
+
+        let db = DB::new_for_test().await.unwrap();
+        let anime = Anime::test(1);
+        db.batch_add_animes((vec![anime], vec![])).await.unwrap();
+
+        let transcript =
+            |provider: &str, model: &str, usage: TokenUsage, cost: Option<f64>| MatchTranscript {
+                platform: Platform::BgmTv,
+                provider: provider.to_string(),
+                model: model.to_string(),
+                prompt_version: "v1".to_string(),
+                prompt: "test".to_string(),
+                tool_calls: vec![],
+                output: None,
+                result: None,
+                error: None,
+                usage,
+                cost,
+                duration_ms: 1000,
+            };
+        let prematch = MatchResult {
+            id: Some(1),
+            name: None,
+            season: None,
+            confidence_score: Some(95),
+            candidates: vec![],
+        };
+        let transcripts = vec![
+            transcript("openai", "gpt-4o", TokenUsage::new(100, 10), Some(0.01)),
+            transcript("openai", "gpt-4o", TokenUsage::new(200, 20), Some(0.02)),
+            transcript("deepseek", "deepseek-chat", TokenUsage::new(50, 5), None),
+            MatchTranscript::prematch(Platform::BgmTv, "test", &prematch, 10),
+        ];
+        db.save_transcripts(1, &transcripts).await.unwrap();
+
+        let usage = db.get_usage_statistics().await.unwrap();
+        // 预匹配没有调用模型，不计入统计
+        assert_eq!(usage.models.len(), 2);
+        assert_eq!(usage.models[0].provider, "openai");
+        assert_eq!(usage.models[0].num_attempts, 2);
+        assert_eq!(usage.models[0].prompt_tokens, 300);
+        assert_eq!(usage.models[0].completion_tokens, 30);
+        assert_eq!(usage.models[1].cost, None);
+        assert_eq!(usage.prompt_tokens, 350);
+        assert_eq!(usage.completion_tokens, 35);
+        assert!((usage.cost - 0.03).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_accept_candidate() {
+        let db = DB::new_for_test().await.unwrap();
+        let anime = Anime::test(1);
+        let mapping = AnimeMapping {
+            platform_id: Some("100".to_string()),
+            review_status: ReviewStatus::Ready,
+            score: 80,
+            ..AnimeMapping::test(1, Platform::Tmdb)
+        };
+        db.batch_add_animes((vec![anime], vec![mapping]))
+            .await
+            .unwrap();
+
+        let candidates = vec![
+            MatchCandidate {
+                id: 100,
+                name: Some("first".to_string()),
+                season: Some(1),
+                confidence_score: Some(80),
+                reason: None,
+                source: None,
+            },
+            MatchCandidate {
+                id: 200,
+                name: Some("second".to_string()),
+                season: Some(2),
+                confidence_score: Some(70),
+                reason: Some("第二季".to_string()),
+                source: Some("openai/gpt-4o".to_string()),
+            },
+        ];
+        db.save_candidates(1, Platform::Tmdb, &candidates)
+            .await
+            .unwrap();
+        // 重复保存会覆盖旧的候选
+        db.save_candidates(1, Platform::Tmdb, &candidates)
+            .await
+            .unwrap();
+        assert_eq!(db.get_candidates(1, Platform::Tmdb).await.unwrap().len(), 2);
+
+        let candidate = db.accept_candidate(1, Platform::Tmdb, 1).await.unwrap();
+        assert_eq!(candidate.platform_id, "200");
+        assert_eq!(candidate.source.as_deref(), Some("openai/gpt-4o"));
+
+        let (anime, mappings) = db.get_anime(1).await.unwrap();
+        assert_eq!(anime.unwrap().season_number, Some(2));
+        assert_eq!(mappings[0].platform_id.as_deref(), Some("200"));
+        assert_eq!(mappings[0].review_status, ReviewStatus::Accepted);
+        assert_eq!(mappings[0].score, 70);
+
+        assert!(db.accept_candidate(1, Platform::Tmdb, 5).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_accept_rejected_candidate() {
+        let db = DB::new_for_test().await.unwrap();
+        let mapping = AnimeMapping {
+            platform_id: Some("100".to_string()),
+            review_status: ReviewStatus::Ready,
+            ..AnimeMapping::test(1, Platform::BgmTv)
+        };
+        db.batch_add_animes((vec![Anime::test(1)], vec![mapping]))
+            .await
+            .unwrap();
+
+        // 先后拒绝两个条目
+        db.review(1, Platform::BgmTv, ReviewStatus::Rejected)
+            .await
+            .unwrap();
+        db.update_anime_mapping(1, Platform::BgmTv, "200".to_string(), 60)
+            .await
+            .unwrap();
+        db.review(1, Platform::BgmTv, ReviewStatus::Rejected)
+            .await
+            .unwrap();
+        let mut rejected_ids = db.get_rejected_ids(1, Platform::BgmTv).await.unwrap();
+        rejected_ids.sort();
+        assert_eq!(rejected_ids, vec!["100", "200"]);
+
+        let candidates = vec![MatchCandidate {
+            id: 200,
+            name: Some("second".to_string()),
+            season: None,
+            confidence_score: Some(70),
+            reason: None,
+            source: None,
+        }];
+        db.save_candidates(1, Platform::BgmTv, &candidates)
+            .await
+            .unwrap();
+        db.accept_candidate(1, Platform::BgmTv, 0).await.unwrap();
+
+        // 只撤销被接受条目的拒绝
+        assert_eq!(
+            db.get_rejected_ids(1, Platform::BgmTv).await.unwrap(),
+            vec!["100"]
+        );
+        let (_, mappings) = db.get_anime(1).await.unwrap();
+        assert_eq!(mappings[0].platform_id.as_deref(), Some("200"));
+        assert_eq!(mappings[0].review_status, ReviewStatus::Accepted);
+    }
+
+    #[tokio::test]
+    async fn test_job_persistence() {
+        use crate::models::enums::{JobItemOutcome, JobStatus};
+        use crate::models::jobs::Model as Job;
+
+        let db = DB::new_for_test().await.unwrap();
+        let animes = (1..=3).map(Anime::test).collect();
+        db.batch_add_animes((animes, vec![])).await.unwrap();
+
+        let job_id = db
+            .create_job(
+                Job {
+                    num_animes_to_match: 3,
+                    ..Job::test(Platform::BgmTv)
+                },
+                &[3, 1, 2],
+            )
+            .await
+            .unwrap();
+
+        let mut job = db.get_jobs().await.unwrap().remove(0);
+        assert_eq!(job.id, job_id);
+        job.status = JobStatus::Running;
+        job.num_processed = 1;
+        db.update_job(job).await.unwrap();
+
+        let (mut item, _) = db.get_job_queue(job_id).await.unwrap().remove(0);
+        item.outcome = Some(JobItemOutcome::Matched);
+        item.platform_id = Some("100".to_string());
+        db.save_job_item(item).await.unwrap();
+
+        // 服务重启时运行中的任务被暂停，队列保持原有顺序和进度
+        assert_eq!(db.pause_interrupted_jobs().await.unwrap(), vec![job_id]);
+        assert!(db.pause_interrupted_jobs().await.unwrap().is_empty());
+        let job = db.get_jobs().await.unwrap().remove(0);
+        assert_eq!(job.status, JobStatus::Paused);
+        assert_eq!(job.num_processed, 1);
+
+        let queue = db.get_job_queue(job_id).await.unwrap();
+        let ids: Vec<i32> = queue.iter().map(|(item, _)| item.anilist_id).collect();
+        assert_eq!(ids, vec![3, 1, 2]);
+        assert_eq!(queue[0].0.outcome, Some(JobItemOutcome::Matched));
+        assert_eq!(queue[0].0.platform_id.as_deref(), Some("100"));
+        assert!(queue[1..].iter().all(|(item, _)| item.outcome.is_none()));
+
+        assert!(db.delete_job(job_id).await.unwrap());
+        assert!(db.get_jobs().await.unwrap().is_empty());
+        assert!(!db.delete_job(job_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_select_job_animes() {
+        let db = DB::new_for_test().await.unwrap();
+        let anime = |anilist_id, media_type, year, season: &str| Anime {
+            media_type,
+            year,
+            season: Some(season.to_string()),
+            ..Anime::test(anilist_id)
+        };
+        let mapping = |anilist_id, platform, review_status, score| AnimeMapping {
+            review_status,
+            score,
+            ..AnimeMapping::test(anilist_id, platform)
+        };
+        db.batch_add_animes((
+            vec![
+                anime(1, MediaType::TV, 2023, "WINTER"),
+                anime(2, MediaType::TV, 2024, "SPRING"),
+                anime(3, MediaType::Movie, 2024, "SPRING"),
+                anime(4, MediaType::TV, 2025, "SPRING"),
+                anime(5, MediaType::TV, 2024, "SPRING"),
+            ],
+            vec![
+                mapping(1, Platform::BgmTv, ReviewStatus::UnMatched, 0),
+                mapping(2, Platform::BgmTv, ReviewStatus::Ready, 60),
+                mapping(3, Platform::BgmTv, ReviewStatus::Accepted, 90),
+                mapping(4, Platform::BgmTv, ReviewStatus::UnMatched, 0),
+                // 只有 TMDB 映射，不会被 Bgm 任务选中
+                mapping(5, Platform::Tmdb, ReviewStatus::UnMatched, 0),
+            ],
+        ))
+        .await
+        .unwrap();
+
+        async fn select(db: &DB, filter: JobFilter) -> Vec<i32> {
+            db.select_job_animes(Platform::BgmTv, &filter)
+                .await
+                .unwrap()
+                .iter()
+                .map(|anime| anime.anilist_id)
+                .collect()
+        }
+        let all = JobFilter {
+            review_status: vec![],
+            ..Default::default()
+        };
+
+        assert_eq!(select(&db, JobFilter::default()).await, vec![1, 4]);
+        assert_eq!(select(&db, all.clone()).await, vec![1, 2, 3, 4]);
+        assert_eq!(
+            select(
+                &db,
+                JobFilter {
+                    year_from: Some(2024),
+                    ..all.clone()
+                }
+            )
+            .await,
+            vec![2, 3, 4]
+        );
+        assert_eq!(
+            select(
+                &db,
+                JobFilter {
+                    year_to: Some(2024),
+                    ..all.clone()
+                }
+            )
+            .await,
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            select(
+                &db,
+                JobFilter {
+                    season: Some("winter".to_string()),
+                    ..all.clone()
+                }
+            )
+            .await,
+            vec![1]
+        );
+        assert_eq!(
+            select(
+                &db,
+                JobFilter {
+                    media_type: Some(MediaType::Movie),
+                    ..all.clone()
+                }
+            )
+            .await,
+            vec![3]
+        );
+        assert_eq!(
+            select(
+                &db,
+                JobFilter {
+                    review_status: vec![ReviewStatus::Ready, ReviewStatus::Accepted],
+                    ..all.clone()
+                }
+            )
+            .await,
+            vec![2, 3]
+        );
+        assert_eq!(
+            select(
+                &db,
+                JobFilter {
+                    score_below: Some(80),
+                    ..all.clone()
+                }
+            )
+            .await,
+            vec![1, 2, 4]
+        );
+        assert_eq!(
+            select(
+                &db,
+                JobFilter {
+                    anilist_ids: vec![3, 4, 5],
+                    ..all.clone()
+                }
+            )
+            .await,
+            vec![3, 4]
+        );
+
+        // 所有条件需同时满足
+        assert_eq!(
+            select(
+                &db,
+                JobFilter {
+                    year_from: Some(2024),
+                    year_to: Some(2024),
+                    season: Some("SPRING".to_string()),
+                    media_type: Some(MediaType::TV),
+                    review_status: vec![ReviewStatus::UnMatched, ReviewStatus::Ready],
+                    score_below: Some(80),
+                    anilist_ids: vec![2, 3, 4, 5],
+                }
+            )
+            .await,
+            vec![2]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_existing_anilist_ids() {
+        let db = DB::new_for_test().await.unwrap();
+        db.batch_add_animes((vec![Anime::test(1), Anime::test(3)], vec![]))
+            .await
+            .unwrap();
+
+        let existing = db.get_existing_anilist_ids(&[1, 2, 3]).await.unwrap();
+        assert_eq!(existing, HashSet::from([1, 3]));
+        assert!(db.get_existing_anilist_ids(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_schedules() {
+        use chrono::TimeZone;
+
+        let db = DB::new_for_test().await.unwrap();
+        let now = Utc::now();
+        let schedule_id = db
+            .create_schedule(Schedule {
+                id: 0,
+                name: "每日导入".to_string(),
+                cron: "0 3 * * *".to_string(),
+                enabled: true,
+                platforms: r#"["BgmTv"]"#.to_string(),
+                provider: "deepseek".to_string(),
+                model: "deepseek-chat".to_string(),
+                options: "{}".to_string(),
+                export: false,
+                last_run_at: None,
+                next_run_at: None,
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+
+        let mut schedule = db.get_schedule(schedule_id).await.unwrap().unwrap();
+        schedule.name = "每周导入".to_string();
+        db.update_schedule(schedule).await.unwrap();
+
+        // 只更新执行时间，不覆盖其他字段
+        let next_run_at = Utc.with_ymd_and_hms(2025, 4, 1, 3, 0, 0).unwrap();
+        let last_run_at = Utc.with_ymd_and_hms(2025, 3, 31, 3, 0, 0).unwrap();
+        db.set_schedule_next_run(schedule_id, Some(next_run_at))
+            .await
+            .unwrap();
+        db.set_schedule_last_run(schedule_id, last_run_at)
+            .await
+            .unwrap();
+        let schedule = db.get_schedule(schedule_id).await.unwrap().unwrap();
+        assert_eq!(schedule.name, "每周导入");
+        assert_eq!(schedule.next_run_at, Some(next_run_at));
+        assert_eq!(schedule.last_run_at, Some(last_run_at));
+
+        let mut run = db.create_schedule_run(schedule_id).await.unwrap();
+        assert_eq!(run.status, ScheduleRunStatus::Running);
+        run.status = ScheduleRunStatus::Succeeded;
+        run.num_fetched = 2;
+        run.num_added = 1;
+        run.job_ids = "[1]".to_string();
+        db.update_schedule_run(run.clone()).await.unwrap();
+        let interrupted = db.create_schedule_run(schedule_id).await.unwrap();
+
+        // 服务重启时只有未结束的执行被标记为失败
+        assert_eq!(db.fail_interrupted_schedule_runs().await.unwrap(), 1);
+        let runs = db.get_schedule_runs(schedule_id, 10).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].id, interrupted.id);
+        assert_eq!(runs[0].status, ScheduleRunStatus::Failed);
+        assert!(runs[0].error.is_some());
+        assert!(runs[0].finished_at.is_some());
+        assert_eq!(runs[1].id, run.id);
+        assert_eq!(runs[1].status, ScheduleRunStatus::Succeeded);
+        assert_eq!((runs[1].num_fetched, runs[1].num_added), (2, 1));
+        assert_eq!(runs[1].job_ids, "[1]");
+        assert_eq!(db.get_schedule_runs(schedule_id, 1).await.unwrap().len(), 1);
+        assert_eq!(db.fail_interrupted_schedule_runs().await.unwrap(), 0);
+
+        assert!(db.delete_schedule(schedule_id).await.unwrap());
+        assert!(db.get_schedule(schedule_id).await.unwrap().is_none());
+        assert!(
+            db.get_schedule_runs(schedule_id, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
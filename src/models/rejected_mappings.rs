@@ -0,0 +1,36 @@
+use crate::models::enums::Platform;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 审核时被拒绝的平台条目，重新匹配时排除
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "rejected_mappings")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub anilist_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub platform: Platform,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub platform_id: String,
+    pub rejected_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::anime::Entity",
+        from = "Column::AnilistId",
+        to = "super::anime::Column::AnilistId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Anime,
+}
+
+impl Related<super::anime::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Anime.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 外部元数据接口的响应缓存
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "response_cache")]
+pub struct Model {
+    /// 接口名称，如 bgm_tv/search、tmdb/get
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub endpoint: String,
+    /// 规范化后的请求参数
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub params: String,
+    pub body: String,
+    pub expires_at: DateTimeUtc,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
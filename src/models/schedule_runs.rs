@@ -0,0 +1,43 @@
+use crate::models::enums::ScheduleRunStatus;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 定时计划的单次执行记录
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "schedule_runs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub schedule_id: i32,
+    pub status: ScheduleRunStatus,
+    /// 从 AniList 获取到的动画数量
+    pub num_fetched: i32,
+    /// 新导入的动画数量
+    pub num_added: i32,
+    /// JSON 数组，本次创建的匹配任务id
+    pub job_ids: String,
+    pub exported: bool,
+    pub error: Option<String>,
+    pub started_at: DateTimeUtc,
+    pub finished_at: Option<DateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::schedules::Entity",
+        from = "Column::ScheduleId",
+        to = "super::schedules::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Schedule,
+}
+
+impl Related<super::schedules::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Schedule.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,40 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 定时计划，按 cron 表达式导入新番并创建匹配任务
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "schedules")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,
+    pub cron: String,
+    pub enabled: bool,
+    /// JSON 数组，需要创建匹配任务的平台
+    pub platforms: String,
+    pub provider: String,
+    pub model: String,
+    /// JSON 对象，创建任务时的可选参数
+    pub options: String,
+    /// 执行后是否重新生成导出文件
+    pub export: bool,
+    pub last_run_at: Option<DateTimeUtc>,
+    /// 禁用时为空
+    pub next_run_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::schedule_runs::Entity")]
+    ScheduleRuns,
+}
+
+impl Related<super::schedule_runs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ScheduleRuns.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,47 @@
+use crate::models::enums::Platform;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "match_transcripts")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub anilist_id: i32,
+    pub platform: Platform,
+    pub provider: String,
+    pub model: String,
+    pub prompt_version: String,
+    pub prompt: String,
+    /// JSON 数组，每一项为工具调用的名称、参数与结果
+    pub tool_calls: String,
+    pub output: Option<String>,
+    /// JSON 格式的匹配结果
+    pub result: Option<String>,
+    pub error: Option<String>,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cost: Option<f64>,
+    pub duration_ms: i64,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::anime::Entity",
+        from = "Column::AnilistId",
+        to = "super::anime::Column::AnilistId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Anime,
+}
+
+impl Related<super::anime::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Anime.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,22 +2,40 @@ use actix_cors::Cors;
 use actix_web::web;
 use actix_web::{App, HttpServer, middleware::Logger};
 use anyhow::Result;
-use std::sync::Mutex;
 use std::{env, sync::Arc};
+use tokio::sync::Mutex;
 use tracing::info;
 
+use crate::agent::pricing::PriceTable;
+use crate::agent::prompt::PromptRegistry;
+use crate::agent::provider::ProviderRegistry;
 use crate::anilist::AniListClient;
-use crate::api::animes::{manual_mapping, query_animes, summary, year_statistics};
+use crate::api::animes::{
+    anime_episodes, anime_transcripts, manual_mapping, query_animes, summary, sync_anime_episodes,
+    usage_statistics, year_statistics,
+};
+use crate::api::cache::{cache_stats, purge_cache, refresh_cache};
 use crate::api::export::{compact_export_dir, export_animes, import_animes};
-use crate::api::job::{create_job, list_jobs, pause_job, remove_job, resume_job, run_job};
-use crate::api::review::review_anime;
+use crate::api::job::{
+    create_consensus_job, create_job, job_events, list_jobs, list_prompts, pause_job, remove_job,
+    resume_job, run_job, update_max_cost,
+};
+use crate::api::review::{accept_candidate, anime_candidates, review_anime};
+use crate::api::schedule::{
+    create_schedule, list_schedules, remove_schedule, run_schedule, schedule_runs, update_schedule,
+};
+use crate::http::cache::ResponseCache;
 use crate::job::mapping_bgm::MappingBgmJobRunner;
+use crate::job::scheduler::Scheduler;
 use crate::models::db::DB;
 
 #[derive(Clone)]
 pub struct AppState {
     pub anilist: Arc<AniListClient>,
     pub job_runner: Arc<Mutex<MappingBgmJobRunner>>,
+    pub providers: Arc<ProviderRegistry>,
+    pub prompts: Arc<PromptRegistry>,
+    pub scheduler: Scheduler,
     pub db: DB,
 }
 
@@ -45,12 +63,29 @@ impl Server {
     pub async fn serve(self) -> Result<()> {
         info!("启动服务器: {}:{}", self.host, self.port);
         let db = DB::new_from_env().await?;
+        ResponseCache::install_from_env(&db)?;
         let anilist = Arc::new(AniListClient::new());
-        let job_runner = Arc::new(Mutex::new(MappingBgmJobRunner::new().await?));
+        let providers = Arc::new(ProviderRegistry::from_env()?);
+        let prompts = Arc::new(PromptRegistry::from_env()?);
+        let prices = Arc::new(PriceTable::from_env()?);
+        let job_runner = Arc::new(Mutex::new(
+            MappingBgmJobRunner::new(providers.clone(), prompts.clone(), prices.clone()).await?,
+        ));
+        let scheduler = Scheduler::new(
+            db.clone(),
+            anilist.clone(),
+            providers.clone(),
+            job_runner.clone(),
+        )
+        .await?;
+        scheduler.start();
         let state = AppState {
             anilist,
             db,
             job_runner,
+            providers,
+            prompts,
+            scheduler,
         };
 
         // 创建HTTP服务器
@@ -67,18 +102,37 @@ impl Server {
                 .app_data(web::Data::new(state.clone()))
                 .service(query_animes)
                 .service(review_anime)
+                .service(anime_candidates)
+                .service(accept_candidate)
                 .service(create_job)
+                .service(create_consensus_job)
                 .service(run_job)
                 .service(list_jobs)
+                .service(list_prompts)
+                .service(job_events)
                 .service(pause_job)
                 .service(resume_job)
+                .service(update_max_cost)
                 .service(remove_job)
                 .service(export_animes)
                 .service(import_animes)
                 .service(compact_export_dir)
                 .service(summary)
                 .service(year_statistics)
+                .service(usage_statistics)
                 .service(manual_mapping)
+                .service(anime_transcripts)
+                .service(anime_episodes)
+                .service(sync_anime_episodes)
+                .service(cache_stats)
+                .service(purge_cache)
+                .service(refresh_cache)
+                .service(create_schedule)
+                .service(list_schedules)
+                .service(update_schedule)
+                .service(run_schedule)
+                .service(remove_schedule)
+                .service(schedule_runs)
                 .wrap(Logger::default())
                 .wrap(cors)
         })
@@ -23,6 +23,7 @@ import {
   getSummary, 
   getYearStatistics,
   listJobs, 
+  subscribeJobEvents,
   runJob, 
   pauseJob, 
   resumeJob, 
@@ -33,6 +34,8 @@ import {
 } from "@/lib/api/animes"
 import { 
   JobDetails, 
+  JobEvent,
+  JobFilter,
   Platform, 
   Provider, 
   ProviderModelMap, 
@@ -56,13 +59,14 @@ import {
 // 在组件内，statsData下方添加一个常量
 export default function Home() {
   const [jobs, setJobs] = useState<JobDetails[]>([])
+  const [jobEvents, setJobEvents] = useState<JobEvent[]>([])
   const [summary, setSummary] = useState<Summary | null>(null)
   const [yearStats, setYearStats] = useState<YearStatistic[]>([])
   const [isLoading, setIsLoading] = useState(false)
   const [isSummaryLoading, setIsSummaryLoading] = useState(false)
   const [isYearStatsLoading, setIsYearStatsLoading] = useState(false)
   const [isCreateDialogOpen, setIsCreateDialogOpen] = useState(false)
-  const [jobToRemove, setJobToRemove] = useState<{platform: Platform, year: number} | null>(null)
+  const [jobToRemove, setJobToRemove] = useState<number | null>(null)
   const [jobForm, setJobForm] = useState({
     platform: Platform.BgmTv,
     year: new Date().getFullYear(),
@@ -126,7 +130,7 @@ export default function Home() {
     try {
       await createJob(
         jobForm.platform, 
-        jobForm.year, 
+        { year_from: jobForm.year, year_to: jobForm.year }, 
         jobForm.provider, 
         ProviderModelMap[jobForm.provider]
       )
@@ -138,9 +142,9 @@ export default function Home() {
   }
 
   // 运行任务
-  const handleRunJob = async (platform: Platform, year: number) => {
+  const handleRunJob = async (id: number) => {
     try {
-      await runJob(platform, year)
+      await runJob(id)
       fetchJobs()
     } catch (error) {
       console.error("Failed to run job:", error)
@@ -148,9 +152,9 @@ export default function Home() {
   }
 
   // 暂停任务
-  const handlePauseJob = async (platform: Platform, year: number) => {
+  const handlePauseJob = async (id: number) => {
     try {
-      await pauseJob(platform, year)
+      await pauseJob(id)
       fetchJobs()
     } catch (error) {
       console.error("Failed to pause job:", error)
@@ -158,9 +162,9 @@ export default function Home() {
   }
 
   // 恢复任务
-  const handleResumeJob = async (platform: Platform, year: number) => {
+  const handleResumeJob = async (id: number) => {
     try {
-      await resumeJob(platform, year)
+      await resumeJob(id)
       fetchJobs()
     } catch (error) {
       console.error("Failed to resume job:", error)
@@ -169,10 +173,10 @@ export default function Home() {
 
   // 移除任务
   const handleRemoveJob = async () => {
-    if (!jobToRemove) return;
+    if (jobToRemove === null) return;
     
     try {
-      await removeJob(jobToRemove.platform, jobToRemove.year)
+      await removeJob(jobToRemove)
       setJobToRemove(null)
       fetchJobs()
     } catch (error) {
@@ -190,6 +194,7 @@ export default function Home() {
       case JobStatus.Completed:
         return 'bg-green-900/30 text-green-300';
       case JobStatus.Failed:
+      case JobStatus.CostCapReached:
         return 'bg-red-900/30 text-red-300';
       default:
         return 'bg-gray-900/30 text-gray-300';
@@ -209,6 +214,8 @@ export default function Home() {
         return '已完成';
       case JobStatus.Failed:
         return '失败';
+      case JobStatus.CostCapReached:
+        return '已达费用上限';
       default:
         return '未知';
     }
@@ -273,10 +280,53 @@ export default function Home() {
       fetchJobs()
     }, 3000)
     
-    // 组件卸载时清除定时器
-    return () => clearInterval(intervalId)
+    // 收到任务事件时刷新任务列表，并保留最近的事件用于展示
+    const unsubscribe = subscribeJobEvents((event) => {
+      setJobEvents((events) => [event, ...events].slice(0, 50))
+      fetchJobs()
+    })
+    
+    // 组件卸载时清除定时器和连接
+    return () => {
+      clearInterval(intervalId)
+      unsubscribe()
+    }
   }, [])
 
+  // 任务选择条件的简要描述
+  const formatJobFilter = (filter: JobFilter) => {
+    const parts: string[] = []
+    if (filter.year_from != null || filter.year_to != null) {
+      parts.push(filter.year_from === filter.year_to
+        ? `${filter.year_from}`
+        : `${filter.year_from ?? ''}-${filter.year_to ?? ''}`)
+    }
+    if (filter.season) parts.push(filter.season)
+    if (filter.media_type) parts.push(filter.media_type)
+    if (filter.review_status?.length) parts.push(filter.review_status.join('/'))
+    if (filter.score_below != null) parts.push(`<${filter.score_below}分`)
+    if (filter.anilist_ids?.length) parts.push(`${filter.anilist_ids.length}部`)
+    return parts.join(' ') || '全部'
+  }
+
+  // 获取任务事件文本
+  const getEventText = (event: JobEvent) => {
+    switch (event.type) {
+      case "Started":
+        return '任务开始';
+      case "ItemMatched":
+        return `${event.anilist_id} 匹配成功: ${event.platform_id} (${event.score})`;
+      case "ItemFailed":
+        return `${event.anilist_id} 匹配失败: ${event.error}`;
+      case "Paused":
+        return '任务暂停';
+      case "Completed":
+        return '任务完成';
+      case "Stopped":
+        return `任务停止: ${event.reason}`;
+    }
+  }
+
   // 构建统计卡片数据
   const buildStatsData = () => {
     if (!summary) {
@@ -682,7 +732,7 @@ export default function Home() {
             </div>
             
             {/* 移除任务确认对话框 */}
-            <AlertDialog open={!!jobToRemove} onOpenChange={(open) => !open && setJobToRemove(null)}>
+            <AlertDialog open={jobToRemove !== null} onOpenChange={(open) => !open && setJobToRemove(null)}>
               <AlertDialogContent className="bg-[#111] border-[#333] text-white">
                 <AlertDialogHeader>
                   <AlertDialogTitle>确认移除任务</AlertDialogTitle>
@@ -708,7 +758,7 @@ export default function Home() {
                   <thead>
                     <tr className="border-b border-[#222] bg-[#1a1a1a]">
                       <th className="px-4 py-3 text-left text-xs font-medium text-[#777] uppercase w-[10%]">平台</th>
-                      <th className="px-4 py-3 text-left text-xs font-medium text-[#777] uppercase w-[8%]">年份</th>
+                      <th className="px-4 py-3 text-left text-xs font-medium text-[#777] uppercase w-[8%]">范围</th>
                       <th className="px-4 py-3 text-left text-xs font-medium text-[#777] uppercase w-[12%]">提供商</th>
                       <th className="px-4 py-3 text-left text-xs font-medium text-[#777] uppercase w-[15%]">模型</th>
                       <th className="px-4 py-3 text-left text-xs font-medium text-[#777] uppercase w-[10%]">状态</th>
@@ -729,8 +779,8 @@ export default function Home() {
                     ) : (
                       <AnimatePresence>
                         {jobs.map((job) => {
-                          // 使用平台、年份和提供商组合作为唯一稳定的key
-                          const jobKey = `${job.platform}-${job.year}-${job.provider}`;
+                          // 使用任务id作为唯一稳定的key
+                          const jobKey = `job-${job.id}`;
                           
                           return (
                             <motion.tr 
@@ -747,7 +797,7 @@ export default function Home() {
                                 <motion.div layout>{job.platform}</motion.div>
                               </td>
                               <td className="px-4 py-3 text-sm">
-                                <motion.div layout>{job.year}</motion.div>
+                                <motion.div layout>{formatJobFilter(job.filter)}</motion.div>
                               </td>
                               <td className="px-4 py-3 text-sm">
                                 <motion.div layout>{job.provider}</motion.div>
@@ -782,6 +832,13 @@ export default function Home() {
                                     <span className="text-green-400">成功: {job.num_matched}</span>
                                     <span className="text-red-400">失败: {job.num_failed}</span>
                                   </div>
+                                  <div className="flex justify-between text-xs text-[#777]">
+                                    <span>Token: {job.usage.prompt_tokens + job.usage.completion_tokens}</span>
+                                    <span>
+                                      ${job.cost.toFixed(4)}
+                                      {job.max_cost != null && ` / $${job.max_cost}`}
+                                    </span>
+                                  </div>
                                 </motion.div>
                               </td>
                               <td className="px-4 py-3 text-sm text-[#777]">
@@ -794,7 +851,7 @@ export default function Home() {
                                       size="sm" 
                                       variant="ghost" 
                                       className="h-8 px-2 text-blue-400 hover:text-blue-300 hover:bg-blue-900/20"
-                                      onClick={() => handleRunJob(job.platform, job.year)}
+                                      onClick={() => handleRunJob(job.id)}
                                       title="运行"
                                     >
                                       <Play className="h-4 w-4" />
@@ -806,7 +863,7 @@ export default function Home() {
                                       size="sm" 
                                       variant="ghost" 
                                       className="h-8 px-2 text-yellow-400 hover:text-yellow-300 hover:bg-yellow-900/20"
-                                      onClick={() => handlePauseJob(job.platform, job.year)}
+                                      onClick={() => handlePauseJob(job.id)}
                                       title="暂停"
                                     >
                                       <Pause className="h-4 w-4" />
@@ -818,7 +875,7 @@ export default function Home() {
                                       size="sm" 
                                       variant="ghost" 
                                       className="h-8 px-2 text-green-400 hover:text-green-300 hover:bg-green-900/20"
-                                      onClick={() => handleResumeJob(job.platform, job.year)}
+                                      onClick={() => handleResumeJob(job.id)}
                                       title="恢复"
                                     >
                                       <RotateCcw className="h-4 w-4" />
@@ -830,7 +887,7 @@ export default function Home() {
                                     size="sm" 
                                     variant="ghost" 
                                     className="h-8 px-2 text-red-400 hover:text-red-300 hover:bg-red-900/20"
-                                    onClick={() => setJobToRemove({platform: job.platform, year: job.year})}
+                                    onClick={() => setJobToRemove(job.id)}
                                     title="移除"
                                   >
                                     <Trash2 className="h-4 w-4" />
@@ -846,6 +903,22 @@ export default function Home() {
                 </table>
               </div>
             </div>
+
+            {jobEvents.length > 0 && (
+              <div className="mt-4 bg-[#111] border border-[#222] rounded-lg p-4 max-h-64 overflow-y-auto">
+                <h3 className="text-xs font-medium text-[#777] uppercase mb-2">实时动态</h3>
+                <ul className="space-y-1 text-xs font-mono">
+                  {jobEvents.map((event, index) => (
+                    <li
+                      key={index}
+                      className={event.type === "ItemFailed" || event.type === "Stopped" ? 'text-red-400' : 'text-[#aaa]'}
+                    >
+                      [{event.platform} #{event.job_id}] {getEventText(event)}
+                    </li>
+                  ))}
+                </ul>
+              </div>
+            )}
           </div>
         </div>
       </div>
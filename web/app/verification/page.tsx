@@ -28,6 +28,7 @@ import { getStatusLabel } from "@/lib/utils"
 const filterTabs = [
   { id: "all", label: "所有", status: null },
   { id: "ready", label: "待审核", status: ReviewStatus.Ready },
+  { id: "conflict", label: "有分歧", status: ReviewStatus.Conflict },
   { id: "accepted", label: "已接受", status: ReviewStatus.Accepted },
   { id: "rejected", label: "已拒绝", status: ReviewStatus.Rejected },
   { id: "dropped", label: "已丢弃", status: ReviewStatus.Dropped },
@@ -1,5 +1,5 @@
-import { apiClient } from "./api-client"
-import type { Anime, JobDetails, PaginatedResult, PaginationParams, Platform, Provider, ReviewStatus, Summary, YearStatistics } from "../types"
+import { API_BASE_URL, apiClient } from "./api-client"
+import type { Anime, JobDetails, JobEvent, JobFilter, PaginatedResult, PaginationParams, Platform, Provider, ReviewStatus, ScheduleDetails, ScheduleRequest, ScheduleRunDetails, Summary, YearStatistics } from "../types"
 
 function fetchAnimes(params: PaginationParams): Promise<PaginatedResult<Anime>> {
     return apiClient.post<PaginatedResult<Anime>>("/api/animes/page", params)
@@ -17,30 +17,45 @@ function reviewAnime(anilist_id: number, platform: Platform, status: ReviewStatu
     return apiClient.get<void>(`/api/anime/${anilist_id}/review/${platform}/${status}`)
 }
 
-function createJob(platform: Platform, year: number, provider: Provider, model: string): Promise<void> {
-    return apiClient.get<void>(`/api/job/${platform}/create/${year}/${provider}/${model}`)
+// 创建任务，返回任务id
+function createJob(platform: Platform, filter: JobFilter, provider: Provider, model: string): Promise<number> {
+    return apiClient.post<number>("/api/job/create", { platform, filter, provider, model })
 }
 
-function runJob(platform: Platform, year: number): Promise<void> {
-    return apiClient.get<void>(`/api/job/${platform}/run/${year}`)
+function runJob(id: number): Promise<void> {
+    return apiClient.get<void>(`/api/job/${id}/run`)
 }
 
-function pauseJob(platform: Platform, year: number): Promise<void> {
-    return apiClient.get<void>(`/api/job/${platform}/pause/${year}`)
+function pauseJob(id: number): Promise<void> {
+    return apiClient.get<void>(`/api/job/${id}/pause`)
 }
 
-function resumeJob(platform: Platform, year: number): Promise<void> {
-    return apiClient.get<void>(`/api/job/${platform}/resume/${year}`)
+function resumeJob(id: number): Promise<void> {
+    return apiClient.get<void>(`/api/job/${id}/resume`)
 }
 
-function removeJob(platform: Platform, year: number): Promise<void> {
-    return apiClient.get<void>(`/api/job/${platform}/remove/${year}`)
+function removeJob(id: number): Promise<void> {
+    return apiClient.get<void>(`/api/job/${id}/remove`)
 }
 
 function listJobs(): Promise<JobDetails[]> {
     return apiClient.get<JobDetails[]>('/api/job/list')
 }
 
+// 订阅任务事件，返回关闭连接的函数
+function subscribeJobEvents(onEvent: (event: JobEvent) => void): () => void {
+    const url = `${API_BASE_URL.replace(/^http/, "ws")}/job/events`
+    const socket = new WebSocket(url)
+    socket.onmessage = (message) => {
+        try {
+            onEvent(JSON.parse(message.data) as JobEvent)
+        } catch (error) {
+            console.error("Failed to parse job event:", error)
+        }
+    }
+    return () => socket.close()
+}
+
 // 数据导出/导入相关API
 function exportAnimes(year: number): Promise<void> {
     return apiClient.get<void>(`/api/export/animes/${year}`)
@@ -63,4 +78,30 @@ function manualMapping(anilist_id: number, platform: Platform, platform_id: stri
     })
 }
 
-export { fetchAnimes, getSummary, getYearStatistics, reviewAnime, createJob, runJob, pauseJob, resumeJob, removeJob, listJobs, exportAnimes, importAnimes, compactAnimes, manualMapping }
+// 创建定时计划，返回计划id
+function createSchedule(request: ScheduleRequest): Promise<number> {
+    return apiClient.post<number>("/api/schedule/create", request)
+}
+
+function listSchedules(): Promise<ScheduleDetails[]> {
+    return apiClient.get<ScheduleDetails[]>("/api/schedule/list")
+}
+
+function updateSchedule(id: number, request: ScheduleRequest): Promise<void> {
+    return apiClient.post<void>(`/api/schedule/${id}/update`, request)
+}
+
+// 立即执行一次，计划正在执行时返回 false
+function runSchedule(id: number): Promise<boolean> {
+    return apiClient.get<boolean>(`/api/schedule/${id}/run`)
+}
+
+function removeSchedule(id: number): Promise<void> {
+    return apiClient.get<void>(`/api/schedule/${id}/remove`)
+}
+
+function listScheduleRuns(id: number): Promise<ScheduleRunDetails[]> {
+    return apiClient.get<ScheduleRunDetails[]>(`/api/schedule/${id}/runs`)
+}
+
+export { fetchAnimes, getSummary, getYearStatistics, reviewAnime, createJob, runJob, pauseJob, resumeJob, removeJob, listJobs, subscribeJobEvents, exportAnimes, importAnimes, compactAnimes, manualMapping, createSchedule, listSchedules, updateSchedule, runSchedule, removeSchedule, listScheduleRuns }
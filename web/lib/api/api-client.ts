@@ -3,7 +3,7 @@
  */
 
 // API基础URL - 从环境变量获取
-const API_BASE_URL = process.env.NEXT_PUBLIC_API_BASE_URL || "http://localhost:8080/api"
+export const API_BASE_URL = process.env.NEXT_PUBLIC_API_BASE_URL || "http://localhost:8080/api"
 
 // 请求超时时间（毫秒）
 const REQUEST_TIMEOUT = 10000
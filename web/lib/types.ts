@@ -4,6 +4,7 @@ export enum ReviewStatus {
   Dropped = "Dropped",
   Ready = "Ready",
   UnMatched = "UnMatched",
+  Conflict = "Conflict",
 }
 
 export interface Anime {
@@ -61,20 +62,89 @@ export enum JobStatus {
   Paused = "Paused",
   Completed = "Completed",
   Failed = "Failed",
+  CostCapReached = "CostCapReached",
+}
+
+export interface TokenUsage {
+  prompt_tokens: number
+  completion_tokens: number
+}
+
+export type JobEvent =
+  | { type: "Started"; platform: Platform; job_id: number }
+  | { type: "ItemMatched"; platform: Platform; job_id: number; anilist_id: number; platform_id: string; score: number }
+  | { type: "ItemFailed"; platform: Platform; job_id: number; anilist_id: number; error: string }
+  | { type: "Paused"; platform: Platform; job_id: number }
+  | { type: "Completed"; platform: Platform; job_id: number }
+  | { type: "Stopped"; platform: Platform; job_id: number; reason: string }
+
+// 任务选择动画的条件，未设置的条件不限制
+export interface JobFilter {
+  year_from?: number | null
+  year_to?: number | null
+  season?: string | null
+  media_type?: string | null
+  review_status?: ReviewStatus[]
+  score_below?: number | null
+  anilist_ids?: number[]
 }
 
 export interface JobDetails {
+  id: number
   platform: Platform
-  year: number
+  filter: JobFilter
   provider: Provider
   model: string
   num_animes_to_match: number
   num_processed: number
   num_matched: number
   num_failed: number
+  usage: TokenUsage
+  cost: number
+  max_cost?: number
   job_start_time: string
   status: JobStatus
-  current_index: number
+  concurrency: number
+}
+
+// 定时计划：导入当前和下一季度的新番，并为新增的动画创建匹配任务
+export interface ScheduleDetails {
+  id: number
+  name: string
+  cron: string
+  enabled: boolean
+  platforms: Platform[]
+  provider: Provider
+  model: string
+  export: boolean
+  last_run_at?: string | null
+  next_run_at?: string | null
+  created_at: string
+}
+
+export interface ScheduleRequest {
+  name: string
+  cron: string
+  enabled?: boolean
+  platforms?: Platform[]
+  provider: Provider
+  model?: string
+  export?: boolean
+  max_cost?: number
+  concurrency?: number
+}
+
+export interface ScheduleRunDetails {
+  id: number
+  schedule_id: number
+  status: "Running" | "Succeeded" | "Failed"
+  num_fetched: number
+  num_added: number
+  job_ids: number[]
+  exported: boolean
+  error?: string | null
+  started_at: string
+  finished_at?: string | null
 }
 
 export interface Summary {
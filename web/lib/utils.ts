@@ -24,6 +24,8 @@ export function getStatusLabel(status: ReviewStatus) {
       return { label: "待验证", color: "bg-blue-500" }
     case ReviewStatus.UnMatched:
       return { label: "未匹配", color: "bg-gray-500" }
+    case ReviewStatus.Conflict:
+      return { label: "有分歧", color: "bg-orange-500" }
   }
 }
 